@@ -0,0 +1,56 @@
+use std::time::{ Duration, Instant };
+
+use nanograd::forward::{ gemm::gemm, utils::naive_mul_data };
+// to run this example:
+// cargo run --release --example gemm_benchmark
+
+/// Best time of a few runs, to filter out noise.
+fn time<F: FnMut()>(runs: usize, mut f: F) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn benchmark<T: nanograd::TensorTrait<T>>(name: &str, m: usize, k: usize, n: usize) {
+    let a: Vec<T> = (0..m * k).map(|i| T::from_usize(i % 17).unwrap() / T::from_f32(17.0).unwrap()).collect();
+    let b: Vec<T> = (0..k * n).map(|i| T::from_usize(i % 13).unwrap() / T::from_f32(13.0).unwrap()).collect();
+    let naive = time(3, || {
+        naive_mul_data(&a, (m, k), &b, (k, n));
+    });
+    let blocked = time(3, || {
+        gemm(&a, (m, k), &b, (k, n));
+    });
+    let flops = (2 * m * k * n) as f64;
+    println!(
+        "{:>4} {:>4}x{:<4} * {:>4}x{:<4} naive {:>9.3} ms {:>6.2} GFLOP/s | gemm {:>9.3} ms {:>6.2} GFLOP/s | {:>5.1}x",
+        name,
+        m,
+        k,
+        k,
+        n,
+        naive.as_secs_f64() * 1e3,
+        flops / naive.as_secs_f64() / 1e9,
+        blocked.as_secs_f64() * 1e3,
+        flops / blocked.as_secs_f64() / 1e9,
+        naive.as_secs_f64() / blocked.as_secs_f64()
+    );
+}
+
+fn main() {
+    println!();
+    println!("Running gemm benchmark...");
+    println!();
+    // a batch of MNIST images through the layers of examples/mnist.rs, then square matrices
+    for (m, k, n) in [(64, 784, 128), (64, 128, 10), (256, 256, 256), (512, 512, 512)] {
+        benchmark::<f32>("f32", m, k, n);
+        benchmark::<f64>("f64", m, k, n);
+    }
+    println!();
+    println!("Finished gemm benchmark.");
+    println!();
+}
@@ -0,0 +1,101 @@
+use core::any::Any;
+use alloc::collections::BTreeMap;
+use alloc::boxed::Box;
+
+use crate::{ Tensor, TensorTrait, Element, DataArray };
+use crate::autograd::{ GradientMap, try_accumulate_gradients, try_propagate, assign_leaf_gradients };
+use crate::error::Result;
+use crate::types::dtype::cast_data;
+
+///
+/// The graph a cast tensor was converted from, whatever its element type.
+///
+/// A graph only holds tensors of one element type, so a cast cannot keep its source as a parent.
+/// Instead the cast tensor is a leaf of its own graph that carries its source here, and the
+/// backward pass continues into the source graph when it reaches the leaf.
+pub trait CastSource<U: Element> {
+    /// Accumulate the gradients of the leaves and targets of the source graph, converted to `U`.
+    fn accumulate(&self, grad_output: &[U], gradients: &mut GradientMap<U>, targets: &[i32]) -> Result<()>;
+    /// Store gradients on every node of the source graph, as `Tensor::backward` does.
+    fn propagate(&mut self, grad_output: &[U]) -> Result<()>;
+    /// The unique id of the source tensor.
+    fn source_id(&self) -> i32;
+    fn clone_source(&self) -> Box<dyn CastSource<U>>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+///
+/// The source of a tensor created by `Tensor::cast`.
+pub struct Cast<T: TensorTrait<T>> {
+    pub source: Tensor<T>,
+}
+
+impl<T: TensorTrait<T>, U: TensorTrait<U>> CastSource<U> for Cast<T> {
+    fn accumulate(&self, grad_output: &[U], gradients: &mut GradientMap<U>, targets: &[i32]) -> Result<()> {
+        let mut source_gradients: GradientMap<T> = BTreeMap::new();
+        try_accumulate_gradients(&self.source, cast_data(grad_output), &mut source_gradients, targets)?;
+        for (id, gradient) in source_gradients {
+            let gradient: DataArray<U> = cast_data(&gradient);
+            match gradients.get_mut(&id) {
+                Some(existing) => {
+                    for (acc, g) in existing.iter_mut().zip(gradient.iter()) {
+                        *acc = *acc + *g;
+                    }
+                }
+                None => {
+                    gradients.insert(id, gradient);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn propagate(&mut self, grad_output: &[U]) -> Result<()> {
+        let mut leaf_gradients: GradientMap<T> = BTreeMap::new();
+        try_propagate(&mut self.source, cast_data(grad_output), &mut leaf_gradients)?;
+        assign_leaf_gradients(&mut self.source, &leaf_gradients);
+        Ok(())
+    }
+
+    fn source_id(&self) -> i32 {
+        self.source.unique_id
+    }
+
+    fn clone_source(&self) -> Box<dyn CastSource<U>> {
+        Box::new(Cast { source: self.source.clone() })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+///
+/// The link from a cast tensor to its source graph, stored on the tensor.
+pub struct CastOp<U: Element> {
+    pub source: Box<dyn CastSource<U>>,
+}
+
+impl<U: Element> CastOp<U> {
+    /// Get the source tensor, if it has element type `T`.
+    pub fn source<T: TensorTrait<T>>(&self) -> Option<&Tensor<T>> {
+        self.source
+            .as_any()
+            .downcast_ref::<Cast<T>>()
+            .map(|cast| &cast.source)
+    }
+}
+
+impl<U: Element> Clone for CastOp<U> {
+    fn clone(&self) -> Self {
+        CastOp { source: self.source.clone_source() }
+    }
+}
+
+impl<U: Element> PartialEq for CastOp<U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.source.source_id() == other.source.source_id()
+    }
+}
+
+impl<U: Element> Eq for CastOp<U> {}
@@ -0,0 +1,255 @@
+use alloc::rc::Rc;
+use alloc::{ vec::Vec, vec, format };
+
+use crate::{ Tensor, TensorTrait, Element, Ops, DataArray, Dimensions, NanogradError, types::ops::LoadOps };
+use crate::error::Result;
+
+///
+/// Storage shared between the forward and backward pass of a custom `Function`.
+pub struct Context<T: Element> {
+    saved: Vec<Tensor<T>>,
+}
+
+impl<T: Element> Context<T> {
+    pub(crate) fn new() -> Self {
+        Context { saved: Vec::new() }
+    }
+
+    /// Keep a tensor around for the backward pass.
+    pub fn save_for_backward(&mut self, tensor: &Tensor<T>) {
+        self.saved.push(tensor.clone());
+    }
+
+    /// Get the tensors saved during the forward pass, in the order they were saved.
+    pub fn saved_tensors(&self) -> &[Tensor<T>] {
+        &self.saved
+    }
+}
+
+///
+/// A differentiable operation defined outside of the crate.
+///
+/// `forward` computes the result from the inputs and may stash whatever it needs in the context.
+/// `backward` receives the gradient of the result and returns one gradient per input,
+/// or `None` for inputs that do not need one.
+///
+/// A function takes one or two inputs, since a graph node has at most a left and a right parent.
+/// Pack more operands into one tensor, or split the op into several functions.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, autograd::{ apply, grad, Context, Function } };
+///
+/// // element-wise square, fused into a single node
+/// struct Square;
+///
+/// impl Function<f64> for Square {
+///     fn forward(&self, ctx: &mut Context<f64>, inputs: &[Tensor<f64>]) -> Tensor<f64> {
+///         ctx.save_for_backward(&inputs[0]);
+///         let data: Vec<f64> = inputs[0].data().iter().map(|x| x * x).collect();
+///         Tensor::from_vec(data, inputs[0].dim(), None, None)
+///     }
+///
+///     fn backward(&self, ctx: &Context<f64>, grad_output: &Tensor<f64>) -> Vec<Option<Tensor<f64>>> {
+///         let input = &ctx.saved_tensors()[0];
+///         let data: Vec<f64> = input
+///             .data()
+///             .iter()
+///             .zip(grad_output.data().iter())
+///             .map(|(x, g)| 2.0 * x * g)
+///             .collect();
+///         vec![Some(Tensor::from_vec(data, input.dim(), None, None))]
+///     }
+/// }
+///
+/// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+/// let grads = grad(|x| apply(Square, vec![x[0].clone()]), &[x]);
+///
+/// assert_eq!(grads[0].data(), &vec![2.0, 4.0, 6.0, 8.0].into_boxed_slice());
+/// ```
+pub trait Function<T: Element> {
+    ///
+    /// Compute the result of the op.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Where to save tensors the backward pass needs.
+    /// * `inputs` - The one or two inputs passed to `apply`, in order.
+    fn forward(&self, ctx: &mut Context<T>, inputs: &[Tensor<T>]) -> Tensor<T>;
+
+    ///
+    /// Compute the gradients of the inputs from the gradient of the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context filled by `forward`.
+    /// * `grad_output` - The gradient of the result, with its dimensions.
+    ///
+    /// # Returns
+    ///
+    /// One entry per input, in order, with the dimensions of that input. `None` stands for zeros.
+    fn backward(&self, ctx: &Context<T>, grad_output: &Tensor<T>) -> Vec<Option<Tensor<T>>>;
+}
+
+///
+/// A custom function recorded in the graph together with its context.
+///
+/// Both are shared through `Rc`, so tensors, which may carry a `CustomOp`, are not `Send` and stay
+/// on the thread that built them.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<nanograd::Tensor<f64>>();
+/// ```
+#[derive(Clone)]
+pub struct CustomOp<T: Element> {
+    pub function: Rc<dyn Function<T>>,
+    pub ctx: Rc<Context<T>>,
+}
+
+impl<T: Element> PartialEq for CustomOp<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function) && Rc::ptr_eq(&self.ctx, &other.ctx)
+    }
+}
+
+impl<T: Element> Eq for CustomOp<T> {}
+
+impl<T: TensorTrait<T>> CustomOp<T> {
+    ///
+    /// Run the function's backward pass on raw gradient data.
+    ///
+    /// # Arguments
+    ///
+    /// * `grad_output` - The gradient of the function's result.
+    /// * `dim` - The dimensions of the function's result.
+    /// * `input_dims` - The dimensions of each input, used to fill in gradients the function skipped.
+    ///
+    /// # Panics
+    ///
+    /// * If the function returns the wrong number of gradients, or one with the wrong dimensions.
+    pub fn backward_data(
+        &self,
+        grad_output: &[T],
+        dim: Dimensions,
+        input_dims: &[Dimensions]
+    ) -> Vec<DataArray<T>> {
+        self.try_backward_data(grad_output, dim, input_dims).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    ///
+    /// Like `backward_data`, but fails instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// * `GradientCount` if the function returns a gradient count other than one per input.
+    /// * `ShapeMismatch` if a gradient does not match the dimensions of its input.
+    pub fn try_backward_data(
+        &self,
+        grad_output: &[T],
+        dim: Dimensions,
+        input_dims: &[Dimensions]
+    ) -> Result<Vec<DataArray<T>>> {
+        let grad_tensor = Tensor::new(grad_output.into(), dim, None, None);
+        let grads = self.function.backward(&self.ctx, &grad_tensor);
+        if grads.len() != input_dims.len() {
+            return Err(NanogradError::GradientCount { expected: input_dims.len(), found: grads.len() });
+        }
+        grads
+            .into_iter()
+            .zip(input_dims.iter())
+            .map(|(gradient, input_dim)| {
+                match gradient {
+                    Some(gradient) => {
+                        if gradient.dim() != *input_dim {
+                            return Err(NanogradError::shape_mismatch("custom backward", *input_dim, gradient.dim()));
+                        }
+                        Ok(gradient.data().clone())
+                    }
+                    None => Ok(vec![T::zero(); input_dim.0 * input_dim.1].into_boxed_slice()),
+                }
+            })
+            .collect()
+    }
+}
+
+///
+/// Run a custom function and record it in the graph like a built-in op.
+///
+/// # Arguments
+///
+/// * `function` - The function to apply.
+/// * `inputs` - The inputs of the function, one or two. Graph nodes have at most a left and a right
+///   parent, so functions of more inputs are not supported.
+///
+/// # Returns
+///
+/// The result of `function.forward`, with the inputs as its parents.
+///
+/// # Panics
+///
+/// * If there are no inputs or more than two.
+pub fn apply<T, F>(function: F, inputs: Vec<Tensor<T>>) -> Tensor<T>
+    where T: TensorTrait<T>, F: Function<T> + 'static
+{
+    try_apply(function, inputs).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `apply`, but fails instead of panicking.
+///
+/// # Errors
+///
+/// * `UnsupportedOp` if there are no inputs or more than two.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, NanogradError, autograd::{ try_apply, Context, Function } };
+///
+/// struct Sum3;
+///
+/// impl Function<f64> for Sum3 {
+///     fn forward(&self, _: &mut Context<f64>, inputs: &[Tensor<f64>]) -> Tensor<f64> {
+///         inputs[0].clone() + inputs[1].clone() + inputs[2].clone()
+///     }
+///
+///     fn backward(&self, _: &Context<f64>, grad_output: &Tensor<f64>) -> Vec<Option<Tensor<f64>>> {
+///         vec![Some(grad_output.clone()); 3]
+///     }
+/// }
+///
+/// let x: Tensor<f64> = Tensor::ones((2, 2), None, None);
+/// let result = try_apply(Sum3, vec![x.clone(), x.clone(), x]);
+/// assert!(matches!(result, Err(NanogradError::UnsupportedOp { .. })));
+/// ```
+pub fn try_apply<T, F>(function: F, inputs: Vec<Tensor<T>>) -> Result<Tensor<T>>
+    where T: TensorTrait<T>, F: Function<T> + 'static
+{
+    if inputs.is_empty() || inputs.len() > 2 {
+        let reason = format!("custom functions take one or two inputs, not {}", inputs.len());
+        return Err(NanogradError::unsupported("apply", &reason));
+    }
+    let mut ctx = Context::new();
+    let result = function.forward(&mut ctx, &inputs);
+    let dim: Dimensions = result.dim();
+    let mut inputs = inputs.into_iter();
+    let left = inputs.next();
+    let right = inputs.next();
+    let mut new_tensor = Tensor::_build_raw(
+        result.data().clone(),
+        dim,
+        None,
+        Some(true),
+        Some(Ops::LoadOps(LoadOps::CUSTOM)),
+        left,
+        right
+    );
+    new_tensor.custom = Some(CustomOp {
+        function: Rc::new(function),
+        ctx: Rc::new(ctx),
+    });
+    new_tensor.set_gradient(Tensor::zeros(dim, None, None));
+    Ok(new_tensor)
+}
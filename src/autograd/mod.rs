@@ -0,0 +1,647 @@
+use alloc::collections::BTreeMap;
+use alloc::{ vec::Vec, vec, format };
+
+use crate::{ Tensor, TensorTrait, DataArray, Dimensions, Dual, NanogradError };
+use crate::backward::functional::try_vjp_by_operation;
+use crate::error::Result;
+
+pub mod function;
+pub use crate::autograd::function::{ Function, Context, apply, try_apply };
+
+pub mod cast;
+
+/// Gradients accumulated for each leaf of a graph, keyed by the leaf's unique id.
+pub type GradientMap<T> = BTreeMap<i32, DataArray<T>>;
+
+///
+/// Walk the graph below `node` and accumulate the gradient of every leaf, and of every node in
+/// `targets`. The walk stops at targets, so a target computed from other tensors gets the gradient
+/// of its own value. Nothing stored on the graph is modified.
+///
+/// # Arguments
+///
+/// * `node` - The tensor to start from.
+/// * `grad_output` - The gradient flowing into `node`.
+/// * `gradients` - Where gradients are accumulated, keyed by unique id.
+/// * `targets` - Unique ids of the nodes to stop at, e.g. the inputs of a function.
+///
+/// # Panics
+///
+/// * If an op of the graph has no backward rule.
+pub fn accumulate_gradients<T: TensorTrait<T>>(
+    node: &Tensor<T>,
+    grad_output: DataArray<T>,
+    gradients: &mut GradientMap<T>,
+    targets: &[i32]
+) {
+    try_accumulate_gradients(node, grad_output, gradients, targets).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `accumulate_gradients`, but returns an `UnsupportedOp` error for ops without a backward
+/// rule. Gradients accumulated before the failure stay in `gradients`.
+pub fn try_accumulate_gradients<T: TensorTrait<T>>(
+    node: &Tensor<T>,
+    grad_output: DataArray<T>,
+    gradients: &mut GradientMap<T>,
+    targets: &[i32]
+) -> Result<()> {
+    let is_leaf = node.left.is_none() && node.right.is_none() && node.cast.is_none();
+    if is_leaf || targets.contains(&node.unique_id) {
+        match gradients.get_mut(&node.unique_id) {
+            Some(existing) => {
+                for (acc, g) in existing.iter_mut().zip(grad_output.iter()) {
+                    *acc = *acc + *g;
+                }
+            }
+            None => {
+                gradients.insert(node.unique_id, grad_output);
+            }
+        }
+        return Ok(());
+    }
+    if let Some(cast) = &node.cast {
+        return cast.source.accumulate(&grad_output, gradients, targets);
+    }
+    let (grad_left, grad_right) = try_vjp_by_operation(node, &grad_output)?;
+    if let (Some(left), Some(grad_left)) = (&node.left, grad_left) {
+        try_accumulate_gradients(left, grad_left, gradients, targets)?;
+    }
+    if let (Some(right), Some(grad_right)) = (&node.right, grad_right) {
+        try_accumulate_gradients(right, grad_right, gradients, targets)?;
+    }
+    Ok(())
+}
+
+///
+/// Compute gradients for several roots at once and store them on their graphs.
+/// Gradients of leaves shared between roots (same unique id) are summed before being stored.
+///
+/// # Arguments
+///
+/// * `roots` - The tensors to start the backward pass from, e.g. several losses.
+/// * `grad_outputs` - One gradient per root. Pass an empty slice to seed every root with ones.
+/// * `retain_graph` - Whether to keep intermediate buffers for another pass. Defaults to true.
+///
+/// # Panics
+///
+/// * If the number of gradients does not match the number of roots.
+/// * If a gradient does not match the dimensions of its root.
+/// * If the graph was already freed by a pass with `retain_graph` set to false.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, autograd::backward, nn::transformation::sum };
+///
+/// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, Some(true));
+/// let mut losses = vec![sum(x.clone()), sum(x.clone() + x.clone())];
+///
+/// backward(&mut losses, &[], None);
+///
+/// // x receives 1 from the first loss and 2 from the second
+/// let leaf = losses[0].left.as_ref().unwrap();
+/// assert_eq!(leaf.get_gradient().unwrap().data(), &vec![3.0, 3.0, 3.0, 3.0].into_boxed_slice());
+/// ```
+pub fn backward<T: TensorTrait<T>>(
+    roots: &mut [Tensor<T>],
+    grad_outputs: &[Tensor<T>],
+    retain_graph: Option<bool>
+) {
+    try_backward(roots, grad_outputs, retain_graph).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `backward`, but fails instead of panicking. Gradients stored before the failure stay on
+/// the graph.
+///
+/// # Errors
+///
+/// * `GradientCount` if the number of gradients does not match the number of roots.
+/// * `ShapeMismatch` if a gradient does not match the dimensions of its root.
+/// * `GraphFreed` if the graph was already freed by a pass with `retain_graph` set to false.
+/// * `UnsupportedOp` if an op of the graph has no backward rule.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, NanogradError, autograd::try_backward, nn::transformation::sum };
+///
+/// let x: Tensor<f64> = Tensor::ones((2, 2), None, Some(true));
+/// let mut losses = vec![sum(x.clone()), sum(x.clone() + x)];
+///
+/// let seeds = [Tensor::ones((1, 1), None, None)];
+/// assert!(matches!(try_backward(&mut losses, &seeds, None), Err(NanogradError::GradientCount { expected: 2, found: 1 })));
+/// ```
+pub fn try_backward<T: TensorTrait<T>>(
+    roots: &mut [Tensor<T>],
+    grad_outputs: &[Tensor<T>],
+    retain_graph: Option<bool>
+) -> Result<()> {
+    if !grad_outputs.is_empty() && grad_outputs.len() != roots.len() {
+        return Err(NanogradError::GradientCount { expected: roots.len(), found: grad_outputs.len() });
+    }
+    let retain_graph = retain_graph.unwrap_or(true);
+    let mut leaf_gradients: GradientMap<T> = BTreeMap::new();
+    for (i, root) in roots.iter_mut().enumerate() {
+        let dim: Dimensions = root.dim();
+        let seed: DataArray<T> = match grad_outputs.get(i) {
+            Some(grad_output) => {
+                if grad_output.dim() != dim {
+                    return Err(NanogradError::shape_mismatch("backward", dim, grad_output.dim()));
+                }
+                grad_output.data().clone()
+            }
+            None => vec![T::one(); dim.0 * dim.1].into_boxed_slice(),
+        };
+        try_propagate(root, seed, &mut leaf_gradients)?;
+    }
+    for root in roots.iter_mut() {
+        assign_leaf_gradients(root, &leaf_gradients);
+        if !retain_graph {
+            free_intermediates(root, true);
+        }
+    }
+    Ok(())
+}
+
+/// Store the gradient flowing into every node and collect leaf gradients by unique id.
+pub(crate) fn try_propagate<T: TensorTrait<T>>(
+    node: &mut Tensor<T>,
+    grad_output: DataArray<T>,
+    leaf_gradients: &mut GradientMap<T>
+) -> Result<()> {
+    let dim: Dimensions = node.dim();
+    if let Some(cast) = node.cast.as_mut() {
+        cast.source.propagate(&grad_output)?;
+        node.set_gradient(Tensor::new(grad_output, dim, None, None));
+        return Ok(());
+    }
+    if node.left.is_none() && node.right.is_none() {
+        match leaf_gradients.get_mut(&node.unique_id) {
+            Some(existing) => {
+                for (acc, g) in existing.iter_mut().zip(grad_output.iter()) {
+                    *acc = *acc + *g;
+                }
+            }
+            None => {
+                leaf_gradients.insert(node.unique_id, grad_output);
+            }
+        }
+        return Ok(());
+    }
+    if !node.lazy_data.is_realized() {
+        // the graph was freed by a previous backward pass; retain_graph keeps it
+        return Err(NanogradError::GraphFreed);
+    }
+    let (grad_left, grad_right) = try_vjp_by_operation(node, &grad_output)?;
+    node.set_gradient(Tensor::new(grad_output, dim, None, None));
+    if let (Some(left), Some(grad_left)) = (node.left.as_mut(), grad_left) {
+        try_propagate(left, grad_left, leaf_gradients)?;
+    }
+    if let (Some(right), Some(grad_right)) = (node.right.as_mut(), grad_right) {
+        try_propagate(right, grad_right, leaf_gradients)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn assign_leaf_gradients<T: TensorTrait<T>>(node: &mut Tensor<T>, leaf_gradients: &GradientMap<T>) {
+    if node.left.is_none() && node.right.is_none() {
+        if let Some(gradient) = leaf_gradients.get(&node.unique_id) {
+            let dim: Dimensions = node.dim();
+            node.set_gradient(Tensor::new(gradient.clone(), dim, None, None));
+        }
+        return;
+    }
+    if let Some(left) = node.left.as_mut() {
+        assign_leaf_gradients(left, leaf_gradients);
+    }
+    if let Some(right) = node.right.as_mut() {
+        assign_leaf_gradients(right, leaf_gradients);
+    }
+}
+
+/// Drop the data and gradients of intermediate tensors. The root keeps its data.
+fn free_intermediates<T: TensorTrait<T>>(node: &mut Tensor<T>, is_root: bool) {
+    if node.left.is_none() && node.right.is_none() {
+        return;
+    }
+    if !is_root {
+        node.lazy_data.free();
+        node.gradient = None;
+    }
+    if let Some(left) = node.left.as_mut() {
+        free_intermediates(left, false);
+    }
+    if let Some(right) = node.right.as_mut() {
+        free_intermediates(right, false);
+    }
+}
+
+/// Run `f` on copies of the inputs so the caller's tensors are never touched.
+fn evaluate<T, F>(f: &F, inputs: &[Tensor<T>]) -> Tensor<T>
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<T>>) -> Tensor<T>
+{
+    f(inputs.to_vec())
+}
+
+/// The unique ids of the inputs of a function, where its backward pass stops.
+fn input_ids<T: TensorTrait<T>>(inputs: &[Tensor<T>]) -> Vec<i32> {
+    inputs
+        .iter()
+        .map(|input| input.unique_id)
+        .collect()
+}
+
+/// Gather the gradient of each input from the accumulated gradients.
+/// Inputs that `f` did not use get a gradient of zeros.
+fn gradients_for_inputs<T: TensorTrait<T>>(
+    inputs: &[Tensor<T>],
+    gradients: &GradientMap<T>
+) -> Vec<Tensor<T>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let dim: Dimensions = input.dim();
+            match gradients.get(&input.unique_id) {
+                Some(gradient) => Tensor::new(gradient.clone(), dim, None, None),
+                None => Tensor::zeros(dim, None, None),
+            }
+        })
+        .collect()
+}
+
+fn try_vjp_internal<T, F>(
+    f: &F,
+    inputs: &[Tensor<T>],
+    v: DataArray<T>,
+    v_dim: Dimensions
+) -> Result<(Tensor<T>, Vec<Tensor<T>>)>
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<T>>) -> Tensor<T>
+{
+    let output = evaluate(f, inputs);
+    let dim: Dimensions = output.dim();
+    if v_dim != dim {
+        return Err(NanogradError::shape_mismatch("vjp", dim, v_dim));
+    }
+    let mut gradients: GradientMap<T> = BTreeMap::new();
+    try_accumulate_gradients(&output, v, &mut gradients, &input_ids(inputs))?;
+    let input_gradients = gradients_for_inputs(inputs, &gradients);
+    Ok((output, input_gradients))
+}
+
+///
+/// Compute the gradient of `f` with respect to each of its inputs.
+///
+/// # Arguments
+///
+/// * `f` - The function to differentiate. Receives copies of `inputs` in the same order.
+/// * `inputs` - The points at which to evaluate the gradient.
+///
+/// # Returns
+///
+/// One gradient tensor per input, with the same dimensions as that input.
+/// Non-scalar outputs are seeded with ones, matching `Tensor::backward`.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, autograd::grad, nn::transformation::sum };
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+/// let b: Tensor<f64> = Tensor::from_vec(vec![5.0, 6.0, 7.0, 8.0], (2, 2), None, None);
+///
+/// let grads = grad(|x| sum(x[0].clone() - x[1].clone()), &[a.clone(), b]);
+///
+/// assert_eq!(grads[0].data(), &vec![1.0, 1.0, 1.0, 1.0].into_boxed_slice());
+/// assert_eq!(grads[1].data(), &vec![-1.0, -1.0, -1.0, -1.0].into_boxed_slice());
+/// // the inputs themselves are left untouched
+/// assert!(a.get_gradient().is_none());
+///
+/// // an input computed from other tensors gets the gradient of its own value
+/// let c = a.clone() + a.clone();
+/// let grads = grad(|x| sum(x[0].clone() - x[1].clone()), &[c, a]);
+/// assert_eq!(grads[0].data(), &vec![1.0, 1.0, 1.0, 1.0].into_boxed_slice());
+/// assert_eq!(grads[1].data(), &vec![-1.0, -1.0, -1.0, -1.0].into_boxed_slice());
+/// ```
+pub fn grad<T, F>(f: F, inputs: &[Tensor<T>]) -> Vec<Tensor<T>>
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<T>>) -> Tensor<T>
+{
+    let output = evaluate(&f, inputs);
+    let dim: Dimensions = output.dim();
+    let seed: DataArray<T> = vec![T::one(); dim.0 * dim.1].into_boxed_slice();
+    let mut gradients: GradientMap<T> = BTreeMap::new();
+    accumulate_gradients(&output, seed, &mut gradients, &input_ids(inputs));
+    gradients_for_inputs(inputs, &gradients)
+}
+
+///
+/// Compute the vector-Jacobian product of `f` at `inputs`.
+///
+/// # Arguments
+///
+/// * `f` - The function to differentiate.
+/// * `inputs` - The points at which to evaluate `f`.
+/// * `v` - The cotangent. Must have the same dimensions as the output of `f`.
+///
+/// # Returns
+///
+/// The output of `f` and the product `v^T * J` for each input.
+///
+/// # Panics
+///
+/// * If `v` does not match the dimensions of the output, or an op of `f` has no backward rule.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, autograd::vjp };
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+/// let b: Tensor<f64> = Tensor::from_vec(vec![1.0, 0.0, 0.0, 1.0], (2, 2), None, None);
+/// let v: Tensor<f64> = Tensor::ones((2, 2), None, None);
+///
+/// let (output, grads) = vjp(|x| x[0].clone() * x[1].clone(), &[a, b], &v);
+///
+/// assert_eq!(output.data(), &vec![1.0, 2.0, 3.0, 4.0].into_boxed_slice());
+/// assert_eq!(grads[0].data(), &vec![1.0, 1.0, 1.0, 1.0].into_boxed_slice());
+/// assert_eq!(grads[1].data(), &vec![4.0, 4.0, 6.0, 6.0].into_boxed_slice());
+/// ```
+pub fn vjp<T, F>(f: F, inputs: &[Tensor<T>], v: &Tensor<T>) -> (Tensor<T>, Vec<Tensor<T>>)
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<T>>) -> Tensor<T>
+{
+    try_vjp(f, inputs, v).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `vjp`, but fails instead of panicking.
+///
+/// # Errors
+///
+/// * `ShapeMismatch` if `v` does not match the dimensions of the output of `f`.
+/// * `UnsupportedOp` if an op of `f` has no backward rule.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, NanogradError, autograd::try_vjp };
+///
+/// let a: Tensor<f64> = Tensor::ones((2, 3), None, None);
+/// let v: Tensor<f64> = Tensor::ones((3, 2), None, None);
+///
+/// match try_vjp(|x| x[0].clone() + x[0].clone(), &[a], &v) {
+///     Err(NanogradError::ShapeMismatch { left, right, .. }) => assert_eq!((left, right), ((2, 3), (3, 2))),
+///     _ => panic!("expected a shape mismatch"),
+/// }
+/// ```
+pub fn try_vjp<T, F>(f: F, inputs: &[Tensor<T>], v: &Tensor<T>) -> Result<(Tensor<T>, Vec<Tensor<T>>)>
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<T>>) -> Tensor<T>
+{
+    try_vjp_internal(&f, inputs, v.data().clone(), v.dim())
+}
+
+///
+/// Compute the Jacobian of `f` with respect to each of its inputs.
+/// Uses one reverse pass per element of the output.
+///
+/// # Arguments
+///
+/// * `f` - The function to differentiate.
+/// * `inputs` - The points at which to evaluate the Jacobian.
+///
+/// # Returns
+///
+/// One matrix per input with dimensions `(output elements, input elements)`.
+/// Row `r` holds the derivatives of output element `r`, both flattened row-major.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, autograd::jacobian };
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+///
+/// let jac = jacobian(|x| x[0].clone() + x[0].clone(), &[a]);
+///
+/// assert_eq!(jac[0].dim(), (4, 4));
+/// assert_eq!(jac[0].data()[0], 2.0);
+/// assert_eq!(jac[0].data()[1], 0.0);
+/// ```
+pub fn jacobian<T, F>(f: F, inputs: &[Tensor<T>]) -> Vec<Tensor<T>>
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<T>>) -> Tensor<T>
+{
+    let output_dim: Dimensions = evaluate(&f, inputs).dim();
+    let output_len = output_dim.0 * output_dim.1;
+    let mut rows: Vec<Vec<T>> = inputs
+        .iter()
+        .map(|input| Vec::with_capacity(output_len * input.data().len()))
+        .collect();
+    for r in 0..output_len {
+        let mut seed = vec![T::zero(); output_len];
+        seed[r] = T::one();
+        let (_, grads) = try_vjp_internal(&f, inputs, seed.into_boxed_slice(), output_dim)
+            .unwrap_or_else(|error| panic!("{}", error));
+        for (row, gradient) in rows.iter_mut().zip(grads.iter()) {
+            row.extend_from_slice(gradient.data());
+        }
+    }
+    rows.into_iter()
+        .zip(inputs.iter())
+        .map(|(row, input)| Tensor::from_vec(row, (output_len, input.data().len()), None, None))
+        .collect()
+}
+
+///
+/// Compute the Jacobian-vector product of `f` at `inputs` in a single forward pass, by running `f`
+/// on dual numbers whose dual parts are the tangents. No Jacobian is built.
+///
+/// # Arguments
+///
+/// * `f` - The function to differentiate, written over dual-valued tensors.
+/// * `inputs` - The points at which to evaluate `f`.
+/// * `tangents` - One tangent per input, each with the same dimensions as its input.
+///
+/// # Returns
+///
+/// The output of `f` and the product `J * v`, with the same dimensions as the output.
+///
+/// # Panics
+///
+/// * If there is not one tangent per input, or a tangent does not match its input.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, autograd::jvp, nn::activation::sigmoid };
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+/// let b: Tensor<f64> = Tensor::from_vec(vec![1.0, 0.0, 0.0, 1.0], (2, 2), None, None);
+/// let t_a: Tensor<f64> = Tensor::zeros((2, 2), None, None);
+/// let t_b: Tensor<f64> = Tensor::ones((2, 2), None, None);
+///
+/// let (output, tangent) = jvp(|x| x[0].clone() * x[1].clone(), &[a.clone(), b], &[t_a, t_b]);
+///
+/// assert_eq!(output.data(), &vec![1.0, 2.0, 3.0, 4.0].into_boxed_slice());
+/// assert_eq!(tangent.data(), &vec![3.0, 3.0, 7.0, 7.0].into_boxed_slice());
+///
+/// let (output, tangent) = jvp(|x| sigmoid(x[0].clone()), &[a], &[Tensor::ones((2, 2), None, None)]);
+/// let s = output.data()[0];
+/// assert!((tangent.data()[0] - s * (1.0 - s)).abs() < 1e-6);
+/// ```
+pub fn jvp<T, F>(f: F, inputs: &[Tensor<T>], tangents: &[Tensor<T>]) -> (Tensor<T>, Tensor<T>)
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<Dual<T>>>) -> Tensor<Dual<T>>
+{
+    try_jvp(f, inputs, tangents).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `jvp`, but fails instead of panicking.
+///
+/// # Errors
+///
+/// * `UnsupportedOp` if there is not one tangent per input.
+/// * `ShapeMismatch` if a tangent does not match the dimensions of its input.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, NanogradError, autograd::try_jvp };
+///
+/// let a: Tensor<f64> = Tensor::ones((2, 2), None, None);
+/// let square = |x: Vec<Tensor<_>>| x[0].clone() * x[0].clone();
+///
+/// assert!(matches!(try_jvp(square, &[a.clone()], &[]), Err(NanogradError::UnsupportedOp { .. })));
+/// assert!(matches!(try_jvp(square, &[a], &[Tensor::ones((1, 2), None, None)]), Err(NanogradError::ShapeMismatch { .. })));
+/// ```
+pub fn try_jvp<T, F>(f: F, inputs: &[Tensor<T>], tangents: &[Tensor<T>]) -> Result<(Tensor<T>, Tensor<T>)>
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<Dual<T>>>) -> Tensor<Dual<T>>
+{
+    if inputs.len() != tangents.len() {
+        let reason = format!("{} tangents were given for {} inputs", tangents.len(), inputs.len());
+        return Err(NanogradError::unsupported("jvp", &reason));
+    }
+    let mut dual_inputs: Vec<Tensor<Dual<T>>> = Vec::with_capacity(inputs.len());
+    for (input, tangent) in inputs.iter().zip(tangents.iter()) {
+        if input.dim() != tangent.dim() {
+            return Err(NanogradError::shape_mismatch("jvp", input.dim(), tangent.dim()));
+        }
+        dual_inputs.push(to_dual(input, tangent.data()));
+    }
+    let output = f(dual_inputs);
+    let dim: Dimensions = output.dim();
+    let values: Vec<T> = output
+        .data()
+        .iter()
+        .map(|x| x.real())
+        .collect();
+    let derivatives: Vec<T> = output
+        .data()
+        .iter()
+        .map(|x| x.dual())
+        .collect();
+    Ok((Tensor::from_vec(values, dim, None, None), Tensor::from_vec(derivatives, dim, None, None)))
+}
+
+///
+/// Compute the Hessian of a scalar-valued `f` with respect to its inputs.
+/// Each column is the derivative of the gradient along one input element, taken exactly by
+/// running the backward pass on dual numbers (forward-over-reverse). This costs one forward and one
+/// backward pass per input element.
+///
+/// # Arguments
+///
+/// * `f` - The function to differentiate, written over dual-valued tensors. Should return a 1x1 tensor.
+/// * `inputs` - The points at which to evaluate the Hessian.
+///
+/// # Returns
+///
+/// Blocks `h[i][j]` with dimensions `(elements of input i, elements of input j)`.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, autograd::hessian, nn::transformation::{ exp2, sum } };
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+///
+/// // sum(A * A) has a constant Hessian
+/// let h = hessian(|x| sum(x[0].clone() * x[0].clone()), &[a.clone()]);
+/// assert_eq!(h[0][0].dim(), (4, 4));
+/// assert_eq!(h[0][0].data().as_ref(), &[
+///     2.0, 1.0, 1.0, 0.0,
+///     1.0, 0.0, 2.0, 1.0,
+///     1.0, 2.0, 0.0, 1.0,
+///     0.0, 1.0, 1.0, 2.0,
+/// ]);
+///
+/// // the second derivative of 2^x is 2^x ln(2)^2, on the diagonal only
+/// let h = hessian(|x| sum(exp2(x[0].clone())), &[a.clone()]);
+/// for (i, x) in a.data().iter().enumerate() {
+///     let expected = x.exp2() * std::f64::consts::LN_2.powi(2);
+///     assert!((h[0][0].data()[i * 4 + i] - expected).abs() < 1e-12);
+///     assert_eq!(h[0][0].data()[i * 4 + (i + 1) % 4], 0.0);
+/// }
+/// ```
+pub fn hessian<T, F>(f: F, inputs: &[Tensor<T>]) -> Vec<Vec<Tensor<T>>>
+    where T: TensorTrait<T>, F: Fn(Vec<Tensor<Dual<T>>>) -> Tensor<Dual<T>>
+{
+    // columns[i][j] holds the flattened columns of block (i, j)
+    let mut columns: Vec<Vec<Vec<Vec<T>>>> = vec![vec![Vec::new(); inputs.len()]; inputs.len()];
+    for j in 0..inputs.len() {
+        for c in 0..inputs[j].data().len() {
+            // seed the tangent of a single input element
+            let dual_inputs: Vec<Tensor<Dual<T>>> = inputs
+                .iter()
+                .enumerate()
+                .map(|(k, input)| {
+                    let mut tangent = vec![T::zero(); input.data().len()];
+                    if k == j {
+                        tangent[c] = T::one();
+                    }
+                    to_dual(input, &tangent)
+                })
+                .collect();
+            let grads = grad(&f, &dual_inputs);
+            for i in 0..inputs.len() {
+                let column: Vec<T> = grads[i]
+                    .data()
+                    .iter()
+                    .map(|g| g.dual())
+                    .collect();
+                columns[i][j].push(column);
+            }
+        }
+    }
+    columns
+        .into_iter()
+        .enumerate()
+        .map(|(i, blocks)| {
+            blocks
+                .into_iter()
+                .enumerate()
+                .map(|(j, block_columns)| {
+                    let n_rows = inputs[i].data().len();
+                    let n_cols = inputs[j].data().len();
+                    let mut block = vec![T::zero(); n_rows * n_cols];
+                    for (c, column) in block_columns.iter().enumerate() {
+                        for (r, value) in column.iter().enumerate() {
+                            block[r * n_cols + c] = *value;
+                        }
+                    }
+                    Tensor::from_vec(block, (n_rows, n_cols), None, None)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A dual-valued copy of a tensor, with `tangent` as the dual part.
+fn to_dual<T: TensorTrait<T>>(input: &Tensor<T>, tangent: &[T]) -> Tensor<Dual<T>> {
+    let data: Vec<Dual<T>> = input
+        .data()
+        .iter()
+        .zip(tangent.iter())
+        .map(|(real, dual)| Dual::new(*real, *dual))
+        .collect();
+    Tensor::from_vec(data, input.dim(), None, None)
+}
@@ -0,0 +1,692 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::{ c_char, c_int, c_void, CStr, CString };
+use std::fs::{ self, DirBuilder };
+use std::hash::{ Hash, Hasher };
+use std::io;
+use std::os::unix::fs::{ DirBuilderExt, MetadataExt, PermissionsExt };
+use std::path::{ Path, PathBuf };
+use std::process::Command;
+use std::sync::OnceLock;
+
+use crate::{ Tensor, TensorTrait, Device, DataArray, Dimensions, LazyBuffer, Ops, NanogradError };
+use crate::error::Result;
+use crate::backend::{ Backend, Storage, cpu::CpuBackend };
+use crate::types::ops::{ UnaryOps, BinaryOps, ReduceOps, MovementOps };
+
+/// Resolve all symbols when the shared object is loaded.
+const RTLD_NOW: c_int = 2;
+
+extern "C" {
+    fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlerror() -> *mut c_char;
+    fn getuid() -> u32;
+}
+
+/// Flags every kernel is compiled with, besides the output and source paths.
+const CFLAGS: [&str; 3] = ["-O2", "-shared", "-fPIC"];
+
+/// Every compiled kernel has this signature: one output and a list of inputs.
+type KernelFn = unsafe extern "C" fn(*mut c_void, *const *const c_void);
+
+thread_local! {
+    static KERNELS: RefCell<HashMap<u64, KernelFn>> = RefCell::new(HashMap::new());
+}
+
+///
+/// A fused element-wise expression, rendered as a single C expression.
+/// Scalars are stored as the bits of an `f64` so expressions can be hashed.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Expr {
+    /// The element of the n-th input at the current index.
+    Input(usize),
+    Const(u64),
+    Unary(UnaryOps, Box<Expr>),
+    /// `MAX` against a scalar, as used by relu.
+    Max(Box<Expr>, u64),
+    Binary(BinaryOps, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn render(&self) -> String {
+        match self {
+            Expr::Input(i) => format!("in{}[i]", i),
+            Expr::Const(bits) => c_literal(f64::from_bits(*bits)),
+            Expr::Unary(op, x) => {
+                let x = x.render();
+                match op {
+                    UnaryOps::EXP2 => format!("exp2({})", x),
+                    UnaryOps::LOG2 => format!("log2({})", x),
+                    UnaryOps::Sigmoid => format!("(1.0 / (1.0 + exp(-({}))))", x),
+                    _ => panic!("{:?} is not an element-wise op", op),
+                }
+            }
+            Expr::Max(x, bits) => {
+                let x = x.render();
+                format!("({x} > {c} ? {x} : {c})", x = x, c = c_literal(f64::from_bits(*bits)))
+            }
+            Expr::Binary(op, a, b) => {
+                let symbol = match op {
+                    BinaryOps::ADD => "+",
+                    BinaryOps::SUB => "-",
+                    BinaryOps::MUL => "*",
+                };
+                format!("({} {} {})", a.render(), symbol, b.render())
+            }
+        }
+    }
+
+    fn inputs(&self) -> usize {
+        match self {
+            Expr::Input(i) => i + 1,
+            Expr::Const(_) => 0,
+            Expr::Unary(_, x) | Expr::Max(x, _) => x.inputs(),
+            Expr::Binary(_, a, b) => a.inputs().max(b.inputs()),
+        }
+    }
+}
+
+/// A C literal for a scalar. Infinities and NaN have no literal and use the macros of `<math.h>`.
+fn c_literal(value: f64) -> String {
+    if value.is_nan() {
+        "NAN".to_string()
+    } else if value.is_infinite() {
+        (if value > 0.0 { "INFINITY" } else { "(-INFINITY)" }).to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+///
+/// The structure of a kernel. Two kernels with the same structure share compiled code,
+/// no matter what data they run on.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Kernel {
+    Elementwise { expr: Expr, len: usize },
+    Reduce { op: ReduceOps, len: usize },
+    Softmax { rows: usize, cols: usize },
+    Matmul { m: usize, k: usize, n: usize },
+    Permute { rows: usize, cols: usize },
+}
+
+impl Kernel {
+    ///
+    /// Render the kernel as a C translation unit exporting `void kernel(T *out, const T **inputs)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `c_type` - The C name of the element type, e.g. `float` or `double`.
+    pub fn render(&self, c_type: &str) -> String {
+        let inputs = self.input_dims().len();
+        let mut source = format!(
+            "#include <math.h>\ntypedef {} T;\nvoid kernel(T *restrict out, const T *const *inputs) {{\n",
+            c_type
+        );
+        for i in 0..inputs {
+            source += &format!("    const T *restrict in{} = inputs[{}];\n", i, i);
+        }
+        source += &(match self {
+            Kernel::Elementwise { expr, len } =>
+                format!("    for (long i = 0; i < {}; i++) {{\n        out[i] = {};\n    }}\n", len, expr.render()),
+            Kernel::Reduce { op: ReduceOps::SUM, len } =>
+                format!(
+                    "    T acc = 0;\n    for (long i = 0; i < {}; i++) {{\n        acc += in0[i];\n    }}\n    out[0] = acc;\n",
+                    len
+                ),
+            Kernel::Reduce { op: ReduceOps::MAX, len } =>
+                format!(
+                    "    T acc = -INFINITY;\n    for (long i = 0; i < {}; i++) {{\n        acc = in0[i] > acc ? in0[i] : acc;\n    }}\n    out[0] = acc;\n",
+                    len
+                ),
+            Kernel::Softmax { rows, cols } =>
+                format!(
+                    "    for (long r = 0; r < {rows}; r++) {{\n        T sum = 0;\n        for (long c = 0; c < {cols}; c++) {{\n            out[r * {cols} + c] = exp(in0[r * {cols} + c]);\n            sum += out[r * {cols} + c];\n        }}\n        for (long c = 0; c < {cols}; c++) {{\n            out[r * {cols} + c] /= sum;\n        }}\n    }}\n",
+                    rows = rows,
+                    cols = cols
+                ),
+            Kernel::Matmul { m, k, n } =>
+                format!(
+                    "    for (long i = 0; i < {m}; i++) {{\n        for (long j = 0; j < {n}; j++) {{\n            T acc = 0;\n            for (long p = 0; p < {k}; p++) {{\n                acc += in0[i * {k} + p] * in1[p * {n} + j];\n            }}\n            out[i * {n} + j] = acc;\n        }}\n    }}\n",
+                    m = m,
+                    k = k,
+                    n = n
+                ),
+            Kernel::Permute { rows, cols } =>
+                format!(
+                    "    for (long c = 0; c < {cols}; c++) {{\n        for (long r = 0; r < {rows}; r++) {{\n            out[c * {rows} + r] = in0[r * {cols} + c];\n        }}\n    }}\n",
+                    rows = rows,
+                    cols = cols
+                ),
+        });
+        source += "}\n";
+        source
+    }
+
+    /// The dimensions of every input the kernel reads, in order.
+    fn input_dims(&self) -> Vec<Dimensions> {
+        match self {
+            Kernel::Elementwise { expr, len } => vec![(1, *len); expr.inputs()],
+            Kernel::Reduce { len, .. } => vec![(1, *len)],
+            Kernel::Softmax { rows, cols } | Kernel::Permute { rows, cols } => vec![(*rows, *cols)],
+            Kernel::Matmul { m, k, n } => vec![(*m, *k), (*k, *n)],
+        }
+    }
+
+    /// Number of elements the kernel writes.
+    fn output_len(&self) -> usize {
+        match self {
+            Kernel::Elementwise { len, .. } => *len,
+            Kernel::Reduce { .. } => 1,
+            Kernel::Softmax { rows, cols } | Kernel::Permute { rows, cols } => rows * cols,
+            Kernel::Matmul { m, n, .. } => m * n,
+        }
+    }
+}
+
+/// The C name of an element type, if the type can be compiled.
+fn c_type<T: TensorTrait<T>>() -> Option<&'static str> {
+    if TypeId::of::<T>() == TypeId::of::<f32>() {
+        Some("float")
+    } else if TypeId::of::<T>() == TypeId::of::<f64>() {
+        Some("double")
+    } else {
+        None
+    }
+}
+
+/// The compiler to build kernels with: `CC`, or `cc` if it is not set.
+fn compiler() -> String {
+    std::env::var("CC").unwrap_or_else(|_| "cc".to_string())
+}
+
+///
+/// The directory compiled kernels are cached in. It is private to the current user: created with
+/// mode `0700`, and used only if it is owned by the user and nobody else can write to it.
+///
+/// The cache lives in `$XDG_CACHE_HOME/nanograd/clang`, or `~/.cache/nanograd/clang`. If neither
+/// is available, or the directory fails the checks, a new private directory in the system temp
+/// directory is used for this process only.
+///
+/// # Examples
+///
+/// ```
+/// use std::os::unix::fs::MetadataExt;
+/// use nanograd::backend::clang::cache_dir;
+///
+/// let metadata = std::fs::symlink_metadata(cache_dir()).unwrap();
+/// assert!(metadata.is_dir());
+/// assert_eq!(metadata.mode() & 0o077, 0);
+/// ```
+pub fn cache_dir() -> &'static Path {
+    static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+    CACHE_DIR.get_or_init(|| {
+        let shared = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .map(|cache| cache.join("nanograd").join("clang"));
+        match shared {
+            Some(dir) if private_dir(&dir, true).is_ok() => dir,
+            _ => process_dir(),
+        }
+    })
+}
+
+/// Create a directory only the current user can use, or check that an existing one is.
+fn private_dir(dir: &Path, recursive: bool) -> io::Result<()> {
+    DirBuilder::new().recursive(recursive).mode(0o700).create(dir)?;
+    let metadata = fs::symlink_metadata(dir)?;
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { getuid() };
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "kernel cache is not private"));
+    }
+    Ok(())
+}
+
+/// A new private directory in the temp directory, used by this process only.
+fn process_dir() -> PathBuf {
+    let temp = std::env::temp_dir();
+    for attempt in 0.. {
+        // creating a directory fails if the name is taken, so nobody else can own the one we get
+        let dir = temp.join(format!("nanograd-clang-{}-{}", std::process::id(), attempt));
+        match private_dir(&dir, false) {
+            Ok(()) => return dir,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => panic!("Failed to create a private kernel cache directory: {}", error),
+        }
+    }
+    unreachable!()
+}
+
+/// Whether a cached library was built by the current user and nobody else can have changed it.
+fn is_trusted(library: &Path) -> bool {
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { getuid() };
+    match fs::symlink_metadata(library) {
+        Ok(metadata) => metadata.is_file() && metadata.uid() == uid && metadata.mode() & 0o022 == 0,
+        Err(_) => false,
+    }
+}
+
+/// A hasher for cache keys, seeded with the compiler and flags so a change of either recompiles.
+fn toolchain_hasher() -> DefaultHasher {
+    let mut hasher = DefaultHasher::new();
+    compiler().hash(&mut hasher);
+    CFLAGS.hash(&mut hasher);
+    hasher
+}
+
+/// Structural hash of a kernel for an element type, used as the key of the in-memory cache.
+fn kernel_hash(kernel: &Kernel, c_type: &str) -> u64 {
+    let mut hasher = toolchain_hasher();
+    kernel.hash(&mut hasher);
+    c_type.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Load the `kernel` symbol from a shared object.
+fn load(path: &Path) -> KernelFn {
+    let path = CString::new(path.to_string_lossy().as_bytes()).unwrap();
+    let symbol = CString::new("kernel").unwrap();
+    unsafe {
+        let handle = dlopen(path.as_ptr(), RTLD_NOW);
+        if handle.is_null() {
+            panic!("Failed to load kernel: {}", CStr::from_ptr(dlerror()).to_string_lossy());
+        }
+        // the library stays loaded for the rest of the process, so the pointer never dangles
+        let function = dlsym(handle, symbol.as_ptr());
+        if function.is_null() {
+            panic!("Kernel has no `kernel` symbol: {}", CStr::from_ptr(dlerror()).to_string_lossy());
+        }
+        std::mem::transmute::<*mut c_void, KernelFn>(function)
+    }
+}
+
+///
+/// Write the source, compile it with the system compiler and load the result. Libraries on disk
+/// are named by the hash of their source, so a change to the generated code is compiled again.
+fn compile(source: &str) -> KernelFn {
+    let mut hasher = toolchain_hasher();
+    source.hash(&mut hasher);
+    let hash = hasher.finish();
+    let dir = cache_dir();
+    let library = dir.join(format!("k_{:016x}.so", hash));
+    if !is_trusted(&library) {
+        // build under a private name and rename, so concurrent processes never load a partial file
+        let stem = format!("k_{:016x}_{}", hash, std::process::id());
+        let source_path = dir.join(format!("{}.c", stem));
+        let temp_library = dir.join(format!("{}.so", stem));
+        fs::write(&source_path, source).expect("Failed to write kernel source");
+        let compiler = compiler();
+        let output = Command::new(&compiler)
+            .args(CFLAGS)
+            .arg("-o")
+            .arg(&temp_library)
+            .arg(&source_path)
+            .arg("-lm")
+            .output()
+            .unwrap_or_else(|error| panic!("Failed to run {}: {}", compiler, error));
+        if !output.status.success() {
+            panic!("Failed to compile kernel:\n{}\n{}", source, String::from_utf8_lossy(&output.stderr));
+        }
+        fs::set_permissions(&temp_library, fs::Permissions::from_mode(0o700))
+            .expect("Failed to restrict the permissions of a compiled kernel");
+        fs::rename(&temp_library, &library).expect("Failed to move compiled kernel into the cache");
+        let _ = fs::remove_file(&source_path);
+    }
+    load(&library)
+}
+
+/// Get the compiled code for a kernel, compiling it the first time its hash is seen.
+fn kernel_for(kernel: &Kernel, c_type: &str, hash: u64) -> KernelFn {
+    if let Some(function) = KERNELS.with(|kernels| kernels.borrow().get(&hash).copied()) {
+        return function;
+    }
+    let function = compile(&kernel.render(c_type));
+    KERNELS.with(|kernels| kernels.borrow_mut().insert(hash, function));
+    function
+}
+
+///
+/// Number of kernels compiled or loaded by this thread.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, Device };
+/// use nanograd::backend::clang::cached_kernels;
+///
+/// let a: Tensor<f32> = Tensor::ones((3, 3), Some(Device::CLANG), None);
+/// let b: Tensor<f32> = Tensor::ones((3, 3), Some(Device::CLANG), None);
+/// let _ = a + b;
+/// let compiled = cached_kernels();
+///
+/// // same structure, different data: the kernel is reused
+/// let c: Tensor<f32> = Tensor::full((3, 3), 2.0, Some(Device::CLANG), None);
+/// let d: Tensor<f32> = Tensor::full((3, 3), 5.0, Some(Device::CLANG), None);
+/// let e = c + d;
+///
+/// assert_eq!(cached_kernels(), compiled);
+/// assert_eq!(e.data(), &vec![7.0; 9].into_boxed_slice());
+/// ```
+pub fn cached_kernels() -> usize {
+    KERNELS.with(|kernels| kernels.borrow().len())
+}
+
+///
+/// Run a kernel on host data.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::backend::clang::{ run, Expr, Kernel };
+/// use nanograd::types::ops::BinaryOps;
+///
+/// // non-finite scalars become the INFINITY and NAN macros of <math.h>
+/// let expr = Expr::Binary(
+///     BinaryOps::ADD,
+///     Box::new(Expr::Max(Box::new(Expr::Input(0)), f64::NEG_INFINITY.to_bits())),
+///     Box::new(Expr::Const(f64::INFINITY.to_bits())),
+/// );
+/// let out = run::<f64>(&Kernel::Elementwise { expr, len: 2 }, &[&[1.0, -2.0]]);
+/// assert_eq!(out.as_ref(), &[f64::INFINITY; 2]);
+///
+/// let expr = Expr::Max(Box::new(Expr::Input(0)), f64::NAN.to_bits());
+/// let out = run::<f32>(&Kernel::Elementwise { expr, len: 1 }, &[&[1.0]]);
+/// assert!(out[0].is_nan());
+/// ```
+///
+/// # Panics
+///
+/// * If `T` has no C equivalent. Only `f32` and `f64` can be compiled.
+/// * If the number of inputs differs from the inputs the kernel reads, or an input is too short.
+pub fn run<T: TensorTrait<T>>(kernel: &Kernel, inputs: &[&[T]]) -> DataArray<T> {
+    try_run(kernel, inputs).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `run`, but returns an error instead of panicking.
+///
+/// # Errors
+///
+/// * `UnsupportedOp` if `T` has no C equivalent, or the number of inputs differs from the inputs
+///   the kernel reads.
+/// * `DataLength` if an input holds fewer elements than the kernel reads from it.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::NanogradError;
+/// use nanograd::backend::clang::{ try_run, Expr, Kernel };
+/// use nanograd::types::ops::ReduceOps;
+///
+/// let kernel = Kernel::Elementwise { expr: Expr::Input(0), len: 4 };
+/// assert!(matches!(try_run::<f64>(&kernel, &[&[1.0]]), Err(NanogradError::DataLength { dim: (1, 4), len: 1 })));
+/// assert!(matches!(try_run::<f64>(&kernel, &[]), Err(NanogradError::UnsupportedOp { .. })));
+///
+/// // the maximum of nothing is -inf, like on the CPU
+/// let out = try_run::<f64>(&Kernel::Reduce { op: ReduceOps::MAX, len: 0 }, &[&[]]).unwrap();
+/// assert_eq!(out.as_ref(), &[f64::NEG_INFINITY]);
+/// ```
+pub fn try_run<T: TensorTrait<T>>(kernel: &Kernel, inputs: &[&[T]]) -> Result<DataArray<T>> {
+    let c_type = try_c_type::<T>()?;
+    try_run_hashed(kernel, inputs, kernel_hash(kernel, c_type))
+}
+
+fn try_c_type<T: TensorTrait<T>>() -> Result<&'static str> {
+    c_type::<T>().ok_or_else(|| NanogradError::unsupported(
+        core::any::type_name::<T>(),
+        "the clang backend only compiles f32 and f64 kernels"
+    ))
+}
+
+/// Run a kernel, checking first that the inputs hold everything it reads.
+fn try_run_hashed<T: TensorTrait<T>>(kernel: &Kernel, inputs: &[&[T]], hash: u64) -> Result<DataArray<T>> {
+    let c_type = try_c_type::<T>()?;
+    let dims = kernel.input_dims();
+    if inputs.len() != dims.len() {
+        return Err(NanogradError::unsupported(
+            "kernel",
+            &format!("it reads {} inputs but {} were given", dims.len(), inputs.len())
+        ));
+    }
+    for (input, dim) in inputs.iter().zip(dims) {
+        if input.len() < dim.0 * dim.1 {
+            return Err(NanogradError::DataLength { dim, len: input.len() });
+        }
+    }
+    let function = kernel_for(kernel, c_type, hash);
+    let mut output = vec![T::zero(); kernel.output_len()];
+    let pointers: Vec<*const c_void> = inputs
+        .iter()
+        .map(|input| input.as_ptr() as *const c_void)
+        .collect();
+    // SAFETY: there is one pointer per input the kernel reads, each to at least as many elements
+    // as it reads, and the output holds every element it writes
+    unsafe {
+        function(output.as_mut_ptr() as *mut c_void, pointers.as_ptr());
+    }
+    Ok(output.into_boxed_slice())
+}
+
+/// Get the host data of a storage on the clang device.
+fn host<T: TensorTrait<T>>(storage: &Storage<T>) -> &DataArray<T> {
+    match storage.as_host() {
+        Some(data) => data,
+        None => panic!("Clang backend received storage from another device"),
+    }
+}
+
+/// Get the host data of a storage, checking that it fills `dim` before it reaches native code.
+fn checked<T: TensorTrait<T>>(storage: &Storage<T>, dim: Dimensions) -> &DataArray<T> {
+    let data = host(storage);
+    if data.len() != dim.0 * dim.1 {
+        panic!("{}", NanogradError::DataLength { dim, len: data.len() });
+    }
+    data
+}
+
+///
+/// A CPU backend that renders each op as C source, compiles it with the system `cc` into a shared
+/// object and loads it with `dlopen`. Compiled kernels are cached in memory and on disk by a
+/// structural hash, so each shape and op is compiled once. Set `CC` to use another compiler.
+///
+/// Ops run as they are built, one kernel per op, so the backend itself does not fuse them. To run
+/// a chain of element-wise ops as a single kernel, call `realize_fused` on the finished tensor.
+/// Operand lengths and dimensions are checked before any data reaches a kernel.
+///
+/// The disk cache is the per-user directory returned by `cache_dir`. Libraries found there are
+/// only loaded if they belong to the current user and nobody else can write to them; anything else
+/// is compiled again.
+///
+/// Buffers live in host memory. Element types other than `f32` and `f64` fall back to the
+/// interpreted `CpuBackend`.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, Device };
+/// use nanograd::backend::{ Backend, Storage, clang::ClangBackend };
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], (2, 3), Some(Device::CLANG), None);
+/// let b: Tensor<f64> = Tensor::ones((3, 1), Some(Device::CLANG), None);
+/// let c = a * b;
+///
+/// assert_eq!(c.data(), &vec![6.0, 15.0].into_boxed_slice());
+///
+/// // storage shorter than its dimensions is rejected before it reaches a kernel
+/// let short: Storage<f64> = Storage::Host(vec![1.0].into_boxed_slice());
+/// let matmul = std::panic::AssertUnwindSafe(|| ClangBackend.matmul(&short, (2, 2), &short, (2, 2)));
+/// assert!(std::panic::catch_unwind(matmul).is_err());
+/// ```
+pub struct ClangBackend;
+
+impl ClangBackend {
+    fn compiles<T: TensorTrait<T>>() -> bool {
+        c_type::<T>().is_some()
+    }
+}
+
+impl<T: TensorTrait<T>> Backend<T> for ClangBackend {
+    fn device(&self) -> Device {
+        Device::CLANG
+    }
+
+    fn alloc(&self, len: usize) -> Storage<T> {
+        Storage::Host(vec![T::zero(); len].into_boxed_slice())
+    }
+
+    fn upload(&self, data: &[T]) -> Storage<T> {
+        Storage::Host(data.to_vec().into_boxed_slice())
+    }
+
+    fn download(&self, storage: &Storage<T>) -> DataArray<T> {
+        host(storage).clone()
+    }
+
+    fn unary(&self, op: UnaryOps, input: &Storage<T>, dim: Dimensions, arg: Option<T>) -> Storage<T> {
+        if !Self::compiles::<T>() {
+            return CpuBackend.unary(op, input, dim, arg);
+        }
+        let len = dim.0 * dim.1;
+        let kernel = match op {
+            UnaryOps::SUM => Kernel::Reduce { op: ReduceOps::SUM, len },
+            UnaryOps::Softmax => Kernel::Softmax { rows: dim.0, cols: dim.1 },
+            UnaryOps::MAX => {
+                let other = match arg.and_then(|other| other.to_f64()) {
+                    Some(other) => other,
+                    None => panic!("MAX needs a scalar to compare against"),
+                };
+                Kernel::Elementwise { expr: Expr::Max(Box::new(Expr::Input(0)), other.to_bits()), len }
+            }
+            _ => Kernel::Elementwise { expr: Expr::Unary(op, Box::new(Expr::Input(0))), len },
+        };
+        Storage::Host(run(&kernel, &[checked(input, dim)]))
+    }
+
+    fn binary(&self, op: BinaryOps, a: &Storage<T>, b: &Storage<T>, dim: Dimensions) -> Storage<T> {
+        if !Self::compiles::<T>() {
+            return CpuBackend.binary(op, a, b, dim);
+        }
+        let expr = Expr::Binary(op, Box::new(Expr::Input(0)), Box::new(Expr::Input(1)));
+        let kernel = Kernel::Elementwise { expr, len: dim.0 * dim.1 };
+        Storage::Host(run(&kernel, &[checked(a, dim), checked(b, dim)]))
+    }
+
+    fn reduce(&self, op: ReduceOps, input: &Storage<T>, dim: Dimensions) -> Storage<T> {
+        if !Self::compiles::<T>() {
+            return CpuBackend.reduce(op, input, dim);
+        }
+        let kernel = Kernel::Reduce { op, len: dim.0 * dim.1 };
+        Storage::Host(run(&kernel, &[checked(input, dim)]))
+    }
+
+    fn matmul(&self, a: &Storage<T>, a_dim: Dimensions, b: &Storage<T>, b_dim: Dimensions) -> Storage<T> {
+        if !Self::compiles::<T>() {
+            return CpuBackend.matmul(a, a_dim, b, b_dim);
+        }
+        if a_dim.1 != b_dim.0 {
+            panic!("Invalid matrix multiplication");
+        }
+        let kernel = Kernel::Matmul { m: a_dim.0, k: a_dim.1, n: b_dim.1 };
+        Storage::Host(run(&kernel, &[checked(a, a_dim), checked(b, b_dim)]))
+    }
+
+    fn movement(&self, op: MovementOps, input: &Storage<T>, dim: Dimensions) -> Storage<T> {
+        match op {
+            MovementOps::PERMUTE if Self::compiles::<T>() => {
+                let kernel = Kernel::Permute { rows: dim.0, cols: dim.1 };
+                Storage::Host(run(&kernel, &[checked(input, dim)]))
+            }
+            _ => CpuBackend.movement(op, input, dim),
+        }
+    }
+}
+
+///
+/// Compute a tensor by fusing the element-wise ops that produced it into one compiled kernel.
+///
+/// This is a standalone entry point, not part of how tensors are computed: ops on `Device::CLANG`
+/// still run one kernel per op as they are built. Call it on a finished graph to evaluate the
+/// element-wise part of it in a single pass, e.g. to compare against the op-by-op result.
+/// Element-wise ops are `ADD`, `SUB`, `MAX`, `EXP2`, `LOG2` and `Sigmoid`; every other node
+/// (leaves, matrix multiplications, reductions, softmax) becomes an input of the kernel and is read
+/// from its stored data. Kernels are cached by the structure of the fused graph and the
+/// `LazyBuffer` of every input, so the same expression on new data reuses compiled code.
+///
+/// # Arguments
+///
+/// * `root` - The tensor to compute.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::activation::relu };
+/// use nanograd::backend::clang::realize_fused;
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![-1.0, 2.0, -3.0, 4.0], (2, 2), None, None);
+/// let b: Tensor<f64> = Tensor::ones((2, 2), None, None);
+/// let c = relu(a + b.clone()) - b;
+///
+/// assert_eq!(realize_fused(&c), c.data().clone());
+/// ```
+pub fn realize_fused<T: TensorTrait<T>>(root: &Tensor<T>) -> DataArray<T> {
+    let mut inputs: Vec<&Tensor<T>> = Vec::new();
+    let expr = fuse(root, &mut inputs);
+    let len = root.dim().0 * root.dim().1;
+    let buffers: Vec<&LazyBuffer<T>> = inputs
+        .iter()
+        .map(|input| &input.lazy_data)
+        .collect();
+    let kernel = Kernel::Elementwise { expr, len };
+    // the fused graph is keyed by its structure and the shape and device of every input
+    let mut hasher = toolchain_hasher();
+    kernel.hash(&mut hasher);
+    buffers.hash(&mut hasher);
+    try_c_type::<T>().unwrap_or_else(|error| panic!("{}", error)).hash(&mut hasher);
+    let data: Vec<DataArray<T>> = inputs
+        .iter()
+        .map(|input| input.lazy_data.storage().to_host())
+        .collect();
+    let data: Vec<&[T]> = data
+        .iter()
+        .map(|data| &data[..])
+        .collect();
+    try_run_hashed(&kernel, &data, hasher.finish()).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Build the expression of a node, collecting the nodes that become kernel inputs.
+fn fuse<'a, T: TensorTrait<T>>(node: &'a Tensor<T>, inputs: &mut Vec<&'a Tensor<T>>) -> Expr {
+    let left = node.left.as_deref();
+    let right = node.right.as_deref();
+    match (node.op, left, right) {
+        (Ops::BinaryOps(op @ (BinaryOps::ADD | BinaryOps::SUB)), Some(left), Some(right)) => {
+            Expr::Binary(op, Box::new(fuse(left, inputs)), Box::new(fuse(right, inputs)))
+        }
+        (Ops::UnaryOps(UnaryOps::MAX), Some(left), None) => {
+            let other = match node.arg.and_then(|other| other.to_f64()) {
+                Some(other) => other,
+                None => panic!("MAX node has no scalar to compare against"),
+            };
+            Expr::Max(Box::new(fuse(left, inputs)), other.to_bits())
+        }
+        (Ops::UnaryOps(op @ (UnaryOps::EXP2 | UnaryOps::LOG2 | UnaryOps::Sigmoid)), Some(left), None) => {
+            Expr::Unary(op, Box::new(fuse(left, inputs)))
+        }
+        _ => {
+            // copies of the same tensor share one input
+            let index = match inputs.iter().position(|input| input.unique_id == node.unique_id) {
+                Some(index) => index,
+                None => {
+                    inputs.push(node);
+                    inputs.len() - 1
+                }
+            };
+            Expr::Input(index)
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use alloc::{ vec::Vec, vec };
+
+use crate::{ TensorTrait, Device, DataArray, Dimensions };
+use crate::backend::{ Backend, Storage };
+use crate::backend::parallel;
+use crate::backend::pool::alloc_host;
+use crate::nn::activation::{ sigmoid_op, softmax_op };
+use crate::types::ops::{ UnaryOps, BinaryOps, ReduceOps, MovementOps };
+use crate::types::half::{ is_half, widen, narrow };
+
+/// Elements per task of a row-wise op, rounded down to whole rows.
+const ROW_CHUNK: usize = 4096;
+
+///
+/// The default backend. Runs every kernel with plain loops over host memory, split across a thread
+/// pool once the op is large enough. Set `NANOGRAD_NUM_THREADS` to choose the number of threads.
+/// Half precision kernels run in f32; see `types::half`.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::backend::{ Backend, Storage, cpu::CpuBackend };
+/// use nanograd::types::ops::{ ReduceOps, BinaryOps };
+///
+/// let empty: Storage<f64> = Storage::Host(Vec::new().into_boxed_slice());
+/// let max = CpuBackend.reduce(ReduceOps::MAX, &empty, (0, 0));
+/// assert_eq!(max.to_host().as_ref(), &[f64::NEG_INFINITY]);
+///
+/// // both operands of an elementwise op have to fill the dimensions
+/// let a: Storage<f64> = Storage::Host(vec![1.0, 2.0].into_boxed_slice());
+/// let b: Storage<f64> = Storage::Host(vec![1.0].into_boxed_slice());
+/// let add = std::panic::AssertUnwindSafe(|| CpuBackend.binary(BinaryOps::ADD, &a, &b, (1, 2)));
+/// assert!(std::panic::catch_unwind(add).is_err());
+/// ```
+pub struct CpuBackend;
+
+/// Get the host data of a storage on the CPU.
+fn host<T: TensorTrait<T>>(storage: &Storage<T>) -> &DataArray<T> {
+    match storage.as_host() {
+        Some(data) => data,
+        None => panic!("CPU backend received storage from another device"),
+    }
+}
+
+/// Copy half precision storage to f32, where its kernels run.
+fn widened<T: TensorTrait<T>>(storage: &Storage<T>) -> Storage<f32> {
+    Storage::Host(widen(host(storage)).into_boxed_slice())
+}
+
+/// Round the f32 result of a kernel back to half precision.
+fn narrowed<T: TensorTrait<T>>(storage: Storage<f32>) -> Storage<T> {
+    Storage::Host(narrow(host(&storage)))
+}
+
+impl<T: TensorTrait<T>> Backend<T> for CpuBackend {
+    fn device(&self) -> Device {
+        Device::CPU
+    }
+
+    fn alloc(&self, len: usize) -> Storage<T> {
+        Storage::Host(alloc_host(len))
+    }
+
+    fn upload(&self, data: &[T]) -> Storage<T> {
+        Storage::Host(data.to_vec().into_boxed_slice())
+    }
+
+    fn download(&self, storage: &Storage<T>) -> DataArray<T> {
+        host(storage).clone()
+    }
+
+    fn unary(&self, op: UnaryOps, input: &Storage<T>, dim: Dimensions, arg: Option<T>) -> Storage<T> {
+        if is_half::<T>() {
+            let arg = arg.and_then(|arg| arg.to_f32());
+            return narrowed(Backend::<f32>::unary(self, op, &widened(input), dim, arg));
+        }
+        let data = host(input);
+        // chunks cover whole rows, so row-wise ops see the same data as on one thread
+        let chunk = dim.1.max(1) * (ROW_CHUNK / dim.1.max(1)).max(1);
+        let mut out = alloc_host::<T>(data.len());
+        let new_data = match op {
+            UnaryOps::EXP2 => {
+                let two = T::one() + T::one();
+                parallel::map(data, &mut out, |x| two.powf(x));
+                out
+            }
+            UnaryOps::LOG2 => {
+                parallel::map(data, &mut out, |x| x.log2());
+                out
+            }
+            UnaryOps::MAX => {
+                match arg {
+                    Some(other) => {
+                        parallel::map(data, &mut out, |x| x.max(other));
+                        out
+                    }
+                    None => panic!("MAX needs a scalar to compare against"),
+                }
+            }
+            UnaryOps::Sigmoid => parallel::map_chunks(data, chunk, |chunk| sigmoid_op(chunk, (1, chunk.len()))),
+            UnaryOps::Softmax => {
+                parallel::map_chunks(data, chunk, |chunk| softmax_op(chunk, (chunk.len() / dim.1, dim.1)))
+            }
+            UnaryOps::SUM => vec![parallel::sum(data)].into_boxed_slice(),
+        };
+        Storage::Host(new_data)
+    }
+
+    fn binary(&self, op: BinaryOps, a: &Storage<T>, b: &Storage<T>, dim: Dimensions) -> Storage<T> {
+        if is_half::<T>() {
+            return narrowed(Backend::<f32>::binary(self, op, &widened(a), &widened(b), dim));
+        }
+        let (a_data, b_data) = (host(a), host(b));
+        if a_data.len() != dim.0 * dim.1 || b_data.len() != dim.0 * dim.1 {
+            panic!(
+                "Data length does not match dimensions: {} and {} values for {}x{}",
+                a_data.len(),
+                b_data.len(),
+                dim.0,
+                dim.1
+            );
+        }
+        let mut out = alloc_host::<T>(a_data.len());
+        match op {
+            BinaryOps::ADD => parallel::zip(a_data, b_data, &mut out, |x, y| x + y),
+            BinaryOps::SUB => parallel::zip(a_data, b_data, &mut out, |x, y| x - y),
+            BinaryOps::MUL => parallel::zip(a_data, b_data, &mut out, |x, y| x * y),
+        }
+        Storage::Host(out)
+    }
+
+    fn reduce(&self, op: ReduceOps, input: &Storage<T>, dim: Dimensions) -> Storage<T> {
+        if is_half::<T>() {
+            return narrowed(Backend::<f32>::reduce(self, op, &widened(input), dim));
+        }
+        let data = host(input);
+        let value = match op {
+            ReduceOps::SUM => parallel::sum(data),
+            // the maximum of nothing is the identity of max, -inf
+            ReduceOps::MAX => {
+                let first = data.first().copied().unwrap_or_else(T::neg_infinity);
+                parallel::reduce(data, first, |a, b| if b > a { b } else { a })
+            }
+        };
+        Storage::Host(vec![value].into_boxed_slice())
+    }
+
+    fn matmul(&self, a: &Storage<T>, a_dim: Dimensions, b: &Storage<T>, b_dim: Dimensions) -> Storage<T> {
+        // half precision products are accumulated in f32
+        if is_half::<T>() {
+            return narrowed(Backend::<f32>::matmul(self, &widened(a), a_dim, &widened(b), b_dim));
+        }
+        Storage::Host(parallel::matmul(host(a), a_dim, host(b), b_dim))
+    }
+
+    fn movement(&self, op: MovementOps, input: &Storage<T>, dim: Dimensions) -> Storage<T> {
+        let data = host(input);
+        match op {
+            MovementOps::RESHAPE => Storage::Host(data.clone()),
+            MovementOps::PERMUTE => {
+                let mut new_data = Vec::with_capacity(dim.0 * dim.1);
+                for i in 0..dim.1 {
+                    for j in 0..dim.0 {
+                        new_data.push(data[j * dim.1 + i]);
+                    }
+                }
+                Storage::Host(new_data.into_boxed_slice())
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use core::any::{ Any, TypeId };
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+
+use crate::{ TensorTrait, Element, Device, DataArray, Dimensions, NanogradError };
+use crate::error::Result;
+use crate::types::ops::{ UnaryOps, BinaryOps, ReduceOps, MovementOps };
+
+pub mod cpu;
+pub mod parallel;
+pub mod pool;
+use crate::backend::cpu::CpuBackend;
+#[cfg(all(unix, feature = "std"))]
+pub mod clang;
+
+///
+/// Memory owned by a backend other than the CPU.
+/// Backends downcast through `as_any` to reach their own buffer type.
+pub trait DeviceBuffer<T: Element> {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Copy the contents back to host memory.
+    fn to_host(&self) -> DataArray<T>;
+    fn clone_buffer(&self) -> Box<dyn DeviceBuffer<T>>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Where the data of a `LazyBuffer` lives.
+pub enum Storage<T: Element> {
+    Host(DataArray<T>),
+    Device(Box<dyn DeviceBuffer<T>>),
+}
+
+impl<T: Element> Storage<T> {
+    pub fn len(&self) -> usize {
+        match self {
+            Storage::Host(data) => data.len(),
+            Storage::Device(buffer) => buffer.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the host data, if the storage lives on the host.
+    pub fn as_host(&self) -> Option<&DataArray<T>> {
+        match self {
+            Storage::Host(data) => Some(data),
+            Storage::Device(_) => None,
+        }
+    }
+
+    /// Copy the contents to host memory, wherever they live.
+    pub fn to_host(&self) -> DataArray<T> {
+        match self {
+            Storage::Host(data) => data.clone(),
+            Storage::Device(buffer) => buffer.to_host(),
+        }
+    }
+}
+
+impl<T: Element> Clone for Storage<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Storage::Host(data) => Storage::Host(data.clone()),
+            Storage::Device(buffer) => Storage::Device(buffer.clone_buffer()),
+        }
+    }
+}
+
+impl<T: Element> PartialEq for Storage<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Storage::Host(a), Storage::Host(b)) => a == b,
+            _ => self.to_host() == other.to_host(),
+        }
+    }
+}
+
+impl<T: Element> Eq for Storage<T> {}
+
+///
+/// Executes tensor operations on a device.
+///
+/// A backend owns the storage of every buffer on its device: it allocates buffers, copies them
+/// to and from the host, and provides one kernel entry per op family. Tensors dispatch to the
+/// backend registered for their device, so a new accelerator only needs to implement this trait
+/// and call `register_backend`.
+///
+/// # Examples
+///
+/// ```
+/// use std::any::Any;
+/// use std::rc::Rc;
+/// use nanograd::{ Tensor, Device, DataArray, Dimensions, TensorTrait };
+/// use nanograd::backend::{ register_backend, Backend, DeviceBuffer, Storage, cpu::CpuBackend };
+/// use nanograd::types::ops::{ UnaryOps, BinaryOps, ReduceOps, MovementOps };
+///
+/// // a "device" that keeps its data in its own buffer type and runs kernels on the CPU
+/// #[derive(Clone)]
+/// struct MirrorBuffer(Vec<f64>);
+///
+/// impl DeviceBuffer<f64> for MirrorBuffer {
+///     fn len(&self) -> usize { self.0.len() }
+///     fn to_host(&self) -> DataArray<f64> { self.0.clone().into_boxed_slice() }
+///     fn clone_buffer(&self) -> Box<dyn DeviceBuffer<f64>> { Box::new(self.clone()) }
+///     fn as_any(&self) -> &dyn Any { self }
+/// }
+///
+/// struct Mirror;
+///
+/// impl Mirror {
+///     fn run(&self, result: Storage<f64>) -> Storage<f64> {
+///         self.upload(&result.to_host())
+///     }
+///     fn host(storage: &Storage<f64>) -> Storage<f64> {
+///         Storage::Host(storage.to_host())
+///     }
+/// }
+///
+/// impl Backend<f64> for Mirror {
+///     fn device(&self) -> Device { Device::Custom("mirror".to_string()) }
+///     fn alloc(&self, len: usize) -> Storage<f64> {
+///         Storage::Device(Box::new(MirrorBuffer(vec![0.0; len])))
+///     }
+///     fn upload(&self, data: &[f64]) -> Storage<f64> {
+///         Storage::Device(Box::new(MirrorBuffer(data.to_vec())))
+///     }
+///     fn download(&self, storage: &Storage<f64>) -> DataArray<f64> { storage.to_host() }
+///     fn unary(&self, op: UnaryOps, input: &Storage<f64>, dim: Dimensions, arg: Option<f64>) -> Storage<f64> {
+///         self.run(CpuBackend.unary(op, &Mirror::host(input), dim, arg))
+///     }
+///     fn binary(&self, op: BinaryOps, a: &Storage<f64>, b: &Storage<f64>, dim: Dimensions) -> Storage<f64> {
+///         self.run(CpuBackend.binary(op, &Mirror::host(a), &Mirror::host(b), dim))
+///     }
+///     fn reduce(&self, op: ReduceOps, input: &Storage<f64>, dim: Dimensions) -> Storage<f64> {
+///         self.run(CpuBackend.reduce(op, &Mirror::host(input), dim))
+///     }
+///     fn matmul(&self, a: &Storage<f64>, a_dim: Dimensions, b: &Storage<f64>, b_dim: Dimensions) -> Storage<f64> {
+///         self.run(CpuBackend.matmul(&Mirror::host(a), a_dim, &Mirror::host(b), b_dim))
+///     }
+///     fn movement(&self, op: MovementOps, input: &Storage<f64>, dim: Dimensions) -> Storage<f64> {
+///         self.run(CpuBackend.movement(op, &Mirror::host(input), dim))
+///     }
+/// }
+///
+/// register_backend::<f64>(Rc::new(Mirror));
+/// let mirror = Device::Custom("mirror".to_string());
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None).to(mirror.clone());
+/// let b: Tensor<f64> = Tensor::ones((2, 2), Some(mirror.clone()), None);
+/// let c = a + b;
+///
+/// assert_eq!(c.device(), &mirror);
+/// let c = c.to(Device::CPU);
+/// assert_eq!(c.data(), &vec![2.0, 3.0, 4.0, 5.0].into_boxed_slice());
+/// ```
+pub trait Backend<T: Element> {
+    /// The device this backend runs on.
+    fn device(&self) -> Device;
+    /// Allocate zeroed storage for `len` elements.
+    fn alloc(&self, len: usize) -> Storage<T>;
+    /// Copy host data into new storage on the device.
+    fn upload(&self, data: &[T]) -> Storage<T>;
+    /// Copy storage from the device back to the host.
+    fn download(&self, storage: &Storage<T>) -> DataArray<T>;
+    /// Element-wise ops. `arg` is the scalar argument of ops that take one, e.g. the threshold of `MAX`.
+    fn unary(&self, op: UnaryOps, input: &Storage<T>, dim: Dimensions, arg: Option<T>) -> Storage<T>;
+    /// Element-wise ops between two buffers of the same dimensions.
+    fn binary(&self, op: BinaryOps, a: &Storage<T>, b: &Storage<T>, dim: Dimensions) -> Storage<T>;
+    /// Reduce every element of the input into a single value.
+    fn reduce(&self, op: ReduceOps, input: &Storage<T>, dim: Dimensions) -> Storage<T>;
+    /// Matrix multiplication of `(m, k)` by `(k, n)`.
+    fn matmul(&self, a: &Storage<T>, a_dim: Dimensions, b: &Storage<T>, b_dim: Dimensions) -> Storage<T>;
+    /// Rearrange the elements of the input. `PERMUTE` swaps rows and columns, `RESHAPE` keeps the order.
+    fn movement(&self, op: MovementOps, input: &Storage<T>, dim: Dimensions) -> Storage<T>;
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static BACKENDS: RefCell<HashMap<(TypeId, Device), Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+///
+/// Make a backend available to every tensor of element type `T` on its device.
+/// Registering again for the same device replaces the previous backend.
+/// Backends are registered per thread, which needs the `std` feature.
+#[cfg(feature = "std")]
+pub fn register_backend<T: Element>(backend: Rc<dyn Backend<T>>) {
+    let key = (TypeId::of::<T>(), backend.device());
+    BACKENDS.with(|backends| {
+        backends.borrow_mut().insert(key, Rc::new(backend));
+    });
+}
+
+///
+/// Get the backend for a device. The CPU backend is always available, the clang backend on Unix with `std`.
+///
+/// # Panics
+///
+/// * If no backend was registered for the device.
+pub fn get_backend<T: TensorTrait<T>>(device: &Device) -> Rc<dyn Backend<T>> {
+    try_get_backend(device).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Get the backend for a device, or an `UnsupportedDevice` error if none is registered.
+pub fn try_get_backend<T: TensorTrait<T>>(device: &Device) -> Result<Rc<dyn Backend<T>>> {
+    match registered::<T>(device) {
+        Some(backend) => Ok(backend),
+        None => {
+            match device {
+                Device::CPU => Ok(Rc::new(CpuBackend)),
+                #[cfg(all(unix, feature = "std"))]
+                Device::CLANG => Ok(Rc::new(clang::ClangBackend)),
+                _ => Err(NanogradError::UnsupportedDevice(device.clone())),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn registered<T: Element>(device: &Device) -> Option<Rc<dyn Backend<T>>> {
+    let key = (TypeId::of::<T>(), device.clone());
+    BACKENDS.with(|backends| {
+        backends
+            .borrow()
+            .get(&key)
+            .and_then(|backend| backend.downcast_ref::<Rc<dyn Backend<T>>>().cloned())
+    })
+}
+
+// without threads there is nowhere to keep registrations, so only the built-in backends exist
+#[cfg(not(feature = "std"))]
+fn registered<T: Element>(_device: &Device) -> Option<Rc<dyn Backend<T>>> {
+    None
+}
+
+///
+/// Get the backend that stores elements of any type on a device, e.g. to upload a `bool` mask.
+/// Besides registered backends, this finds the built-in backends of `f32` and `f64`.
+pub fn find_backend<T: Element>(device: &Device) -> Result<Rc<dyn Backend<T>>> {
+    if let Some(backend) = registered::<T>(device) {
+        return Ok(backend);
+    }
+    let builtin: Option<Rc<dyn Any>> = if TypeId::of::<T>() == TypeId::of::<f32>() {
+        try_get_backend::<f32>(device).ok().map(|backend| Rc::new(backend) as Rc<dyn Any>)
+    } else if TypeId::of::<T>() == TypeId::of::<f64>() {
+        try_get_backend::<f64>(device).ok().map(|backend| Rc::new(backend) as Rc<dyn Any>)
+    } else {
+        None
+    };
+    builtin
+        .and_then(|backend| backend.downcast_ref::<Rc<dyn Backend<T>>>().cloned())
+        .ok_or_else(|| NanogradError::UnsupportedDevice(device.clone()))
+}
@@ -0,0 +1,244 @@
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use rayon::{ ThreadPool, ThreadPoolBuilder };
+
+use crate::{ TensorTrait, DataArray, Dimensions, helpers::is_valid_matrix_multiplication };
+use crate::forward::gemm::gemm_into;
+#[cfg(feature = "std")]
+use crate::forward::gemm::MR;
+use crate::backend::pool::alloc_host;
+
+// Without the `std` feature there are no threads and every op runs serially on the calling thread,
+// in the same order of additions as the parallel kernels.
+
+/// Environment variable that sets the number of threads used by the CPU backend.
+#[cfg(feature = "std")]
+pub const NUM_THREADS_VAR: &str = "NANOGRAD_NUM_THREADS";
+
+/// Number of elements a reduction sums serially before partial results are combined.
+/// Fixed so the order of additions, and with it the result, does not depend on the thread count.
+pub const REDUCE_CHUNK: usize = 4096;
+
+/// Ops on fewer elements than this run on the calling thread.
+#[cfg(feature = "std")]
+const PARALLEL_THRESHOLD: usize = 1 << 15;
+
+/// Number of elements each task of an element-wise op processes.
+#[cfg(feature = "std")]
+const ELEMENTWISE_CHUNK: usize = 1 << 14;
+
+/// The shared pool, sized by `NANOGRAD_NUM_THREADS` or the number of cores.
+#[cfg(feature = "std")]
+fn pool() -> &'static ThreadPool {
+    static POOL: OnceLock<ThreadPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        ThreadPoolBuilder::new()
+            .num_threads(configured_threads())
+            .thread_name(|i| format!("nanograd-{}", i))
+            .build()
+            .expect("Failed to start the CPU thread pool")
+    })
+}
+
+#[cfg(feature = "std")]
+fn configured_threads() -> usize {
+    let configured = std::env
+        ::var(NUM_THREADS_VAR)
+        .ok()
+        .and_then(|threads| threads.trim().parse::<usize>().ok())
+        .filter(|threads| *threads > 0);
+    match configured {
+        Some(threads) => threads,
+        None =>
+            std::thread
+                ::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1),
+    }
+}
+
+/// Run `op` on the current pool, or on the shared pool when called from outside any pool.
+#[cfg(feature = "std")]
+fn install<R: Send>(op: impl FnOnce() -> R + Send) -> R {
+    match rayon::current_thread_index() {
+        Some(_) => op(),
+        None => pool().install(op),
+    }
+}
+
+///
+/// Number of threads CPU kernels are split across on the calling thread.
+#[cfg(feature = "std")]
+pub fn num_threads() -> usize {
+    match rayon::current_thread_index() {
+        Some(_) => rayon::current_num_threads(),
+        None => pool().current_num_threads(),
+    }
+}
+
+///
+/// Run `op` with CPU kernels split across `threads` threads instead of the shared pool.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::transformation::sum };
+/// use nanograd::backend::parallel::with_num_threads;
+///
+/// let run = |threads| {
+///     with_num_threads(threads, || {
+///         let data: Vec<f32> = (0..100_000).map(|i| (i as f32).sin()).collect();
+///         sum(Tensor::from_vec(data, (100, 1000), None, None)).data()[0]
+///     })
+/// };
+///
+/// // reductions give bit-identical results for any number of threads
+/// assert_eq!(run(1), run(4));
+/// ```
+#[cfg(feature = "std")]
+pub fn with_num_threads<R: Send>(threads: usize, op: impl FnOnce() -> R + Send) -> R {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("Failed to start a CPU thread pool")
+        .install(op)
+}
+
+///
+/// Number of threads CPU kernels are split across on the calling thread.
+#[cfg(not(feature = "std"))]
+pub fn num_threads() -> usize {
+    1
+}
+
+#[cfg(feature = "std")]
+fn is_parallel(len: usize) -> bool {
+    len >= PARALLEL_THRESHOLD && num_threads() > 1
+}
+
+/// Concatenate the outputs of the chunks of an op.
+#[cfg(feature = "std")]
+fn concat<T: TensorTrait<T>>(parts: Vec<DataArray<T>>) -> DataArray<T> {
+    let mut data = Vec::with_capacity(parts.iter().map(|part| part.len()).sum());
+    for part in parts {
+        data.extend_from_slice(&part);
+    }
+    data.into_boxed_slice()
+}
+
+///
+/// Apply an op to consecutive chunks of the input in parallel and concatenate the results.
+///
+/// # Arguments
+///
+/// * `data` - The input.
+/// * `chunk` - Elements per chunk. Ops that work on rows need a multiple of the row length.
+/// * `op` - Computes the output of one chunk.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub fn map_chunks<T, F>(data: &[T], chunk: usize, op: F) -> DataArray<T>
+    where T: TensorTrait<T>, F: Fn(&[T]) -> DataArray<T> + Sync
+{
+    #[cfg(feature = "std")]
+    if is_parallel(data.len()) {
+        let chunk = chunk.max(1);
+        return concat(install(|| data.par_chunks(chunk).map(&op).collect()));
+    }
+    op(data)
+}
+
+/// Apply an element-wise op to every element in parallel, writing into `out`.
+pub fn map<T, F>(data: &[T], out: &mut [T], op: F) where T: TensorTrait<T>, F: Fn(T) -> T + Sync {
+    if data.len() != out.len() {
+        panic!("Element-wise op on inputs of different lengths");
+    }
+    let map_chunk = |(data, out): (&[T], &mut [T])| {
+        for (x, y) in data.iter().zip(out.iter_mut()) {
+            *y = op(*x);
+        }
+    };
+    #[cfg(feature = "std")]
+    if is_parallel(data.len()) {
+        return install(|| {
+            data.par_chunks(ELEMENTWISE_CHUNK).zip(out.par_chunks_mut(ELEMENTWISE_CHUNK)).for_each(map_chunk)
+        });
+    }
+    map_chunk((data, out))
+}
+
+/// Combine two inputs of the same length element by element in parallel, writing into `out`.
+pub fn zip<T, F>(a: &[T], b: &[T], out: &mut [T], op: F) where T: TensorTrait<T>, F: Fn(T, T) -> T + Sync {
+    if a.len() != b.len() || a.len() != out.len() {
+        panic!("Element-wise op on inputs of different lengths");
+    }
+    let zip_chunk = |((a, b), out): ((&[T], &[T]), &mut [T])| {
+        for ((x, y), z) in a.iter().zip(b.iter()).zip(out.iter_mut()) {
+            *z = op(*x, *y);
+        }
+    };
+    #[cfg(feature = "std")]
+    if is_parallel(a.len()) {
+        return install(|| {
+            a.par_chunks(ELEMENTWISE_CHUNK)
+                .zip(b.par_chunks(ELEMENTWISE_CHUNK))
+                .zip(out.par_chunks_mut(ELEMENTWISE_CHUNK))
+                .for_each(zip_chunk)
+        });
+    }
+    zip_chunk(((a, b), out))
+}
+
+///
+/// Reduce the input in fixed chunks of `REDUCE_CHUNK` elements, then combine the partial results
+/// in order. The grouping only depends on the length of the input, so the result is the same for
+/// any number of threads.
+pub fn reduce<T, F>(data: &[T], identity: T, op: F) -> T where T: TensorTrait<T>, F: Fn(T, T) -> T + Sync {
+    let fold = |chunk: &[T]| chunk.iter().fold(identity, |acc, x| op(acc, *x));
+    #[cfg(feature = "std")]
+    if is_parallel(data.len()) {
+        let partials: Vec<T> = install(|| data.par_chunks(REDUCE_CHUNK).map(fold).collect());
+        return partials.into_iter().fold(identity, op);
+    }
+    let partials: Vec<T> = data.chunks(REDUCE_CHUNK).map(fold).collect();
+    partials.into_iter().fold(identity, op)
+}
+
+/// Sum every element deterministically.
+pub fn sum<T: TensorTrait<T>>(data: &[T]) -> T {
+    reduce(data, T::zero(), |a, b| a + b)
+}
+
+///
+/// Multiply an `(m, k)` matrix by a `(k, n)` matrix, splitting the rows of the output into bands
+/// that are computed in parallel with the blocked `gemm` kernel. Every output element is summed in
+/// the same order on any number of threads.
+pub fn matmul<T: TensorTrait<T>>(a: &[T], a_dim: Dimensions, b: &[T], b_dim: Dimensions) -> DataArray<T> {
+    if !is_valid_matrix_multiplication(a_dim, b_dim) {
+        panic!("Invalid matrix multiplication");
+    }
+    let (m, n) = (a_dim.0, b_dim.1);
+    let mut out = alloc_host::<T>(m * n);
+    #[cfg(feature = "std")]
+    if is_parallel(m * a_dim.1 * n) && n != 0 {
+        let k = a_dim.1;
+        // whole register tiles per band, enough bands to keep every thread busy
+        let band_rows = m.div_ceil(num_threads()).div_ceil(MR) * MR;
+        install(|| {
+            out.par_chunks_mut(band_rows * n)
+                .enumerate()
+                .for_each(|(band, out_band)| {
+                    let first_row = band * band_rows;
+                    let rows = out_band.len() / n;
+                    let a_band = &a[first_row * k..(first_row + rows) * k];
+                    gemm_into(a_band, (rows, k), b, b_dim, out_band);
+                })
+        });
+        return out;
+    }
+    gemm_into(a, a_dim, b, b_dim, &mut out);
+    out
+}
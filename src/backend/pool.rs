@@ -0,0 +1,256 @@
+#[cfg(feature = "std")]
+use core::any::{ Any, TypeId };
+#[cfg(feature = "std")]
+use core::cell::{ Cell, RefCell };
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use core::mem::size_of;
+use alloc::vec;
+#[cfg(feature = "std")]
+use alloc::{ boxed::Box, vec::Vec };
+
+use crate::{ Element, Numeric, Device, DataArray };
+use crate::backend::Storage;
+
+/// Default cap on the bytes kept in each device's pool, 256 MiB.
+#[cfg(feature = "std")]
+const DEFAULT_LIMIT: usize = 256 << 20;
+
+///
+/// Allocation counters of the pool of one device.
+///
+/// * `hits` - Buffers handed out from the pool.
+/// * `misses` - Buffers that had to be freshly allocated.
+/// * `live_bytes` - Bytes held by buffers currently in use.
+/// * `peak_bytes` - The largest `live_bytes` seen since the stats were reset.
+/// * `cached_bytes` - Bytes kept in the pool, ready for reuse.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub live_bytes: usize,
+    pub peak_bytes: usize,
+    pub cached_bytes: usize,
+}
+
+/// Free buffers of one element type on one device, by size class.
+/// A size class is an exact element count, since training reuses the same shapes every step.
+#[cfg(feature = "std")]
+struct Pool<T: Element> {
+    free: HashMap<usize, Vec<Storage<T>>>,
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static POOLS: RefCell<HashMap<(TypeId, Device), Box<dyn Any>>> = RefCell::new(HashMap::new());
+    static STATS: RefCell<HashMap<Device, PoolStats>> = RefCell::new(HashMap::new());
+    static LIMIT: Cell<usize> = const { Cell::new(DEFAULT_LIMIT) };
+}
+
+#[cfg(feature = "std")]
+fn with_pool<T: Element, R>(device: &Device, f: impl FnOnce(&mut Pool<T>) -> R) -> R {
+    POOLS.with(|pools| {
+        let mut pools = pools.borrow_mut();
+        let pool = pools
+            .entry((TypeId::of::<T>(), device.clone()))
+            .or_insert_with(|| Box::new(Pool::<T> { free: HashMap::new() }));
+        f(pool.downcast_mut::<Pool<T>>().unwrap())
+    })
+}
+
+#[cfg(feature = "std")]
+fn with_stats<R>(device: &Device, f: impl FnOnce(&mut PoolStats) -> R) -> R {
+    STATS.with(|stats| f(stats.borrow_mut().entry(device.clone()).or_default()))
+}
+
+///
+/// Take a free buffer of `len` elements from the pool of a device. Counts a hit or a miss.
+/// The contents of the returned buffer are unspecified.
+#[cfg(feature = "std")]
+pub fn take<T: Element>(device: &Device, len: usize) -> Option<Storage<T>> {
+    let storage = with_pool::<T, _>(device, |pool| pool.free.get_mut(&len).and_then(|free| free.pop()));
+    with_stats(device, |stats| {
+        match storage {
+            Some(_) => {
+                stats.hits += 1;
+                stats.cached_bytes -= len * size_of::<T>();
+            }
+            None => {
+                stats.misses += 1;
+            }
+        }
+    });
+    storage
+}
+
+///
+/// Get zeroed host memory for `len` elements, reusing a buffer from the CPU pool when one is free.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::Device;
+/// use nanograd::backend::pool::{ alloc_host, recycle, stats, reset_stats, clear };
+///
+/// clear::<f64>(&Device::CPU);
+/// reset_stats(&Device::CPU);
+///
+/// let buffer = alloc_host::<f64>(16);
+/// recycle(&Device::CPU, nanograd::backend::Storage::Host(buffer));
+/// let buffer = alloc_host::<f64>(16);
+///
+/// assert_eq!(buffer.len(), 16);
+/// assert_eq!(stats(&Device::CPU).misses, 1);
+/// assert_eq!(stats(&Device::CPU).hits, 1);
+/// ```
+pub fn alloc_host<T: Numeric>(len: usize) -> DataArray<T> {
+    match take::<T>(&Device::CPU, len) {
+        Some(Storage::Host(mut data)) => {
+            data.fill(T::zero());
+            data
+        }
+        _ => vec![T::zero(); len].into_boxed_slice(),
+    }
+}
+
+/// Copy host data into a buffer from the CPU pool, or a fresh one if none is free.
+pub fn copy_host<T: Element>(data: &[T]) -> DataArray<T> {
+    match take::<T>(&Device::CPU, data.len()) {
+        Some(Storage::Host(mut copy)) => {
+            copy.copy_from_slice(data);
+            copy
+        }
+        _ => data.to_vec().into_boxed_slice(),
+    }
+}
+
+///
+/// Record that a buffer of `len` elements came into use on a device.
+#[cfg(feature = "std")]
+pub fn track<T: Element>(device: &Device, len: usize) {
+    with_stats(device, |stats| {
+        stats.live_bytes += len * size_of::<T>();
+        stats.peak_bytes = stats.peak_bytes.max(stats.live_bytes);
+    });
+}
+
+///
+/// Record that a buffer of `len` elements on a device is no longer in use.
+#[cfg(feature = "std")]
+pub fn untrack<T: Element>(device: &Device, len: usize) {
+    let _ = STATS.try_with(|stats| {
+        let mut stats = stats.borrow_mut();
+        let stats = stats.entry(device.clone()).or_default();
+        stats.live_bytes = stats.live_bytes.saturating_sub(len * size_of::<T>());
+    });
+}
+
+///
+/// Return a buffer to the pool of its device. Buffers that would push the pool past its limit
+/// are freed instead.
+#[cfg(feature = "std")]
+pub fn recycle<T: Element>(device: &Device, storage: Storage<T>) {
+    let len = storage.len();
+    if len == 0 {
+        return;
+    }
+    let bytes = len * size_of::<T>();
+    // buffers are dropped during thread shutdown too, when the pool may already be gone
+    let _ = STATS.try_with(|stats| {
+        let mut stats = stats.borrow_mut();
+        let stats = stats.entry(device.clone()).or_default();
+        if stats.cached_bytes + bytes > LIMIT.with(|limit| limit.get()) {
+            return;
+        }
+        stats.cached_bytes += bytes;
+        let _ = POOLS.try_with(|pools| {
+            let mut pools = pools.borrow_mut();
+            let pool = pools
+                .entry((TypeId::of::<T>(), device.clone()))
+                .or_insert_with(|| Box::new(Pool::<T> { free: HashMap::new() }));
+            let pool = pool.downcast_mut::<Pool<T>>().unwrap();
+            pool.free.entry(len).or_default().push(storage);
+        });
+    });
+}
+
+///
+/// Get the allocation counters of a device.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, Device, nn::{ activation::relu, transformation::sum } };
+/// use nanograd::backend::pool::{ stats, reset_stats };
+///
+/// let step = || {
+///     let w: Tensor<f64> = Tensor::ones((8, 8), None, Some(true));
+///     let x: Tensor<f64> = Tensor::ones((8, 4), None, None);
+///     let mut y = sum(relu(w * x));
+///     y.backward();
+/// };
+///
+/// step();
+/// reset_stats(&Device::CPU);
+/// step();
+///
+/// // the second step reuses the buffers the first one returned
+/// let stats = stats(&Device::CPU);
+/// assert_eq!(stats.misses, 0);
+/// assert!(stats.hits > 0);
+/// assert_eq!(stats.live_bytes, 0);
+/// assert!(stats.peak_bytes > 0);
+/// ```
+#[cfg(feature = "std")]
+pub fn stats(device: &Device) -> PoolStats {
+    with_stats(device, |stats| *stats)
+}
+
+/// Reset the hit and miss counters of a device and start tracking the peak from the current usage.
+#[cfg(feature = "std")]
+pub fn reset_stats(device: &Device) {
+    with_stats(device, |stats| {
+        stats.hits = 0;
+        stats.misses = 0;
+        stats.peak_bytes = stats.live_bytes;
+    });
+}
+
+/// Free every cached buffer of element type `T` on a device.
+#[cfg(feature = "std")]
+pub fn clear<T: Element>(device: &Device) {
+    let freed: usize = with_pool::<T, _>(device, |pool| {
+        let freed = pool.free
+            .iter()
+            .map(|(len, free)| len * free.len() * size_of::<T>())
+            .sum();
+        pool.free.clear();
+        freed
+    });
+    with_stats(device, |stats| {
+        stats.cached_bytes = stats.cached_bytes.saturating_sub(freed);
+    });
+}
+
+/// Set the most bytes each device's pool keeps for reuse. Defaults to 256 MiB.
+#[cfg(feature = "std")]
+pub fn set_limit(bytes: usize) {
+    LIMIT.with(|limit| limit.set(bytes));
+}
+
+// without `std` there is no pool: buffers are allocated fresh and freed when dropped
+
+#[cfg(not(feature = "std"))]
+pub fn take<T: Element>(_device: &Device, _len: usize) -> Option<Storage<T>> {
+    None
+}
+
+#[cfg(not(feature = "std"))]
+pub fn track<T: Element>(_device: &Device, _len: usize) {}
+
+#[cfg(not(feature = "std"))]
+pub fn untrack<T: Element>(_device: &Device, _len: usize) {}
+
+#[cfg(not(feature = "std"))]
+pub fn recycle<T: Element>(_device: &Device, _storage: Storage<T>) {}
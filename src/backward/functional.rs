@@ -0,0 +1,226 @@
+use core::f64::consts::LN_2;
+use alloc::{ vec::Vec, vec, format };
+
+use crate::{
+    Tensor,
+    TensorTrait,
+    Ops,
+    types::ops::{ BinaryOps, UnaryOps, ReduceOps, LoadOps },
+    DataArray,
+    Dimensions,
+    autograd::function::CustomOp,
+    NanogradError,
+    error::Result,
+};
+
+/// Gradients flowing from a child to its left and right parents.
+pub type ParentGradients<T> = (Option<DataArray<T>>, Option<DataArray<T>>);
+
+///
+/// Compute the gradients of a tensor's parents from the gradient of the tensor itself.
+/// These are the backward rules of every op. Unlike `backward_by_operation`, which stores
+/// their results on the parents, this does not touch the gradients stored on the graph.
+///
+/// # Arguments
+///
+/// * `child` - The tensor produced by the operation.
+/// * `grad_output` - The gradient flowing into `child`. Must have the same length as the child's data.
+///
+/// # Returns
+///
+/// The gradients for the left and right parents, in that order. A parent that does not exist gets `None`.
+///
+/// # Panics
+///
+/// * If the operation has no backward rule.
+pub fn vjp_by_operation<T: TensorTrait<T>>(
+    child: &Tensor<T>,
+    grad_output: &DataArray<T>
+) -> ParentGradients<T> {
+    try_vjp_by_operation(child, grad_output).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `vjp_by_operation`, but returns an `UnsupportedOp` error for operations without a backward rule.
+pub fn try_vjp_by_operation<T: TensorTrait<T>>(
+    child: &Tensor<T>,
+    grad_output: &DataArray<T>
+) -> Result<ParentGradients<T>> {
+    let left = match &child.left {
+        None => {
+            return Ok((None, None));
+        }
+        Some(left) => left,
+    };
+    let mut inputs: Vec<(&[T], Dimensions)> = vec![(left.data(), left.dim())];
+    if let Some(right) = &child.right {
+        inputs.push((right.data(), right.dim()));
+    }
+    let mut grads = try_vjp_data(
+        child.op,
+        child.custom.as_ref(),
+        &inputs,
+        (child.data(), child.dim()),
+        grad_output
+    )?.into_iter();
+    Ok((grads.next(), grads.next()))
+}
+
+///
+/// Compute the gradients of the inputs of an op from plain data, without a tensor graph.
+/// This is the rule `vjp_by_operation` applies, for callers that keep their own graph.
+///
+/// # Arguments
+///
+/// * `op` - The operation that produced the output.
+/// * `custom` - The function of a custom op, `None` for built-in ops.
+/// * `inputs` - The data and dimensions of the inputs, left before right.
+/// * `output` - The data and dimensions the op produced.
+/// * `grad_output` - The gradient flowing into the output.
+///
+/// # Returns
+///
+/// One gradient per input, in the order of `inputs`.
+///
+/// # Panics
+///
+/// * If the operation has no backward rule.
+pub fn vjp_data<T: TensorTrait<T>>(
+    op: Ops,
+    custom: Option<&CustomOp<T>>,
+    inputs: &[(&[T], Dimensions)],
+    output: (&[T], Dimensions),
+    grad_output: &[T]
+) -> Vec<DataArray<T>> {
+    try_vjp_data(op, custom, inputs, output, grad_output).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Like `vjp_data`, but returns an `UnsupportedOp` error for operations without a backward rule.
+pub fn try_vjp_data<T: TensorTrait<T>>(
+    op: Ops,
+    custom: Option<&CustomOp<T>>,
+    inputs: &[(&[T], Dimensions)],
+    output: (&[T], Dimensions),
+    grad_output: &[T]
+) -> Result<Vec<DataArray<T>>> {
+    match op {
+        Ops::BinaryOps(op) => {
+            let (grad_left, grad_right) = vjp_binary(inputs[0], inputs[1], grad_output, op);
+            Ok(vec![grad_left, grad_right])
+        }
+        Ops::UnaryOps(op) => Ok(vec![vjp_unary(inputs[0].0, output.0, inputs[0].1, grad_output, op)?]),
+        Ops::ReduceOps(op) => Ok(vec![vjp_reduce(inputs[0].1, grad_output, op)?]),
+        Ops::LoadOps(LoadOps::CUSTOM) => {
+            let input_dims: Vec<Dimensions> = inputs
+                .iter()
+                .map(|(_, dim)| *dim)
+                .collect();
+            custom.unwrap().try_backward_data(grad_output, output.1, &input_dims)
+        }
+        op => Err(NanogradError::unsupported(&format!("{:?}", op), "no backward rule")),
+    }
+}
+
+fn vjp_binary<T: TensorTrait<T>>(
+    (a_data, a_dim): (&[T], Dimensions),
+    (b_data, b_dim): (&[T], Dimensions),
+    grad_output: &[T],
+    op: BinaryOps
+) -> (DataArray<T>, DataArray<T>) {
+    match op {
+        // gradient flows through plus signs
+        BinaryOps::ADD => (grad_output.into(), grad_output.into()),
+        // and flips sign for the subtrahend
+        BinaryOps::SUB => {
+            let negated: Vec<T> = grad_output
+                .iter()
+                .map(|g| T::zero() - *g)
+                .collect();
+            (grad_output.into(), negated.into_boxed_slice())
+        }
+        // matrix multiplication: dA = G * B^T, dB = A^T * G
+        BinaryOps::MUL => {
+            let mut grad_a = vec![T::zero(); a_dim.0 * a_dim.1];
+            let mut grad_b = vec![T::zero(); b_dim.0 * b_dim.1];
+            for i in 0..a_dim.0 {
+                for j in 0..b_dim.1 {
+                    let g = grad_output[i * b_dim.1 + j];
+                    for k in 0..a_dim.1 {
+                        grad_a[i * a_dim.1 + k] = grad_a[i * a_dim.1 + k] + g * b_data[k * b_dim.1 + j];
+                        grad_b[k * b_dim.1 + j] = grad_b[k * b_dim.1 + j] + a_data[i * a_dim.1 + k] * g;
+                    }
+                }
+            }
+            (grad_a.into_boxed_slice(), grad_b.into_boxed_slice())
+        }
+    }
+}
+
+fn vjp_unary<T: TensorTrait<T>>(
+    parent_data: &[T],
+    child_data: &[T],
+    dim: Dimensions,
+    grad_output: &[T],
+    op: UnaryOps
+) -> Result<DataArray<T>> {
+    let ln_two = T::from_f64(LN_2).unwrap();
+    let mut new_grad: Vec<T> = Vec::with_capacity(dim.0 * dim.1);
+    match op {
+        UnaryOps::MAX => {
+            // gradient only flows where the input was kept
+            for i in 0..dim.0 * dim.1 {
+                let kept = if child_data[i] == parent_data[i] { T::one() } else { T::zero() };
+                new_grad.push(kept * grad_output[i]);
+            }
+        }
+        UnaryOps::Sigmoid => {
+            // sigmoid(x) * (1 - sigmoid(x))
+            for i in 0..dim.0 * dim.1 {
+                let sigmoid = child_data[i];
+                new_grad.push(grad_output[i] * sigmoid * (T::one() - sigmoid));
+            }
+        }
+        UnaryOps::LOG2 => {
+            // 1 / (x * ln(2))
+            for i in 0..dim.0 * dim.1 {
+                new_grad.push(grad_output[i] / (parent_data[i] * ln_two));
+            }
+        }
+        UnaryOps::EXP2 => {
+            // 2^x * ln(2)
+            for i in 0..dim.0 * dim.1 {
+                new_grad.push(grad_output[i] * child_data[i] * ln_two);
+            }
+        }
+        UnaryOps::Softmax => {
+            // softmax is taken row-wise: s_j * (g_j - sum_k g_k * s_k)
+            for i in 0..dim.0 {
+                let mut dot = T::zero();
+                for j in 0..dim.1 {
+                    dot = dot + grad_output[i * dim.1 + j] * child_data[i * dim.1 + j];
+                }
+                for j in 0..dim.1 {
+                    let index = i * dim.1 + j;
+                    new_grad.push(child_data[index] * (grad_output[index] - dot));
+                }
+            }
+        }
+        op => {
+            return Err(NanogradError::unsupported(&format!("{:?}", op), "no backward rule"));
+        }
+    }
+    Ok(new_grad.into_boxed_slice())
+}
+
+fn vjp_reduce<T: TensorTrait<T>>(
+    dim: Dimensions,
+    grad_output: &[T],
+    op: ReduceOps
+) -> Result<DataArray<T>> {
+    match op {
+        // every input contributes once to the sum
+        ReduceOps::SUM => Ok(vec![grad_output[0]; dim.0 * dim.1].into_boxed_slice()),
+        ReduceOps::MAX => Err(NanogradError::unsupported("MAX", "the reduction has no backward rule")),
+    }
+}
@@ -1,4 +1,2 @@
 pub mod orchestrator;
-pub mod binary;
-pub mod unary;
-pub mod reduce;
\ No newline at end of file
+pub mod functional;
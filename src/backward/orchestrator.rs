@@ -1,41 +1,49 @@
 use crate::{
     Tensor,
     TensorTrait,
-    Ops,
-    backward::binary::backward_binary,
-    backward::unary::backward_unary,
-    backward::reduce::backward_reduce,
+    backward::functional::try_vjp_by_operation,
+    error::Result,
 };
 
+///
+/// Hand the gradient stored on `child` to its parents, storing theirs on them.
+///
+/// # Panics
+///
+/// * If `child` has no gradient, or its op has no backward rule.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, backward::orchestrator::backward_by_operation };
+///
+/// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, Some(true));
+/// let b: Tensor<f64> = Tensor::from_vec(vec![1.0, 0.0, 0.0, 1.0], (2, 2), None, Some(true));
+/// let mut c = a * b;
+/// c.set_gradient(Tensor::ones((2, 2), None, None));
+/// backward_by_operation(&mut c);
+///
+/// // the rules of a matrix product: dB = A^T * G
+/// let grad_b = c.right.as_ref().unwrap().get_gradient().unwrap();
+/// assert_eq!(grad_b.data().as_ref(), &[4.0, 4.0, 6.0, 6.0]);
+/// ```
 pub fn backward_by_operation<T: TensorTrait<T>>(child: &mut Tensor<T>) {
-    // control flow based on operation
-    // get operation
-    let parent = child.left.as_mut().unwrap();
-    let op = child.op;
-    let grad = child.gradient.as_mut().unwrap();
+    try_backward_by_operation(child).unwrap_or_else(|error| panic!("{}", error))
+}
 
-    match op {
-        Ops::BinaryOps(_) => {
-            println!("binary here!");
-            let parent_2 = child.right.as_mut().unwrap();
-            println!("binary here again!");
-            backward_binary(parent, parent_2, grad, op);
-        }
-        Ops::ReduceOps(_) => {
-            backward_reduce(parent, grad, op);
-        }
-        Ops::UnaryOps(_) => {
-            backward_unary(parent, grad, op);
-        }
-        // shouldn't need to implement these
-        Ops::TernaryOps(_) => {
-            panic!("Not implemented");
-        }
-        Ops::LoadOps(_) => {
-            panic!("Not implemented");
-        }
-        Ops::None => {
-            panic!("Not implemented");
-        }
+///
+/// Like `backward_by_operation`, but returns an `UnsupportedOp` error for ops without a backward rule.
+/// The gradient of `child` is handed to its parents with the rules of `vjp_by_operation`.
+pub fn try_backward_by_operation<T: TensorTrait<T>>(child: &mut Tensor<T>) -> Result<()> {
+    let grad = child.gradient.as_ref().unwrap().data().clone();
+    let (grad_left, grad_right) = try_vjp_by_operation(child, &grad)?;
+    if let (Some(left), Some(grad_left)) = (child.left.as_mut(), grad_left) {
+        let dim = left.dim();
+        left.set_gradient(Tensor::new(grad_left, dim, None, None));
+    }
+    if let (Some(right), Some(grad_right)) = (child.right.as_mut(), grad_right) {
+        let dim = right.dim();
+        right.set_gradient(Tensor::new(grad_right, dim, None, None));
     }
+    Ok(())
 }
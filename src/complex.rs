@@ -0,0 +1,305 @@
+use core::any::Any;
+use alloc::collections::BTreeMap;
+use alloc::{ vec::Vec, vec, boxed::Box };
+
+pub use num::Complex;
+
+use crate::{ Tensor, TensorTrait, Dimensions, DataArray, Device, LazyBuffer, Ops, NanogradError };
+use crate::autograd::{ Context, Function, GradientMap, apply, accumulate_gradients, try_accumulate_gradients, try_propagate, assign_leaf_gradients };
+use crate::autograd::cast::{ CastSource, CastOp };
+use crate::error::Result;
+use crate::types::ops::LoadOps;
+
+///
+/// The real and imaginary parts of a complex tensor, each a graph of real tensors.
+///
+/// Complex tensors are differentiated through their parts: every complex op is recorded as real
+/// ops on the parts, and a complex tensor carries its parts as its source. A complex leaf is split
+/// into two real leaves, the real part keeping the id of the leaf and the imaginary part taking
+/// `-id - 1`, so their gradients can be put back together.
+pub struct Parts<F: TensorTrait<F>> {
+    pub re: Tensor<F>,
+    pub im: Tensor<F>,
+}
+
+impl<F: TensorTrait<F>> CastSource<Complex<F>> for Parts<F> {
+    fn accumulate(&self, grad_output: &[Complex<F>], gradients: &mut GradientMap<Complex<F>>, targets: &[i32]) -> Result<()> {
+        let mut part_gradients: GradientMap<F> = BTreeMap::new();
+        try_accumulate_gradients(&self.re, grad_output.iter().map(|g| g.re).collect(), &mut part_gradients, targets)?;
+        try_accumulate_gradients(&self.im, grad_output.iter().map(|g| g.im).collect(), &mut part_gradients, targets)?;
+        for (id, gradient) in combine(part_gradients) {
+            match gradients.get_mut(&id) {
+                Some(existing) => {
+                    for (acc, g) in existing.iter_mut().zip(gradient.iter()) {
+                        *acc = *acc + *g;
+                    }
+                }
+                None => {
+                    gradients.insert(id, gradient);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn propagate(&mut self, grad_output: &[Complex<F>]) -> Result<()> {
+        for (part, grad_output) in [
+            (&mut self.re, grad_output.iter().map(|g| g.re).collect::<DataArray<F>>()),
+            (&mut self.im, grad_output.iter().map(|g| g.im).collect::<DataArray<F>>()),
+        ] {
+            let mut leaf_gradients: GradientMap<F> = BTreeMap::new();
+            try_propagate(part, grad_output, &mut leaf_gradients)?;
+            assign_leaf_gradients(part, &leaf_gradients);
+        }
+        Ok(())
+    }
+
+    fn source_id(&self) -> i32 {
+        self.re.unique_id
+    }
+
+    fn clone_source(&self) -> Box<dyn CastSource<Complex<F>>> {
+        Box::new(Parts { re: self.re.clone(), im: self.im.clone() })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The id of the real leaf holding the imaginary part of the complex leaf `id`.
+fn imaginary_id(id: i32) -> i32 {
+    -id - 1
+}
+
+/// Put the gradients of the parts of complex leaves back together, keyed by the complex leaf.
+fn combine<F: TensorTrait<F>>(part_gradients: GradientMap<F>) -> GradientMap<Complex<F>> {
+    let mut gradients: GradientMap<Complex<F>> = BTreeMap::new();
+    for (id, gradient) in part_gradients {
+        let (id, is_imaginary) = if id < 0 { (imaginary_id(id), true) } else { (id, false) };
+        let entry = gradients
+            .entry(id)
+            .or_insert_with(|| vec![Complex::new(F::zero(), F::zero()); gradient.len()].into_boxed_slice());
+        for (acc, g) in entry.iter_mut().zip(gradient.iter()) {
+            if is_imaginary {
+                acc.im = acc.im + *g;
+            } else {
+                acc.re = acc.re + *g;
+            }
+        }
+    }
+    gradients
+}
+
+///
+/// Split a complex tensor into the graphs of its real and imaginary parts.
+/// A complex leaf becomes two real leaves; see `Parts`.
+pub fn parts<F: TensorTrait<F>>(z: &Tensor<Complex<F>>) -> (Tensor<F>, Tensor<F>) {
+    if let Some(parts) = z.cast.as_ref().and_then(|cast| cast.source.as_any().downcast_ref::<Parts<F>>()) {
+        return (parts.re.clone(), parts.im.clone());
+    }
+    let dim: Dimensions = z.dim();
+    let requires_grad = Some(*z.requires_grad());
+    let mut re: Tensor<F> = Tensor::new(z.data().iter().map(|value| value.re).collect(), dim, None, requires_grad);
+    let mut im: Tensor<F> = Tensor::new(z.data().iter().map(|value| value.im).collect(), dim, None, requires_grad);
+    re.unique_id = z.unique_id;
+    im.unique_id = imaginary_id(z.unique_id);
+    (re, im)
+}
+
+///
+/// Build a complex tensor from its real and imaginary parts. Differentiable in both parts.
+///
+/// # Panics
+///
+/// * If the parts have different dimensions.
+pub fn from_parts<F: TensorTrait<F>>(re: Tensor<F>, im: Tensor<F>) -> Tensor<Complex<F>> {
+    try_from_parts(re, im).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Build a complex tensor from its parts, failing instead of panicking on mismatched dimensions.
+pub fn try_from_parts<F: TensorTrait<F>>(re: Tensor<F>, im: Tensor<F>) -> Result<Tensor<Complex<F>>> {
+    if re.dim() != im.dim() {
+        return Err(NanogradError::shape_mismatch("complex", re.dim(), im.dim()));
+    }
+    let dim: Dimensions = re.dim();
+    let data: DataArray<Complex<F>> = re
+        .data()
+        .iter()
+        .zip(im.data().iter())
+        .map(|(re, im)| Complex::new(*re, *im))
+        .collect();
+    let lazy_data: LazyBuffer<Complex<F>> = LazyBuffer::try_new(data, dim, Some(Device::CPU))?;
+    let mut z = Tensor::_build_lazy(lazy_data, Some(true), Some(Ops::LoadOps(LoadOps::CAST)), None, None);
+    z.cast = Some(CastOp { source: Box::new(Parts { re, im }) });
+    Ok(z)
+}
+
+/// The real part of a complex tensor, as a real tensor in the same graph.
+pub fn real<F: TensorTrait<F>>(z: &Tensor<Complex<F>>) -> Tensor<F> {
+    parts(z).0
+}
+
+/// The imaginary part of a complex tensor, as a real tensor in the same graph.
+pub fn imag<F: TensorTrait<F>>(z: &Tensor<Complex<F>>) -> Tensor<F> {
+    parts(z).1
+}
+
+/// The complex conjugate, element-wise.
+pub fn conj<F: TensorTrait<F>>(z: &Tensor<Complex<F>>) -> Tensor<Complex<F>> {
+    let (re, im) = parts(z);
+    let zeros: Tensor<F> = Tensor::zeros(im.dim(), None, None);
+    from_parts(re, zeros - im)
+}
+
+/// The modulus `sqrt(re² + im²)`, element-wise. Its gradient is `z / |z|`, and zero at zero.
+pub fn abs<F: TensorTrait<F>>(z: &Tensor<Complex<F>>) -> Tensor<F> {
+    let (re, im) = parts(z);
+    apply(Modulus, vec![re, im])
+}
+
+/// Add complex tensors of the same dimensions.
+pub fn add<F: TensorTrait<F>>(a: &Tensor<Complex<F>>, b: &Tensor<Complex<F>>) -> Tensor<Complex<F>> {
+    let ((a_re, a_im), (b_re, b_im)) = (parts(a), parts(b));
+    from_parts(a_re + b_re, a_im + b_im)
+}
+
+/// Subtract complex tensors of the same dimensions.
+pub fn sub<F: TensorTrait<F>>(a: &Tensor<Complex<F>>, b: &Tensor<Complex<F>>) -> Tensor<Complex<F>> {
+    let ((a_re, a_im), (b_re, b_im)) = (parts(a), parts(b));
+    from_parts(a_re - b_re, a_im - b_im)
+}
+
+/// Multiply complex tensors of the same dimensions element-wise.
+pub fn mul<F: TensorTrait<F>>(a: &Tensor<Complex<F>>, b: &Tensor<Complex<F>>) -> Tensor<Complex<F>> {
+    let ((a_re, a_im), (b_re, b_im)) = (parts(a), parts(b));
+    let product = |x: &Tensor<F>, y: &Tensor<F>| apply(Hadamard, vec![x.clone(), y.clone()]);
+    let re = product(&a_re, &b_re) - product(&a_im, &b_im);
+    let im = product(&a_re, &b_im) + product(&a_im, &b_re);
+    from_parts(re, im)
+}
+
+/// Multiply complex matrices.
+pub fn matmul<F: TensorTrait<F>>(a: &Tensor<Complex<F>>, b: &Tensor<Complex<F>>) -> Tensor<Complex<F>> {
+    let ((a_re, a_im), (b_re, b_im)) = (parts(a), parts(b));
+    let re = a_re.clone() * b_re.clone() - a_im.clone() * b_im.clone();
+    let im = a_re * b_im + a_im * b_re;
+    from_parts(re, im)
+}
+
+///
+/// Compute the gradient of a real-valued function of complex tensors.
+///
+/// Gradients follow PyTorch's convention for real losses: the gradient of `L` at `z = x + iy` is
+/// `∂L/∂x + i ∂L/∂y`, the conjugate Wirtinger derivative times two, so `z - lr * grad` is a
+/// step of gradient descent.
+///
+/// # Arguments
+///
+/// * `f` - The function to differentiate. Receives copies of `inputs` in the same order.
+/// * `inputs` - The points at which to evaluate the gradient.
+///
+/// # Returns
+///
+/// One complex gradient per input. Non-scalar outputs are seeded with ones.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::transformation::sum };
+/// use nanograd::complex::{ self, Complex };
+///
+/// let z: Tensor<Complex<f64>> = Tensor::from_vec(vec![Complex::new(3.0, 4.0), Complex::new(0.0, -2.0)], (1, 2), None, None);
+/// let w: Tensor<Complex<f64>> = Tensor::from_vec(vec![Complex::new(1.0, 1.0), Complex::new(2.0, 0.0)], (1, 2), None, None);
+///
+/// // |z| has gradient z / |z|
+/// let grads = complex::grad(|x| sum(complex::abs(&x[0])), &[z.clone()]);
+/// assert_eq!(grads[0].data().as_ref(), &[Complex::new(0.6, 0.8), Complex::new(0.0, -1.0)]);
+///
+/// // Re(w z) has gradient conj(w), Im(conj(z)) has gradient -i
+/// let grads = complex::grad(|x| sum(complex::real(&complex::mul(&x[0], &x[1]))), &[z.clone(), w.clone()]);
+/// assert_eq!(grads[0].data().as_ref(), &[Complex::new(1.0, -1.0), Complex::new(2.0, 0.0)]);
+/// assert_eq!(grads[1].data().as_ref(), &[Complex::new(3.0, -4.0), Complex::new(0.0, 2.0)]);
+///
+/// // the same for a matrix product
+/// let column: Tensor<Complex<f64>> = Tensor::from_vec(w.data().to_vec(), (2, 1), None, None);
+/// let grads = complex::grad(|x| sum(complex::real(&complex::matmul(&x[0], &x[1]))), &[z.clone(), column]);
+/// assert_eq!(grads[0].data().as_ref(), &[Complex::new(1.0, -1.0), Complex::new(2.0, 0.0)]);
+///
+/// let grads = complex::grad(|x| sum(complex::imag(&complex::conj(&x[0]))), &[z]);
+/// assert_eq!(grads[0].data().as_ref(), &[Complex::new(0.0, -1.0), Complex::new(0.0, -1.0)]);
+/// ```
+pub fn grad<F, G>(f: G, inputs: &[Tensor<Complex<F>>]) -> Vec<Tensor<Complex<F>>>
+    where F: TensorTrait<F>, G: Fn(Vec<Tensor<Complex<F>>>) -> Tensor<F>
+{
+    let output = f(inputs.to_vec());
+    let dim: Dimensions = output.dim();
+    let mut part_gradients: GradientMap<F> = BTreeMap::new();
+    accumulate_gradients(&output, vec![F::one(); dim.0 * dim.1].into_boxed_slice(), &mut part_gradients, &[]);
+    let gradients = combine(part_gradients);
+    inputs
+        .iter()
+        .map(|input| {
+            let dim: Dimensions = input.dim();
+            let gradient: DataArray<Complex<F>> = match gradients.get(&input.unique_id) {
+                Some(gradient) => gradient.clone(),
+                None => vec![Complex::new(F::zero(), F::zero()); dim.0 * dim.1].into_boxed_slice(),
+            };
+            Tensor::new(gradient, dim, None, None)
+        })
+        .collect()
+}
+
+/// Element-wise product of two real tensors.
+struct Hadamard;
+
+impl<F: TensorTrait<F>> Function<F> for Hadamard {
+    fn forward(&self, ctx: &mut Context<F>, inputs: &[Tensor<F>]) -> Tensor<F> {
+        if inputs[0].dim() != inputs[1].dim() {
+            panic!("{}", NanogradError::shape_mismatch("complex mul", inputs[0].dim(), inputs[1].dim()));
+        }
+        ctx.save_for_backward(&inputs[0]);
+        ctx.save_for_backward(&inputs[1]);
+        let data: Vec<F> = inputs[0].data().iter().zip(inputs[1].data().iter()).map(|(x, y)| *x * *y).collect();
+        Tensor::from_vec(data, inputs[0].dim(), None, None)
+    }
+
+    fn backward(&self, ctx: &Context<F>, grad_output: &Tensor<F>) -> Vec<Option<Tensor<F>>> {
+        let saved = ctx.saved_tensors();
+        let scale = |other: &Tensor<F>| {
+            let data: Vec<F> = other.data().iter().zip(grad_output.data().iter()).map(|(y, g)| *y * *g).collect();
+            Some(Tensor::from_vec(data, other.dim(), None, None))
+        };
+        vec![scale(&saved[1]), scale(&saved[0])]
+    }
+}
+
+/// `sqrt(re² + im²)` of the real and imaginary parts of a complex tensor.
+struct Modulus;
+
+impl<F: TensorTrait<F>> Function<F> for Modulus {
+    fn forward(&self, ctx: &mut Context<F>, inputs: &[Tensor<F>]) -> Tensor<F> {
+        let data: Vec<F> = inputs[0].data().iter().zip(inputs[1].data().iter()).map(|(re, im)| re.hypot(*im)).collect();
+        let result = Tensor::from_vec(data, inputs[0].dim(), None, None);
+        ctx.save_for_backward(&inputs[0]);
+        ctx.save_for_backward(&inputs[1]);
+        ctx.save_for_backward(&result);
+        result
+    }
+
+    fn backward(&self, ctx: &Context<F>, grad_output: &Tensor<F>) -> Vec<Option<Tensor<F>>> {
+        let saved = ctx.saved_tensors();
+        let modulus = saved[2].data();
+        let part = |values: &Tensor<F>| {
+            let data: Vec<F> = values
+                .data()
+                .iter()
+                .zip(modulus.iter())
+                .zip(grad_output.data().iter())
+                .map(|((x, r), g)| if r.is_zero() { F::zero() } else { *g * *x / *r })
+                .collect();
+            Some(Tensor::from_vec(data, values.dim(), None, None))
+        };
+        vec![part(&saved[0]), part(&saved[1])]
+    }
+}
@@ -0,0 +1,174 @@
+use core::fmt;
+use alloc::string::{ String, ToString };
+
+use crate::{ Device, Dimensions };
+use crate::types::shape::Shape;
+#[cfg(feature = "std")]
+use crate::jit::TraceError;
+
+/// The result of a fallible nanograd operation.
+pub type Result<T> = core::result::Result<T, NanogradError>;
+
+///
+/// Everything that can go wrong in nanograd. The `try_` variants of constructors and ops return it;
+/// their panicking counterparts panic with its message.
+///
+/// * `ShapeMismatch` - The operands of an op have shapes the op cannot combine.
+/// * `SymbolicShapeMismatch` - Like `ShapeMismatch`, for shapes whose variables are not bound yet.
+/// * `DataLength` - The data of a tensor does not fill its dimensions.
+/// * `UnsupportedOp` - The op, or the op with these arguments, has no implementation.
+/// * `DeviceMismatch` - The operands of an op live on different devices.
+/// * `UnsupportedDevice` - No backend is registered for the device.
+/// * `DTypeConversion` - A value cannot be represented in the target element type.
+/// * `IndexOutOfBounds` - An index tensor points past the end of the axis it indexes.
+/// * `GradientCount` - A different number of gradients was given than there are outputs to seed.
+/// * `GraphFreed` - A backward pass reached a tensor whose data an earlier pass released.
+/// * `Io` - Reading or writing a file failed. Only with the `std` feature.
+/// * `Trace` - A compiled function was called with inputs it was not traced for. Only with the `std` feature.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, NanogradError };
+///
+/// let a: Tensor<f64> = Tensor::ones((2, 3), None, None);
+/// let b: Tensor<f64> = Tensor::ones((2, 3), None, None);
+///
+/// match a.try_mul(b) {
+///     Err(NanogradError::ShapeMismatch { op, left, right }) => {
+///         assert_eq!(op, "MUL");
+///         assert_eq!((left, right), ((2, 3), (2, 3)));
+///     }
+///     _ => panic!("expected a shape mismatch"),
+/// }
+///
+/// assert!(Tensor::<f64>::try_from_vec(vec![1.0, 2.0, 3.0], (2, 2), None, None).is_err());
+/// ```
+#[derive(Debug)]
+pub enum NanogradError {
+    ShapeMismatch {
+        op: String,
+        left: Dimensions,
+        right: Dimensions,
+    },
+    SymbolicShapeMismatch {
+        op: String,
+        left: Shape,
+        right: Shape,
+    },
+    DataLength {
+        dim: Dimensions,
+        len: usize,
+    },
+    UnsupportedOp {
+        op: String,
+        reason: String,
+    },
+    DeviceMismatch {
+        left: Device,
+        right: Device,
+    },
+    UnsupportedDevice(Device),
+    DTypeConversion {
+        from: &'static str,
+        to: &'static str,
+        value: String,
+    },
+    IndexOutOfBounds {
+        index: String,
+        len: usize,
+    },
+    GradientCount {
+        expected: usize,
+        found: usize,
+    },
+    GraphFreed,
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    #[cfg(feature = "std")]
+    Trace(TraceError),
+}
+
+impl NanogradError {
+    /// A shape mismatch in the op `op`.
+    pub fn shape_mismatch(op: &str, left: Dimensions, right: Dimensions) -> Self {
+        NanogradError::ShapeMismatch { op: op.to_string(), left, right }
+    }
+
+    /// An op that is not implemented, with why.
+    pub fn unsupported(op: &str, reason: &str) -> Self {
+        NanogradError::UnsupportedOp { op: op.to_string(), reason: reason.to_string() }
+    }
+
+    /// A value of type `F` that cannot be represented as a `U`.
+    pub fn conversion<F, U>(value: impl fmt::Display) -> Self {
+        NanogradError::DTypeConversion {
+            from: core::any::type_name::<F>(),
+            to: core::any::type_name::<U>(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for NanogradError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NanogradError::ShapeMismatch { op, left, right } => {
+                write!(f, "Shape mismatch in {}: {}x{} and {}x{}", op, left.0, left.1, right.0, right.1)
+            }
+            NanogradError::SymbolicShapeMismatch { op, left, right } => {
+                write!(f, "Shape mismatch in {}: {} and {}", op, left, right)
+            }
+            NanogradError::DataLength { dim, len } => {
+                write!(f, "Data length does not match dimensions: {} values for {}x{}", len, dim.0, dim.1)
+            }
+            NanogradError::UnsupportedOp { op, reason } => write!(f, "{} is not supported: {}", op, reason),
+            NanogradError::DeviceMismatch { left, right } => {
+                write!(f, "Tensors live on different devices: {:?} and {:?}", left, right)
+            }
+            NanogradError::UnsupportedDevice(device) => write!(f, "No backend registered for {:?}", device),
+            NanogradError::DTypeConversion { from, to, value } => {
+                write!(f, "Cannot convert {} from {} to {}", value, from, to)
+            }
+            NanogradError::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {} is out of bounds for an axis of length {}", index, len)
+            }
+            NanogradError::GradientCount { expected, found } => {
+                write!(f, "Expected {} gradients but {} were given", expected, found)
+            }
+            NanogradError::GraphFreed => {
+                write!(f, "The graph was freed by a backward pass; set retain_graph to keep it for another pass")
+            }
+            #[cfg(feature = "std")]
+            NanogradError::Io(error) => write!(f, "I/O error: {}", error),
+            #[cfg(feature = "std")]
+            NanogradError::Trace(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl core::error::Error for NanogradError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            NanogradError::Io(error) => Some(error),
+            #[cfg(feature = "std")]
+            NanogradError::Trace(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for NanogradError {
+    fn from(error: std::io::Error) -> Self {
+        NanogradError::Io(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<TraceError> for NanogradError {
+    fn from(error: TraceError) -> Self {
+        NanogradError::Trace(error)
+    }
+}
@@ -47,5 +47,4 @@ pub fn forward_binary<T: TensorTrait<T>>(
             panic!("Not implemented");
         }
     }
-    println!("binary returning");
 }
@@ -3,3 +3,4 @@ pub mod binary;
 pub mod unary;
 pub mod reduce;
 pub mod utils;
+pub mod gemm;
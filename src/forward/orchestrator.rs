@@ -1,3 +1,5 @@
+use alloc::format;
+
 use crate::{
     Tensor,
     TensorTrait,
@@ -5,18 +7,27 @@ use crate::{
     forward::binary::forward_binary,
     forward::unary::forward_unary,
     forward::reduce::forward_reduce,
+    NanogradError,
+    error::Result,
 };
 
 pub fn forward_by_operation<T: TensorTrait<T>>(child: &mut Tensor<T>) {
+    try_forward_by_operation(child).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Like `forward_by_operation`, but returns an `UnsupportedOp` error for ops without a forward rule,
+/// or a tensor without the parents or gradient its op needs.
+pub fn try_forward_by_operation<T: TensorTrait<T>>(child: &mut Tensor<T>) -> Result<()> {
     // control flow based on operation
     // get operation
-    let parent = child.left.as_mut().unwrap();
     let op = child.op;
-    let grad = child.gradient.as_mut().unwrap();
+    let missing = |what: &str| NanogradError::unsupported(&format!("{:?}", op), &format!("the tensor has no {}", what));
+    let parent = child.left.as_mut().ok_or_else(|| missing("left parent"))?;
+    let grad = child.gradient.as_mut().ok_or_else(|| missing("gradient"))?;
 
     match op {
         Ops::BinaryOps(_) => {
-            let parent_2 = child.right.as_mut().unwrap();
+            let parent_2 = child.right.as_mut().ok_or_else(|| missing("right parent"))?;
             forward_binary(parent, parent_2, grad, op);
         }
         Ops::ReduceOps(_) => {
@@ -26,14 +37,9 @@ pub fn forward_by_operation<T: TensorTrait<T>>(child: &mut Tensor<T>) {
             forward_unary(parent, grad);
         }
         // shouldn't need to implement these
-        Ops::TernaryOps(_) => {
-            panic!("Not implemented");
-        }
-        Ops::LoadOps(_) => {
-            panic!("Not implemented");
-        }
-        Ops::None => {
-            panic!("Not implemented");
+        Ops::TernaryOps(_) | Ops::LoadOps(_) | Ops::None => {
+            return Err(NanogradError::unsupported(&format!("{:?}", op), "no forward rule"));
         }
     }
+    Ok(())
 }
@@ -1,4 +1,5 @@
-use std::f64::consts::E;
+use core::f64::consts::E;
+use alloc::vec::Vec;
 
 use crate::{
     Tensor,
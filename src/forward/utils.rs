@@ -1,9 +1,22 @@
+use alloc::{ vec::Vec, boxed::Box };
+
 use crate::{ TensorTrait, Dimensions, helpers::is_valid_matrix_multiplication, DataArray };
+use crate::forward::gemm::gemm;
 
 pub fn mul_data<T: TensorTrait<T>>(
-    a_data: &Box<[T]>,
+    a_data: &[T],
     a_dim: Dimensions,
-    b_data: &Box<[T]>,
+    b_data: &[T],
+    b_dim: Dimensions
+) -> DataArray<T> {
+    gemm(a_data, a_dim, b_data, b_dim)
+}
+
+/// Reference matrix multiplication with a plain i-j-k loop. Used to check and benchmark `gemm`.
+pub fn naive_mul_data<T: TensorTrait<T>>(
+    a_data: &[T],
+    a_dim: Dimensions,
+    b_data: &[T],
     b_dim: Dimensions
 ) -> DataArray<T> {
     if !is_valid_matrix_multiplication(a_dim, b_dim) {
@@ -15,7 +28,7 @@ pub fn mul_data<T: TensorTrait<T>>(
         for j in 0..b_dim.1 {
             let mut sum = T::zero();
             for k in 0..b_dim.0 {
-                let index_a = i * a_dim.0 + k;
+                let index_a = i * a_dim.1 + k;
                 let index_b = k * b_dim.1 + j;
                 sum = sum + a_data[index_a] * b_data[index_b];
             }
@@ -24,7 +37,7 @@ pub fn mul_data<T: TensorTrait<T>>(
             new_data.push(sum);
         }
     }
-    return new_data.into_boxed_slice();
+    new_data.into_boxed_slice()
 }
 
 pub fn add_data<T: TensorTrait<T>>(
@@ -39,7 +52,7 @@ pub fn add_data<T: TensorTrait<T>>(
     let mut new_data = Vec::with_capacity(a_dim.0 * a_dim.1);
     for i in 0..a_dim.0 {
         for j in 0..a_dim.1 {
-            let index = i * a_dim.0 + j;
+            let index = i * a_dim.1 + j;
             new_data.push(a_data[index] + b_data[index]);
         }
     }
@@ -58,7 +71,7 @@ pub fn subtract_data<T: TensorTrait<T>>(
     let mut new_data = Vec::with_capacity(a_dim.0 * a_dim.1);
     for i in 0..a_dim.0 {
         for j in 0..a_dim.1 {
-            let index = i * a_dim.0 + j;
+            let index = i * a_dim.1 + j;
             new_data.push(a_data[index] - b_data[index]);
         }
     }
@@ -0,0 +1,163 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::Write;
+
+use crate::{ Tensor, TensorTrait, Ops };
+use crate::graph::view::{ GraphNode, GraphView };
+use crate::graph::visitor::Visitor;
+
+/// Number of values shown before a data summary is cut off.
+const SUMMARY_LENGTH: usize = 4;
+
+///
+/// A Graphviz DOT graph built one node and edge at a time.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::graph::dot::DotGraph;
+///
+/// let mut graph = DotGraph::new("example");
+/// graph.add_node("a", &["Leaf".to_string(), "1x1".to_string()]);
+/// graph.add_node("b", &["Tanh".to_string()]);
+/// graph.add_edge("a", "b");
+///
+/// assert!(graph.render().contains("\"a\" -> \"b\";"));
+/// ```
+pub struct DotGraph {
+    name: String,
+    nodes: Vec<String>,
+    edges: Vec<String>,
+}
+
+impl DotGraph {
+    pub fn new(name: &str) -> Self {
+        DotGraph { name: name.to_string(), nodes: Vec::new(), edges: Vec::new() }
+    }
+
+    ///
+    /// Add a node drawn as a record with one row per field.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A name that is unique within the graph.
+    /// * `fields` - The rows of the record, top to bottom.
+    pub fn add_node(&mut self, id: &str, fields: &[String]) {
+        let rows: Vec<String> = fields
+            .iter()
+            .map(|field| escape(field))
+            .collect();
+        self.nodes.push(format!("    \"{}\" [label=\"{{{}}}\"];", id, rows.join(" | ")));
+    }
+
+    /// Add an edge from a parent to the node it feeds into.
+    pub fn add_edge(&mut self, from: &str, to: &str) {
+        self.edges.push(format!("    \"{}\" -> \"{}\";", from, to));
+    }
+
+    /// Render the graph as DOT source.
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            format!("digraph {} {{", self.name),
+            "    rankdir=LR;".to_string(),
+            "    node [shape=record, fontname=\"monospace\"];".to_string()
+        ];
+        lines.extend(self.nodes.iter().cloned());
+        lines.extend(self.edges.iter().cloned());
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    /// Write the rendered graph to a `.dot` file.
+    pub fn write(&self, path: &str) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.render().as_bytes())
+    }
+}
+
+/// Escape the characters that have a meaning inside record labels.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+///
+/// Summarize a slice of values, showing the first few and the total count.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::graph::dot::summarize;
+///
+/// assert_eq!(summarize(&[1.0, 2.0]), "[1, 2]");
+/// assert_eq!(summarize(&[1.0, 2.0, 3.0, 4.0, 5.0]), "[1, 2, 3, 4, … (5 values)]");
+/// ```
+pub fn summarize<T: Display>(values: &[T]) -> String {
+    let shown: Vec<String> = values
+        .iter()
+        .take(SUMMARY_LENGTH)
+        .map(|value| format!("{:.4}", value).trim_end_matches('0').trim_end_matches('.').to_string())
+        .collect();
+    if values.len() > SUMMARY_LENGTH {
+        format!("[{}, … ({} values)]", shown.join(", "), values.len())
+    } else {
+        format!("[{}]", shown.join(", "))
+    }
+}
+
+/// Name shown for an op in a rendered graph.
+pub fn op_name(op: &Ops) -> String {
+    match op {
+        Ops::None => "Leaf".to_string(),
+        Ops::UnaryOps(op) => format!("{:?}", op),
+        Ops::BinaryOps(op) => format!("{:?}", op),
+        Ops::ReduceOps(op) => format!("{:?}", op),
+        Ops::TernaryOps(op) => format!("{:?}", op),
+        Ops::LoadOps(op) => format!("{:?}", op),
+    }
+}
+
+///
+/// Build the DOT graph of a tensor and everything it was computed from.
+/// Copies of the same tensor (same unique id) are drawn once.
+///
+/// # Arguments
+///
+/// * `root` - The tensor to start from.
+pub fn tensor_to_dot<T: TensorTrait<T>>(root: &Tensor<T>) -> DotGraph {
+    let mut visitor = DotVisitor { graph: DotGraph::new("tensor_graph") };
+    GraphView::new(root).accept(&mut visitor);
+    visitor.graph
+}
+
+/// Draws every visited node along with the edges from its parents.
+struct DotVisitor {
+    graph: DotGraph,
+}
+
+impl<T: TensorTrait<T>> Visitor<T> for DotVisitor {
+    fn visit(&mut self, node: &GraphNode<'_, T>, view: &GraphView<'_, T>) {
+        let tensor = node.tensor;
+        let id = format!("t{}", tensor.unique_id);
+        let mut fields = vec![op_name(&node.op), format!("{}x{}", node.dim.0, node.dim.1)];
+        if let Some(label) = &tensor.label {
+            fields.push(label.clone());
+        }
+        fields.push(format!("data: {}", summarize(tensor.data())));
+        if let Some(gradient) = tensor.get_gradient() {
+            fields.push(format!("grad: {}", summarize(gradient.data())));
+        }
+        fields.push(format!("requires_grad: {}", tensor.requires_grad()));
+        self.graph.add_node(&id, &fields);
+        for parent in &node.parents {
+            let parent_id = format!("t{}", view.node(*parent).tensor.unique_id);
+            self.graph.add_edge(&parent_id, &id);
+        }
+    }
+}
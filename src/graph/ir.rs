@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{ Tensor, TensorTrait, Ops, DataArray, Dimensions, Device };
+use crate::autograd::function::{ Context, CustomOp };
+use crate::backend::{ get_backend, Storage };
+use crate::graph::dot::op_name;
+use crate::types::ops::{ BinaryOps, LoadOps };
+use crate::types::shape::{ infer_shape, Bindings, Dim, Shape };
+
+/// What a node of a `Graph` is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// A leaf whose data is supplied each time the graph is evaluated.
+    Input,
+    /// A leaf that requires gradients.
+    Parameter,
+    /// A leaf whose data never changes.
+    Constant,
+    /// The result of an op on other nodes.
+    Op,
+}
+
+///
+/// A node of a `Graph`.
+///
+/// # Fields
+/// * `kind` - Whether the node is a leaf or an op
+/// * `op` - The op that computes the node, `Ops::None` for leaves
+/// * `inputs` - Indices of the nodes the op reads, left before right
+/// * `dim` - The dimensions of the node
+/// * `shape` - The dimensions of the node in terms of the variables of the input shapes
+/// * `arg` - The scalar argument of the op, e.g. the threshold of `MAX`
+/// * `data` - The data of parameters and constants, and the example data of inputs
+/// * `custom` - The function of a custom op
+/// * `id` - The unique id of the tensor the node was recorded from
+#[derive(Clone)]
+pub struct Node<T: TensorTrait<T>> {
+    pub kind: NodeKind,
+    pub op: Ops,
+    pub inputs: Vec<usize>,
+    pub dim: Dimensions,
+    pub shape: Shape,
+    pub arg: Option<T>,
+    pub data: Option<DataArray<T>>,
+    pub custom: Option<CustomOp<T>>,
+    pub id: i32,
+}
+
+///
+/// An owned, index-based copy of a tensor graph that can be rewritten and evaluated again on new
+/// inputs. Nodes are kept in topological order and copies of the same tensor become one node.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, graph::ir::Graph };
+///
+/// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+/// let w: Tensor<f64> = Tensor::ones((2, 2), None, Some(true));
+/// let y = x.clone() * w + 1.0;
+///
+/// let graph = Graph::from_tensors(&[&y], &[&x]);
+/// let outputs = graph.evaluate(&[vec![0.0, 1.0, 0.0, 1.0].into_boxed_slice()]);
+///
+/// assert_eq!(outputs[0], vec![2.0, 2.0, 2.0, 2.0].into_boxed_slice());
+/// ```
+#[derive(Clone)]
+pub struct Graph<T: TensorTrait<T>> {
+    nodes: Vec<Node<T>>,
+    inputs: Vec<usize>,
+    outputs: Vec<usize>,
+}
+
+impl<T: TensorTrait<T>> Graph<T> {
+    /// Record the graph below a single tensor. Leaves that do not require gradients become constants.
+    pub fn from_tensor(root: &Tensor<T>) -> Self {
+        Self::from_tensors(&[root], &[])
+    }
+
+    ///
+    /// Record the graph below a set of output tensors.
+    ///
+    /// # Arguments
+    ///
+    /// * `outputs` - The tensors to compute, in order.
+    /// * `inputs` - Leaves whose data is supplied on every evaluation, in order. Other leaves that
+    ///   require gradients become parameters and the rest become constants.
+    pub fn from_tensors(outputs: &[&Tensor<T>], inputs: &[&Tensor<T>]) -> Self {
+        let input_ids: Vec<i32> = inputs
+            .iter()
+            .map(|input| input.unique_id)
+            .collect();
+        let mut graph = Graph { nodes: Vec::new(), inputs: Vec::new(), outputs: Vec::new() };
+        let mut index: HashMap<i32, usize> = HashMap::new();
+        for output in outputs {
+            let output = graph.record(output, &input_ids, &mut index);
+            graph.outputs.push(output);
+        }
+        graph.inputs = input_ids
+            .iter()
+            .map(|id| {
+                match index.get(id) {
+                    Some(input) => *input,
+                    None => {
+                        // an input the outputs do not depend on still takes its place in the signature
+                        let tensor = inputs.iter().find(|input| input.unique_id == *id).unwrap();
+                        graph.push_leaf(tensor, NodeKind::Input)
+                    }
+                }
+            })
+            .collect();
+        graph
+    }
+
+    fn record(&mut self, tensor: &Tensor<T>, input_ids: &[i32], index: &mut HashMap<i32, usize>) -> usize {
+        if let Some(node) = index.get(&tensor.unique_id) {
+            return *node;
+        }
+        let node = match (&tensor.left, tensor.op) {
+            (Some(left), op) if op != Ops::None => {
+                let mut inputs = vec![self.record(left, input_ids, index)];
+                if let Some(right) = &tensor.right {
+                    inputs.push(self.record(right, input_ids, index));
+                }
+                self.nodes.push(Node {
+                    kind: NodeKind::Op,
+                    op,
+                    inputs,
+                    dim: tensor.dim(),
+                    shape: Shape::from(tensor.dim()),
+                    arg: tensor.arg,
+                    data: None,
+                    custom: tensor.custom.clone(),
+                    id: tensor.unique_id,
+                });
+                self.nodes.len() - 1
+            }
+            _ => {
+                let kind = if input_ids.contains(&tensor.unique_id) {
+                    NodeKind::Input
+                } else if *tensor.requires_grad() {
+                    NodeKind::Parameter
+                } else {
+                    NodeKind::Constant
+                };
+                self.push_leaf(tensor, kind)
+            }
+        };
+        index.insert(tensor.unique_id, node);
+        node
+    }
+
+    fn push_leaf(&mut self, tensor: &Tensor<T>, kind: NodeKind) -> usize {
+        self.nodes.push(Node {
+            kind,
+            op: Ops::None,
+            inputs: Vec::new(),
+            dim: tensor.dim(),
+            shape: Shape::from(tensor.dim()),
+            arg: None,
+            data: Some(tensor.lazy_data.storage().to_host()),
+            custom: None,
+            id: tensor.unique_id,
+        });
+        self.nodes.len() - 1
+    }
+
+    pub fn nodes(&self) -> &[Node<T>] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> &Node<T> {
+        &self.nodes[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Indices of the input nodes, in the order their data is passed to `evaluate`.
+    pub fn inputs(&self) -> &[usize] {
+        &self.inputs
+    }
+
+    /// Indices of the output nodes, in the order `evaluate` returns them.
+    pub fn outputs(&self) -> &[usize] {
+        &self.outputs
+    }
+
+    /// Choose which nodes are outputs. Passes keep only what the outputs depend on.
+    pub fn set_outputs(&mut self, outputs: Vec<usize>) {
+        self.outputs = outputs;
+    }
+
+    /// The shape each input must have, in the order their data is passed to `evaluate`.
+    pub fn input_shapes(&self) -> Vec<Shape> {
+        self.inputs
+            .iter()
+            .map(|input| self.nodes[*input].shape.clone())
+            .collect()
+    }
+
+    /// Whether any node has a shape that depends on a variable.
+    pub fn is_symbolic(&self) -> bool {
+        self.nodes.iter().any(|node| node.shape.is_symbolic())
+    }
+
+    ///
+    /// Give the inputs shapes with variables, e.g. a `batch` number of rows, and infer the shape of
+    /// every other node from them. Constants are only as large as the traced inputs, like the
+    /// one `x + 1.0` builds or the column of ones `Linear` spreads its bias with. A constant whose
+    /// rows (or columns) are all the same takes a variable for them when an op needs it to, through
+    /// any chain of ops.
+    ///
+    /// # Arguments
+    ///
+    /// * `shapes` - One shape per input, which the dimensions the graph was recorded with must fit.
+    ///
+    /// # Panics
+    ///
+    /// * If a recorded input does not fit its shape, or the shapes do not fit the ops.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, graph::ir::Graph, types::shape::{ Bindings, Dim, Shape } };
+    ///
+    /// let x: Tensor<f64> = Tensor::ones((4, 3), None, None);
+    /// let w: Tensor<f64> = Tensor::ones((3, 2), None, Some(true));
+    /// let y = x.clone() * w + 1.0;
+    ///
+    /// let mut graph = Graph::from_tensors(&[&y], &[&x]);
+    /// graph.set_input_shapes(&[Shape(Dim::var("batch"), Dim::from(3))]);
+    /// assert_eq!(graph.node(graph.outputs()[0]).shape, Shape(Dim::var("batch"), Dim::from(2)));
+    ///
+    /// let mut bindings = Bindings::new();
+    /// bindings.insert("batch".to_string(), 1);
+    /// let outputs = graph.bind(&bindings).evaluate(&[vec![1.0, 2.0, 3.0].into_boxed_slice()]);
+    /// assert_eq!(outputs[0], vec![7.0, 7.0].into_boxed_slice());
+    /// ```
+    pub fn set_input_shapes(&mut self, shapes: &[Shape]) {
+        if shapes.len() != self.inputs.len() {
+            panic!("Graph takes {} inputs but {} shapes were given", self.inputs.len(), shapes.len());
+        }
+        // the sizes the graph was recorded with
+        let mut recorded = Bindings::new();
+        for (input, shape) in self.inputs.clone().into_iter().zip(shapes.iter()) {
+            let node = &mut self.nodes[input];
+            if !shape.unify(node.dim, &mut recorded) {
+                panic!("Input %{} was recorded as {}x{}, which does not fit {}", input, node.dim.0, node.dim.1, shape);
+            }
+            node.shape = shape.clone();
+        }
+        // widening a constant changes the shapes of ops that were already inferred, so start over
+        while self.infer_shapes(&recorded) {}
+    }
+
+    /// Infer the shape of every op in order. Returns whether a constant was widened on the way.
+    fn infer_shapes(&mut self, recorded: &Bindings) -> bool {
+        for index in 0..self.nodes.len() {
+            if self.nodes[index].kind != NodeKind::Op {
+                continue;
+            }
+            let node = &self.nodes[index];
+            let shapes: Vec<Shape> = node.inputs
+                .iter()
+                .map(|input| self.nodes[*input].shape.clone())
+                .collect();
+            if let (Ops::BinaryOps(op), [left, right]) = (node.op, node.inputs.clone().as_slice()) {
+                // the shape each operand needs for the other one to fit
+                let (left_shape, right_shape) = match op {
+                    BinaryOps::MUL => (
+                        Shape(shapes[0].0.clone(), shapes[1].0.clone()),
+                        Shape(shapes[0].1.clone(), shapes[1].1.clone()),
+                    ),
+                    _ => (shapes[1].clone(), shapes[0].clone()),
+                };
+                let widened = self.widen(*left, &left_shape, recorded) | self.widen(*right, &right_shape, recorded);
+                if widened {
+                    return true;
+                }
+            }
+            let node = &self.nodes[index];
+            let shape = match infer_shape(node.op, &shapes.iter().collect::<Vec<&Shape>>()) {
+                Ok(Some(shape)) => shape,
+                // a custom op that keeps the dimensions of its first input is assumed to keep its shape
+                Ok(None) if node.dim == self.nodes[node.inputs[0]].dim => shapes[0].clone(),
+                Ok(None) => Shape::from(node.dim),
+                Err(error) => panic!("Cannot infer the shape of %{}: {}", index, error),
+            };
+            self.nodes[index].shape = shape;
+        }
+        false
+    }
+
+    ///
+    /// Give the fixed axes of a node the variables of `shape` where they were recorded with the same
+    /// size, down to the constants the node is computed from.
+    ///
+    /// # Returns
+    ///
+    /// Whether the shape of a constant changed.
+    fn widen(&mut self, index: usize, shape: &Shape, recorded: &Bindings) -> bool {
+        let node = &self.nodes[index];
+        let widen_axis = |current: &Dim, wanted: &Dim, size: usize| {
+            !current.is_symbolic() && wanted.is_symbolic() && wanted.bind(recorded) == Some(size)
+        };
+        let rows = widen_axis(&node.shape.0, &shape.0, node.dim.0);
+        let columns = widen_axis(&node.shape.1, &shape.1, node.dim.1);
+        if !rows && !columns {
+            return false;
+        }
+        let widened = Shape(
+            if rows { shape.0.clone() } else { node.shape.0.clone() },
+            if columns { shape.1.clone() } else { node.shape.1.clone() },
+        );
+        match (node.kind, node.op) {
+            (NodeKind::Constant, _) => {
+                let data = node.data.as_ref().unwrap();
+                // only repeated rows or columns can be refilled to another size
+                if (rows && !repeats_rows(data, node.dim)) || (columns && !repeats_columns(data, node.dim)) {
+                    return false;
+                }
+                self.nodes[index].shape = widened;
+                true
+            }
+            (NodeKind::Op, Ops::UnaryOps(_)) => self.widen(node.inputs[0], &widened, recorded),
+            (NodeKind::Op, Ops::BinaryOps(BinaryOps::MUL)) => {
+                let (left, right) = (node.inputs[0], node.inputs[1]);
+                let left_shape = Shape(widened.0.clone(), self.nodes[left].shape.1.clone());
+                let right_shape = Shape(self.nodes[right].shape.0.clone(), widened.1);
+                self.widen(left, &left_shape, recorded) | self.widen(right, &right_shape, recorded)
+            }
+            (NodeKind::Op, Ops::BinaryOps(_)) => {
+                let (left, right) = (node.inputs[0], node.inputs[1]);
+                self.widen(left, &widened, recorded) | self.widen(right, &widened, recorded)
+            }
+            _ => false,
+        }
+    }
+
+    ///
+    /// Make a copy of the graph with every variable replaced by its size. Constants that took a
+    /// symbolic shape are refilled to the new size by repeating their first row or column.
+    ///
+    /// # Panics
+    ///
+    /// * If a variable the graph uses has no binding.
+    pub fn bind(&self, bindings: &Bindings) -> Graph<T> {
+        let mut graph = self.clone();
+        for (index, node) in graph.nodes.iter_mut().enumerate() {
+            if !node.shape.is_symbolic() {
+                continue;
+            }
+            let dim = match node.shape.bind(bindings) {
+                Some(dim) => dim,
+                None => panic!("Node %{} has shape {} but not all of its variables are bound", index, node.shape),
+            };
+            if dim != node.dim {
+                node.data = match (node.kind, &node.data) {
+                    (NodeKind::Constant, Some(data)) => Some(refill(data, node.dim, dim)),
+                    // the example data of an input is replaced on every evaluation anyway
+                    _ => None,
+                };
+                node.dim = dim;
+            }
+        }
+        graph
+    }
+
+    /// Number of op nodes, i.e. the work done per evaluation.
+    pub fn op_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Op)
+            .count()
+    }
+
+    ///
+    /// Compute the outputs for new input data.
+    ///
+    /// # Panics
+    ///
+    /// * If the number or lengths of the inputs do not match the graph.
+    pub fn evaluate(&self, inputs: &[DataArray<T>]) -> Vec<DataArray<T>> {
+        let values = self.evaluate_all(inputs);
+        self.outputs
+            .iter()
+            .map(|output| values[*output].clone())
+            .collect()
+    }
+
+    /// Compute the value of every node for new input data, indexed like the nodes.
+    pub fn evaluate_all(&self, inputs: &[DataArray<T>]) -> Vec<DataArray<T>> {
+        if inputs.len() != self.inputs.len() {
+            panic!("Graph takes {} inputs but {} were given", self.inputs.len(), inputs.len());
+        }
+        let mut values: Vec<Option<DataArray<T>>> = vec![None; self.nodes.len()];
+        for (index, data) in self.inputs.iter().zip(inputs.iter()) {
+            let dim = self.nodes[*index].dim;
+            if data.len() != dim.0 * dim.1 {
+                panic!("Input has {} values but the graph expects {}x{}", data.len(), dim.0, dim.1);
+            }
+            values[*index] = Some(data.clone());
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            if values[index].is_some() {
+                continue;
+            }
+            let value = match node.kind {
+                NodeKind::Op => {
+                    let inputs: Vec<&[T]> = node.inputs
+                        .iter()
+                        .map(|input| &values[*input].as_ref().unwrap()[..])
+                        .collect();
+                    self.evaluate_node(node, &inputs)
+                }
+                _ => node.data.clone().unwrap(),
+            };
+            values[index] = Some(value);
+        }
+        values
+            .into_iter()
+            .map(|value| value.unwrap())
+            .collect()
+    }
+
+    /// Run the op of a single node on the values of its inputs.
+    pub(crate) fn evaluate_node(&self, node: &Node<T>, inputs: &[&[T]]) -> DataArray<T> {
+        let backend = get_backend::<T>(&Device::CPU);
+        let dims: Vec<Dimensions> = node.inputs
+            .iter()
+            .map(|input| self.nodes[*input].dim)
+            .collect();
+        let storage: Vec<Storage<T>> = inputs
+            .iter()
+            .map(|data| Storage::Host((*data).into()))
+            .collect();
+        let result = match node.op {
+            Ops::UnaryOps(op) => backend.unary(op, &storage[0], dims[0], node.arg),
+            Ops::BinaryOps(BinaryOps::MUL) => backend.matmul(&storage[0], dims[0], &storage[1], dims[1]),
+            Ops::BinaryOps(op) => backend.binary(op, &storage[0], &storage[1], dims[0]),
+            Ops::ReduceOps(op) => backend.reduce(op, &storage[0], dims[0]),
+            Ops::LoadOps(LoadOps::CUSTOM) => {
+                let custom = node.custom.as_ref().unwrap();
+                let tensors: Vec<Tensor<T>> = inputs
+                    .iter()
+                    .zip(dims.iter())
+                    .map(|(data, dim)| Tensor::new((*data).into(), *dim, None, None))
+                    .collect();
+                let output = custom.function.forward(&mut Context::new(), &tensors);
+                Storage::Host(output.data().clone())
+            }
+            op => panic!("Cannot evaluate {:?}", op),
+        };
+        result.to_host()
+    }
+
+    ///
+    /// Keep only the nodes marked in `keep`, renumbering the rest.
+    /// Kept nodes must not read removed ones.
+    pub(crate) fn retain(&mut self, keep: &[bool]) {
+        let mut remap = vec![usize::MAX; self.nodes.len()];
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        for (index, node) in self.nodes.drain(..).enumerate() {
+            if keep[index] {
+                remap[index] = nodes.len();
+                nodes.push(node);
+            }
+        }
+        for node in nodes.iter_mut() {
+            for input in node.inputs.iter_mut() {
+                *input = remap[*input];
+            }
+        }
+        self.nodes = nodes;
+        self.inputs = self.inputs
+            .iter()
+            .map(|input| remap[*input])
+            .collect();
+        self.outputs = self.outputs
+            .iter()
+            .map(|output| remap[*output])
+            .collect();
+    }
+
+    /// Replace every use of a node by the node `remap` points it to.
+    pub(crate) fn redirect(&mut self, remap: &[usize]) {
+        for node in self.nodes.iter_mut() {
+            for input in node.inputs.iter_mut() {
+                *input = remap[*input];
+            }
+        }
+        for output in self.outputs.iter_mut() {
+            *output = remap[*output];
+        }
+    }
+
+    pub(crate) fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        &mut self.nodes[index]
+    }
+}
+
+impl<T: TensorTrait<T>> fmt::Display for Graph<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, node) in self.nodes.iter().enumerate() {
+            let inputs: Vec<String> = node.inputs
+                .iter()
+                .map(|input| format!("%{}", input))
+                .collect();
+            let name = match node.kind {
+                NodeKind::Op => op_name(&node.op),
+                kind => format!("{:?}", kind),
+            };
+            write!(f, "%{} = {}({}) {}", index, name, inputs.join(", "), node.shape)?;
+            if let Some(arg) = node.arg {
+                write!(f, " arg={}", arg)?;
+            }
+            if self.outputs.contains(&index) {
+                write!(f, " output")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether every row of a matrix is the same.
+fn repeats_rows<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> bool {
+    data.chunks(dim.1.max(1)).all(|row| row == &data[..row.len()])
+}
+
+/// Whether every column of a matrix is the same.
+fn repeats_columns<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> bool {
+    data.chunks(dim.1.max(1)).all(|row| row.iter().all(|value| *value == row[0]))
+}
+
+/// Resize a constant with repeated rows or columns, repeating the first of those that changed size.
+fn refill<T: TensorTrait<T>>(data: &DataArray<T>, from: Dimensions, to: Dimensions) -> DataArray<T> {
+    let mut refilled = Vec::with_capacity(to.0 * to.1);
+    for row in 0..to.0 {
+        let row = if to.0 == from.0 { row } else { 0 };
+        for column in 0..to.1 {
+            let column = if to.1 == from.1 { column } else { 0 };
+            refilled.push(data[row * from.1 + column]);
+        }
+    }
+    refilled.into_boxed_slice()
+}
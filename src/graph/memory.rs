@@ -0,0 +1,355 @@
+use std::fmt;
+use std::mem::size_of;
+use std::ops::Range;
+
+use crate::{ Tensor, TensorTrait, Dimensions };
+use crate::graph::ir::{ Graph, NodeKind };
+use crate::graph::view::GraphView;
+use crate::types::shape::Bindings;
+
+/// Offsets in the arena are rounded up to this many bytes.
+pub const ALIGNMENT: usize = 64;
+
+/// What a planned buffer holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BufferKind {
+    /// The data a node computes in the forward pass.
+    Value,
+    /// The gradient of the loss with respect to a node, computed in the backward pass.
+    Gradient,
+}
+
+///
+/// A buffer placed in the arena.
+///
+/// # Fields
+/// * `node` - Index of the node in the `GraphView` the plan was made from
+/// * `kind` - Whether the buffer holds the value or the gradient of the node
+/// * `offset` - Start of the buffer in the arena, in bytes
+/// * `bytes` - Size of the buffer
+/// * `first_use` - The step that writes the buffer
+/// * `last_use` - The last step that reads it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    pub node: usize,
+    pub kind: BufferKind,
+    pub offset: usize,
+    pub bytes: usize,
+    pub first_use: usize,
+    pub last_use: usize,
+}
+
+impl Allocation {
+    /// Whether two buffers are needed at the same time.
+    pub fn overlaps_in_time(&self, other: &Allocation) -> bool {
+        self.first_use <= other.last_use && other.first_use <= self.last_use
+    }
+
+    /// Whether two buffers share any bytes of the arena.
+    pub fn overlaps_in_memory(&self, other: &Allocation) -> bool {
+        self.offset < other.offset + other.bytes && other.offset < self.offset + self.bytes
+    }
+}
+
+///
+/// Offsets for every intermediate buffer of a training step in one preallocated arena.
+///
+/// The step runs the forward pass in topological order, one node per step, then the backward pass
+/// in reverse order. Liveness analysis finds the first and last step that touches each buffer,
+/// and buffers whose lifetimes do not overlap share memory. Leaves are owned by the caller and are
+/// not placed, but the gradients of parameters are, and stay live until the end of the step.
+/// `jit::Compiled` runs every call in an arena laid out by the plan of its graph.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, graph::memory::MemoryPlan, nn::{ activation::relu, transformation::sum } };
+///
+/// let x: Tensor<f64> = Tensor::ones((16, 32), None, None);
+/// let w1: Tensor<f64> = Tensor::ones((32, 32), None, Some(true));
+/// let w2: Tensor<f64> = Tensor::ones((32, 32), None, Some(true));
+/// let w3: Tensor<f64> = Tensor::ones((32, 8), None, Some(true));
+/// let loss = sum(relu(relu(x * w1) * w2) * w3);
+///
+/// let plan = MemoryPlan::new(&loss);
+/// assert!(plan.peak_bytes() < plan.naive_bytes());
+///
+/// // buffers that are alive at the same time never share memory
+/// for a in plan.allocations() {
+///     for b in plan.allocations() {
+///         if a != b && a.overlaps_in_time(b) {
+///             assert!(!a.overlaps_in_memory(b));
+///         }
+///     }
+/// }
+/// println!("{}", plan);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MemoryPlan {
+    allocations: Vec<Allocation>,
+    peak_bytes: usize,
+    naive_bytes: usize,
+    steps: usize,
+}
+
+impl MemoryPlan {
+    /// Plan the memory of the forward and backward pass of a loss tensor.
+    pub fn new<T: TensorTrait<T>>(root: &Tensor<T>) -> Self {
+        Self::from_view(&GraphView::new(root))
+    }
+
+    /// Plan the memory of the forward and backward pass of the graph in a view.
+    pub fn from_view<T: TensorTrait<T>>(view: &GraphView<'_, T>) -> Self {
+        let nodes: Vec<PlanNode> = view
+            .iter()
+            .map(|node| PlanNode {
+                dim: node.dim,
+                parents: node.parents.clone(),
+                is_leaf: node.is_leaf,
+                is_parameter: node.is_parameter,
+            })
+            .collect();
+        Self::from_nodes::<T>(&nodes, view.len().saturating_sub(1))
+    }
+
+    ///
+    /// Plan the memory of the forward and backward pass of a graph with a single output, with the
+    /// variables of its shapes bound to sizes. The same symbolic graph gives one plan per batch size.
+    ///
+    /// # Panics
+    ///
+    /// * If a variable the graph uses has no binding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, graph::{ ir::Graph, memory::MemoryPlan }, nn::activation::relu };
+    /// use nanograd::types::shape::{ Bindings, Dim, Shape };
+    ///
+    /// let x: Tensor<f64> = Tensor::ones((64, 32), None, None);
+    /// let w: Tensor<f64> = Tensor::ones((32, 32), None, Some(true));
+    /// let y = relu(x.clone() * w);
+    ///
+    /// let mut graph = Graph::from_tensors(&[&y], &[&x]);
+    /// graph.set_input_shapes(&[Shape(Dim::var("batch"), Dim::from(32))]);
+    ///
+    /// let mut bindings = Bindings::new();
+    /// bindings.insert("batch".to_string(), 64);
+    /// let full = MemoryPlan::from_graph(&graph, &bindings);
+    /// bindings.insert("batch".to_string(), 16);
+    /// let last = MemoryPlan::from_graph(&graph, &bindings);
+    ///
+    /// assert!(last.peak_bytes() < full.peak_bytes());
+    /// ```
+    pub fn from_graph<T: TensorTrait<T>>(graph: &Graph<T>, bindings: &Bindings) -> Self {
+        if graph.outputs().len() != 1 {
+            panic!("A memory plan needs a graph with exactly one output");
+        }
+        let nodes: Vec<PlanNode> = graph
+            .nodes()
+            .iter()
+            .enumerate()
+            .map(|(index, node)| PlanNode {
+                dim: match node.shape.bind(bindings) {
+                    Some(dim) => dim,
+                    None => panic!("Node %{} has shape {} but not all of its variables are bound", index, node.shape),
+                },
+                parents: node.inputs.clone(),
+                is_leaf: node.kind != NodeKind::Op,
+                is_parameter: node.kind == NodeKind::Parameter,
+            })
+            .collect();
+        Self::from_nodes::<T>(&nodes, graph.outputs()[0])
+    }
+
+    fn from_nodes<T: TensorTrait<T>>(nodes: &[PlanNode], root: usize) -> Self {
+        let allocations = liveness::<T>(nodes, root);
+        let naive_bytes = allocations
+            .iter()
+            .map(|allocation| allocation.bytes)
+            .sum();
+        let allocations = assign_offsets(allocations);
+        let peak_bytes = allocations
+            .iter()
+            .map(|allocation| allocation.offset + allocation.bytes)
+            .max()
+            .unwrap_or(0);
+        MemoryPlan { allocations, peak_bytes, naive_bytes, steps: 2 * nodes.len() }
+    }
+
+    /// Every placed buffer, in order of placement.
+    pub fn allocations(&self) -> &[Allocation] {
+        &self.allocations
+    }
+
+    /// Get the placement of the value or gradient of a node, if it is in the arena.
+    pub fn allocation(&self, node: usize, kind: BufferKind) -> Option<&Allocation> {
+        self.allocations
+            .iter()
+            .find(|allocation| allocation.node == node && allocation.kind == kind)
+    }
+
+    /// Size of the arena, the planned peak memory.
+    pub fn peak_bytes(&self) -> usize {
+        self.peak_bytes
+    }
+
+    /// Memory needed if every buffer had its own allocation.
+    pub fn naive_bytes(&self) -> usize {
+        self.naive_bytes
+    }
+
+    /// Number of steps of the forward and backward pass.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Allocate a zeroed arena of elements of type `T` large enough for the plan.
+    pub fn arena<T: TensorTrait<T>>(&self) -> Vec<T> {
+        vec![T::zero(); self.peak_bytes.div_ceil(size_of::<T>())]
+    }
+
+    ///
+    /// Get the slice of an arena that holds the value or gradient of a node.
+    ///
+    /// # Panics
+    ///
+    /// * If the buffer is not in the plan.
+    pub fn slice<'a, T: TensorTrait<T>>(&self, arena: &'a [T], node: usize, kind: BufferKind) -> &'a [T] {
+        &arena[self.range::<T>(node, kind)]
+    }
+
+    ///
+    /// Get the slice of an arena that holds the value or gradient of a node, to write it.
+    ///
+    /// # Panics
+    ///
+    /// * If the buffer is not in the plan.
+    pub fn slice_mut<'a, T: TensorTrait<T>>(&self, arena: &'a mut [T], node: usize, kind: BufferKind) -> &'a mut [T] {
+        &mut arena[self.range::<T>(node, kind)]
+    }
+
+    /// The elements of the arena a buffer occupies.
+    fn range<T: TensorTrait<T>>(&self, node: usize, kind: BufferKind) -> Range<usize> {
+        let allocation = match self.allocation(node, kind) {
+            Some(allocation) => allocation,
+            None => panic!("No {:?} buffer is planned for node {}", kind, node),
+        };
+        let start = allocation.offset / size_of::<T>();
+        start..start + allocation.bytes / size_of::<T>()
+    }
+}
+
+impl fmt::Display for MemoryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let saved = 1.0 - (self.peak_bytes as f64) / (self.naive_bytes.max(1) as f64);
+        writeln!(
+            f,
+            "memory plan: {} buffers over {} steps, planned peak {} bytes, naive {} bytes ({:.1}% saved)",
+            self.allocations.len(),
+            self.steps,
+            self.peak_bytes,
+            self.naive_bytes,
+            saved * 100.0
+        )?;
+        for allocation in &self.allocations {
+            writeln!(
+                f,
+                "  node {:>4} {:<8} offset {:>10} bytes {:>10} live {}..={}",
+                allocation.node,
+                format!("{:?}", allocation.kind),
+                allocation.offset,
+                allocation.bytes,
+                allocation.first_use,
+                allocation.last_use
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// What the planner needs to know about a node, whichever graph it came from.
+struct PlanNode {
+    dim: Dimensions,
+    parents: Vec<usize>,
+    is_leaf: bool,
+    is_parameter: bool,
+}
+
+/// Find the first and last step of every intermediate value and gradient.
+fn liveness<T: TensorTrait<T>>(nodes: &[PlanNode], root: usize) -> Vec<Allocation> {
+    let n = nodes.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let last_step = 2 * n - 1;
+    // the backward step of node i runs after the whole forward pass, in reverse order
+    let backward_step = |i: usize| n + (n - 1 - i);
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (i, node) in nodes.iter().enumerate() {
+        for parent in &node.parents {
+            children[*parent].push(i);
+        }
+    }
+    // only nodes on a path to a parameter get gradients
+    let mut needs_grad = vec![false; n];
+    for (i, node) in nodes.iter().enumerate() {
+        needs_grad[i] = node.is_parameter || node.parents.iter().any(|parent| needs_grad[*parent]);
+    }
+    let mut allocations = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let bytes = node.dim.0 * node.dim.1 * size_of::<T>();
+        if !node.is_leaf {
+            // a value is read by its children, and by the backward step of itself and its children
+            let mut last_use = children[i].iter().copied().max().unwrap_or(i);
+            if needs_grad[i] {
+                last_use = last_use.max(backward_step(i));
+            }
+            for child in &children[i] {
+                if needs_grad[*child] {
+                    last_use = last_use.max(backward_step(*child));
+                }
+            }
+            if i == root {
+                last_use = last_step;
+            }
+            allocations.push(Allocation { node: i, kind: BufferKind::Value, offset: 0, bytes, first_use: i, last_use });
+        }
+        if needs_grad[i] {
+            // a gradient is first written by the backward step of its first child, then read by its own
+            let first_use = children[i]
+                .iter()
+                .filter(|child| needs_grad[**child])
+                .map(|child| backward_step(*child))
+                .min()
+                .unwrap_or(backward_step(root));
+            let last_use = if node.is_leaf { last_step } else { backward_step(i) };
+            allocations.push(Allocation { node: i, kind: BufferKind::Gradient, offset: 0, bytes, first_use, last_use });
+        }
+    }
+    allocations
+}
+
+/// Place the largest buffers first, each at the lowest offset that is free for its whole lifetime.
+fn assign_offsets(mut allocations: Vec<Allocation>) -> Vec<Allocation> {
+    allocations.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.first_use.cmp(&b.first_use)));
+    let mut placed: Vec<Allocation> = Vec::with_capacity(allocations.len());
+    for mut allocation in allocations {
+        let mut busy: Vec<(usize, usize)> = placed
+            .iter()
+            .filter(|other| other.overlaps_in_time(&allocation))
+            .map(|other| (other.offset, other.offset + other.bytes))
+            .collect();
+        busy.sort();
+        let mut offset = 0;
+        for (start, end) in busy {
+            if offset + allocation.bytes <= start {
+                break;
+            }
+            offset = offset.max(end.next_multiple_of(ALIGNMENT));
+        }
+        allocation.offset = offset;
+        placed.push(allocation);
+    }
+    placed
+}
@@ -0,0 +1,11 @@
+pub mod dot;
+
+pub mod view;
+
+pub mod visitor;
+
+pub mod memory;
+
+pub mod ir;
+
+pub mod passes;
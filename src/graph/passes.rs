@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use crate::{ TensorTrait, Ops, Dimensions };
+use crate::graph::ir::{ Graph, NodeKind };
+use crate::types::ops::{ BinaryOps, LoadOps };
+
+/// Environment variable listing passes to turn off, e.g. `NANOGRAD_DISABLE_PASSES=cse,dce`.
+pub const DISABLE_PASSES_VAR: &str = "NANOGRAD_DISABLE_PASSES";
+
+///
+/// Which passes `optimize` runs. Every pass is on by default.
+///
+/// * `constant_folding` - Evaluate ops whose inputs are all constants once, ahead of time.
+/// * `cse` - Merge nodes that compute the same op on the same inputs.
+/// * `dce` - Remove nodes no output depends on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Passes {
+    pub constant_folding: bool,
+    pub cse: bool,
+    pub dce: bool,
+}
+
+impl Default for Passes {
+    fn default() -> Self {
+        Passes { constant_folding: true, cse: true, dce: true }
+    }
+}
+
+impl Passes {
+    /// Run no passes.
+    pub fn none() -> Self {
+        Passes { constant_folding: false, cse: false, dce: false }
+    }
+
+    ///
+    /// Every pass, except the ones named in `NANOGRAD_DISABLE_PASSES`
+    /// (`constant_folding`, `cse` or `dce`, separated by commas).
+    pub fn from_env() -> Self {
+        let mut passes = Passes::default();
+        if let Ok(disabled) = std::env::var(DISABLE_PASSES_VAR) {
+            for pass in disabled.split(',') {
+                match pass.trim() {
+                    "constant_folding" => {
+                        passes.constant_folding = false;
+                    }
+                    "cse" => {
+                        passes.cse = false;
+                    }
+                    "dce" => {
+                        passes.dce = false;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        passes
+    }
+}
+
+/// How much each pass changed a graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PassReport {
+    /// Ops replaced by constants.
+    pub folded: usize,
+    /// Nodes merged into an identical node.
+    pub merged: usize,
+    /// Nodes removed because no output depends on them.
+    pub removed: usize,
+}
+
+///
+/// Run the enabled passes over a graph: constant folding, then common subexpression elimination,
+/// then dead code elimination. The outputs compute the same values before and after.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, graph::{ ir::Graph, passes::{ optimize, Passes } } };
+///
+/// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+/// // `* 2.0` builds a diagonal constant, `+ 1.0` a full one; both get folded into one constant
+/// let c: Tensor<f64> = Tensor::ones((2, 2), None, None) * 2.0 + 1.0;
+/// let y = x.clone() * c.clone() + x.clone() * c;
+///
+/// let mut graph = Graph::from_tensors(&[&y], &[&x]);
+/// let before = graph.evaluate(&[vec![1.0, 0.0, 0.0, 1.0].into_boxed_slice()]);
+///
+/// let report = optimize(&mut graph, &Passes::default());
+///
+/// assert_eq!(report.folded, 2);
+/// assert!(report.merged >= 1);
+/// assert_eq!(graph.op_count(), 2);
+/// assert_eq!(graph.evaluate(&[vec![1.0, 0.0, 0.0, 1.0].into_boxed_slice()]), before);
+///
+/// // each pass can be turned off on its own
+/// let mut unoptimized = Graph::from_tensors(&[&y], &[&x]);
+/// let report = optimize(&mut unoptimized, &Passes { cse: false, ..Passes::default() });
+/// assert_eq!(report.merged, 0);
+/// ```
+pub fn optimize<T: TensorTrait<T>>(graph: &mut Graph<T>, passes: &Passes) -> PassReport {
+    let mut report = PassReport::default();
+    if passes.constant_folding {
+        report.folded = fold_constants(graph);
+    }
+    if passes.cse {
+        report.merged = eliminate_common_subexpressions(graph);
+    }
+    if passes.dce {
+        report.removed = eliminate_dead_code(graph);
+    }
+    report
+}
+
+///
+/// Replace every op whose inputs are all constants by a constant holding its result.
+/// Custom functions are never folded, since they may not be pure.
+///
+/// # Returns
+///
+/// The number of ops folded.
+pub fn fold_constants<T: TensorTrait<T>>(graph: &mut Graph<T>) -> usize {
+    let mut folded = 0;
+    for index in 0..graph.len() {
+        let node = graph.node(index);
+        let foldable =
+            node.kind == NodeKind::Op &&
+            node.op != Ops::LoadOps(LoadOps::CUSTOM) &&
+            node.inputs.iter().all(|input| graph.node(*input).kind == NodeKind::Constant);
+        if !foldable {
+            continue;
+        }
+        let inputs: Vec<&[T]> = node.inputs
+            .iter()
+            .map(|input| &graph.node(*input).data.as_ref().unwrap()[..])
+            .collect();
+        let data = graph.evaluate_node(node, &inputs);
+        let node = graph.node_mut(index);
+        node.kind = NodeKind::Constant;
+        node.op = Ops::None;
+        node.inputs.clear();
+        node.arg = None;
+        node.data = Some(data);
+        folded += 1;
+    }
+    folded
+}
+
+/// What makes two nodes interchangeable.
+#[derive(PartialEq, Eq, Hash)]
+enum NodeKey {
+    Constant(Dimensions, Vec<u64>),
+    Op(Ops, Vec<usize>, Dimensions, Option<u64>),
+}
+
+fn bits<T: TensorTrait<T>>(value: T) -> u64 {
+    value.to_f64().map(f64::to_bits).unwrap_or(0)
+}
+
+///
+/// Point every use of a node at the first earlier node with the same op, inputs and scalar
+/// argument, or, for constants, the same data. Inputs, parameters and custom functions are never
+/// merged. The merged nodes are left unused for `eliminate_dead_code`.
+///
+/// # Returns
+///
+/// The number of nodes merged.
+pub fn eliminate_common_subexpressions<T: TensorTrait<T>>(graph: &mut Graph<T>) -> usize {
+    let mut remap: Vec<usize> = (0..graph.len()).collect();
+    let mut seen: HashMap<NodeKey, usize> = HashMap::new();
+    let mut merged = 0;
+    for index in 0..graph.len() {
+        let node = graph.node(index);
+        let key = match node.kind {
+            NodeKind::Constant => {
+                let data = node.data.as_ref().unwrap();
+                NodeKey::Constant(node.dim, data.iter().map(|value| bits(*value)).collect())
+            }
+            NodeKind::Op if node.op != Ops::LoadOps(LoadOps::CUSTOM) => {
+                let mut inputs: Vec<usize> = node.inputs
+                    .iter()
+                    .map(|input| remap[*input])
+                    .collect();
+                // addition is the only commutative op
+                if node.op == Ops::BinaryOps(BinaryOps::ADD) {
+                    inputs.sort();
+                }
+                NodeKey::Op(node.op, inputs, node.dim, node.arg.map(bits))
+            }
+            _ => {
+                continue;
+            }
+        };
+        match seen.get(&key) {
+            Some(existing) => {
+                remap[index] = *existing;
+                merged += 1;
+            }
+            None => {
+                seen.insert(key, index);
+            }
+        }
+    }
+    graph.redirect(&remap);
+    merged
+}
+
+///
+/// Remove every node that no output depends on. Inputs are kept so the graph takes the same
+/// arguments.
+///
+/// # Returns
+///
+/// The number of nodes removed.
+pub fn eliminate_dead_code<T: TensorTrait<T>>(graph: &mut Graph<T>) -> usize {
+    let mut keep = vec![false; graph.len()];
+    for input in graph.inputs() {
+        keep[*input] = true;
+    }
+    let mut stack: Vec<usize> = graph.outputs().to_vec();
+    while let Some(index) = stack.pop() {
+        if keep[index] {
+            continue;
+        }
+        keep[index] = true;
+        stack.extend(graph.node(index).inputs.iter().copied());
+    }
+    let removed = keep
+        .iter()
+        .filter(|kept| !**kept)
+        .count();
+    graph.retain(&keep);
+    removed
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::{ Tensor, TensorTrait, Ops, Dimensions };
+use crate::graph::visitor::Visitor;
+
+///
+/// A single tensor in a `GraphView`.
+///
+/// # Fields
+/// * `index` - Position of the node in topological order
+/// * `tensor` - The tensor itself
+/// * `op` - The operation that produced the tensor
+/// * `dim` - The dimensions of the tensor
+/// * `parents` - Indices of the nodes this tensor was computed from, left before right
+/// * `is_leaf` - Whether the tensor was created directly rather than by an op
+/// * `is_parameter` - Whether the tensor is a leaf that requires gradients
+pub struct GraphNode<'a, T: TensorTrait<T>> {
+    pub index: usize,
+    pub tensor: &'a Tensor<T>,
+    pub op: Ops,
+    pub dim: Dimensions,
+    pub parents: Vec<usize>,
+    pub is_leaf: bool,
+    pub is_parameter: bool,
+}
+
+///
+/// A read-only view of the graph below a root tensor.
+/// Nodes are stored in topological order (parents before children) and copies of the
+/// same tensor (same unique id) appear once, so shared subexpressions become a single node.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, graph::view::GraphView, nn::transformation::sum };
+///
+/// let w: Tensor<f64> = Tensor::ones((2, 2), None, Some(true));
+/// let x: Tensor<f64> = Tensor::ones((2, 2), None, None);
+/// let loss = sum(x * w.clone() + w.clone());
+///
+/// let view = GraphView::new(&loss);
+/// assert_eq!(view.len(), 5);
+/// assert_eq!(view.parameters().len(), 1);
+/// assert_eq!(view.root().op, loss.op);
+/// ```
+pub struct GraphView<'a, T: TensorTrait<T>> {
+    nodes: Vec<GraphNode<'a, T>>,
+}
+
+impl<'a, T: TensorTrait<T>> GraphView<'a, T> {
+    /// Build the view of everything `root` was computed from.
+    pub fn new(root: &'a Tensor<T>) -> Self {
+        let mut nodes: Vec<GraphNode<'a, T>> = Vec::new();
+        let mut indices: HashMap<i32, usize> = HashMap::new();
+        add_node(root, &mut nodes, &mut indices);
+        GraphView { nodes }
+    }
+
+    /// Get the nodes in topological order.
+    pub fn nodes(&self) -> &[GraphNode<'a, T>] {
+        &self.nodes
+    }
+
+    /// Get a node by its index.
+    pub fn node(&self, index: usize) -> &GraphNode<'a, T> {
+        &self.nodes[index]
+    }
+
+    /// Get the root tensor's node, which always comes last.
+    pub fn root(&self) -> &GraphNode<'a, T> {
+        &self.nodes[self.nodes.len() - 1]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Iterate over the nodes in topological order.
+    pub fn iter(&self) -> std::slice::Iter<'_, GraphNode<'a, T>> {
+        self.nodes.iter()
+    }
+
+    /// Get the leaves that require gradients, i.e. the parameters the root depends on.
+    pub fn parameters(&self) -> Vec<&GraphNode<'a, T>> {
+        self.nodes
+            .iter()
+            .filter(|node| node.is_parameter)
+            .collect()
+    }
+
+    /// Get the indices of the nodes that use each node as a parent.
+    pub fn children(&self) -> Vec<Vec<usize>> {
+        let mut children = vec![Vec::new(); self.nodes.len()];
+        for node in &self.nodes {
+            for parent in &node.parents {
+                children[*parent].push(node.index);
+            }
+        }
+        children
+    }
+
+    /// Call the visitor on every node in topological order.
+    pub fn accept<V: Visitor<T>>(&self, visitor: &mut V) {
+        for node in &self.nodes {
+            visitor.visit(node, self);
+        }
+    }
+}
+
+fn add_node<'a, T: TensorTrait<T>>(
+    tensor: &'a Tensor<T>,
+    nodes: &mut Vec<GraphNode<'a, T>>,
+    indices: &mut HashMap<i32, usize>
+) -> usize {
+    if let Some(index) = indices.get(&tensor.unique_id) {
+        return *index;
+    }
+    let mut parents = Vec::new();
+    for parent in [&tensor.left, &tensor.right].into_iter().flatten() {
+        parents.push(add_node(parent, nodes, indices));
+    }
+    let index = nodes.len();
+    let is_leaf = parents.is_empty();
+    nodes.push(GraphNode {
+        index,
+        tensor,
+        op: tensor.op,
+        dim: tensor.dim(),
+        parents,
+        is_leaf,
+        is_parameter: is_leaf && *tensor.requires_grad(),
+    });
+    indices.insert(tensor.unique_id, index);
+    index
+}
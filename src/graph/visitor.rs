@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use crate::{ TensorTrait, Ops, types::ops::{ BinaryOps, UnaryOps } };
+use crate::graph::view::{ GraphNode, GraphView };
+
+///
+/// Something that walks a `GraphView` one node at a time, parents before children.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, TensorTrait, graph::{ view::{ GraphNode, GraphView }, visitor::Visitor } };
+///
+/// // collect the shape of every node
+/// struct Shapes(Vec<(usize, usize)>);
+///
+/// impl<T: TensorTrait<T>> Visitor<T> for Shapes {
+///     fn visit(&mut self, node: &GraphNode<'_, T>, _view: &GraphView<'_, T>) {
+///         self.0.push(node.dim);
+///     }
+/// }
+///
+/// let a: Tensor<f64> = Tensor::ones((2, 3), None, None);
+/// let b: Tensor<f64> = Tensor::ones((3, 1), None, None);
+/// let c = a * b;
+///
+/// let mut shapes = Shapes(Vec::new());
+/// GraphView::new(&c).accept(&mut shapes);
+/// assert_eq!(shapes.0, vec![(2, 3), (3, 1), (2, 1)]);
+/// ```
+pub trait Visitor<T: TensorTrait<T>> {
+    fn visit(&mut self, node: &GraphNode<'_, T>, view: &GraphView<'_, T>);
+}
+
+///
+/// Count how many times each op appears in a graph. Leaves are not counted.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, Ops, types::ops::BinaryOps, graph::{ view::GraphView, visitor::OpCounter } };
+///
+/// let a: Tensor<f64> = Tensor::ones((2, 2), None, None);
+/// let b = a.clone() + a.clone() + a;
+///
+/// let mut counter = OpCounter::new();
+/// GraphView::new(&b).accept(&mut counter);
+/// assert_eq!(counter.count(Ops::BinaryOps(BinaryOps::ADD)), 2);
+/// ```
+#[derive(Default)]
+pub struct OpCounter {
+    counts: HashMap<Ops, usize>,
+}
+
+impl OpCounter {
+    pub fn new() -> Self {
+        OpCounter { counts: HashMap::new() }
+    }
+
+    /// Get how many times `op` appeared.
+    pub fn count(&self, op: Ops) -> usize {
+        *self.counts.get(&op).unwrap_or(&0)
+    }
+
+    /// Get the counts of every op that appeared.
+    pub fn counts(&self) -> &HashMap<Ops, usize> {
+        &self.counts
+    }
+}
+
+impl<T: TensorTrait<T>> Visitor<T> for OpCounter {
+    fn visit(&mut self, node: &GraphNode<'_, T>, _view: &GraphView<'_, T>) {
+        if !node.is_leaf {
+            *self.counts.entry(node.op).or_insert(0) += 1;
+        }
+    }
+}
+
+///
+/// Estimate the floating point operations needed to compute a graph.
+/// A matrix multiplication of `(m, k)` by `(k, n)` costs `2 * m * k * n`, softmax costs three
+/// per element (exponent, sum and division), every other op one per input element.
+/// Custom functions are opaque and count as zero.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, graph::{ view::GraphView, visitor::FlopCounter } };
+///
+/// let a: Tensor<f64> = Tensor::ones((2, 3), None, None);
+/// let b: Tensor<f64> = Tensor::ones((3, 4), None, None);
+/// let c = a * b;
+///
+/// let mut counter = FlopCounter::new();
+/// GraphView::new(&c).accept(&mut counter);
+/// assert_eq!(counter.flops(), 2 * 2 * 3 * 4);
+/// ```
+#[derive(Default)]
+pub struct FlopCounter {
+    flops: usize,
+}
+
+impl FlopCounter {
+    pub fn new() -> Self {
+        FlopCounter { flops: 0 }
+    }
+
+    pub fn flops(&self) -> usize {
+        self.flops
+    }
+}
+
+impl<T: TensorTrait<T>> Visitor<T> for FlopCounter {
+    fn visit(&mut self, node: &GraphNode<'_, T>, view: &GraphView<'_, T>) {
+        if node.is_leaf {
+            return;
+        }
+        let input_dim = view.node(node.parents[0]).dim;
+        let input_len = input_dim.0 * input_dim.1;
+        self.flops += match node.op {
+            Ops::BinaryOps(BinaryOps::MUL) => {
+                let other_dim = view.node(node.parents[1]).dim;
+                2 * input_dim.0 * input_dim.1 * other_dim.1
+            }
+            Ops::UnaryOps(UnaryOps::Softmax) => 3 * input_len,
+            Ops::LoadOps(_) => 0,
+            _ => input_len,
+        };
+    }
+}
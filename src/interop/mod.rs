@@ -0,0 +1,7 @@
+// conversions to and from other array libraries, each behind a feature of the same name
+
+#[cfg(feature = "ndarray")]
+pub mod ndarray;
+
+#[cfg(feature = "nalgebra")]
+pub mod nalgebra;
@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+
+use ::nalgebra::DMatrix;
+
+use crate::{ Tensor, Element, DataArray, Dimensions, NanogradError };
+
+///
+/// Convert a `nalgebra` matrix into a tensor on the CPU that does not require gradients.
+///
+/// `DMatrix` stores its elements column by column and tensors row by row, so the elements are
+/// transposed into a new buffer. A single row or column is laid out the same either way and is
+/// handed over without a copy.
+///
+/// # Examples
+///
+/// ```
+/// use nalgebra::{ dmatrix, DMatrix };
+/// use nanograd::Tensor;
+///
+/// let matrix = dmatrix![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+/// let tensor: Tensor<f32> = Tensor::from(matrix.clone());
+/// assert_eq!(tensor.dim(), (2, 3));
+/// assert_eq!(tensor.data().as_ref(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+///
+/// let back: DMatrix<f32> = DMatrix::try_from(tensor).unwrap();
+/// assert_eq!(back, matrix);
+///
+/// let column: Tensor<i64> = Tensor::from(DMatrix::from_vec(3, 1, vec![7, 8, 9]));
+/// assert_eq!(column.dim(), (3, 1));
+/// ```
+impl<T: Element> From<DMatrix<T>> for Tensor<T> {
+    fn from(matrix: DMatrix<T>) -> Self {
+        let dim: Dimensions = matrix.shape();
+        let data: Vec<T> = if dim.0 == 1 || dim.1 == 1 {
+            matrix.data.into()
+        } else {
+            matrix.transpose().data.into()
+        };
+        Tensor::from_vec(data, dim, None, None)
+    }
+}
+
+///
+/// Convert a tensor into a `nalgebra` matrix. The graph of the tensor is dropped, and data on a
+/// device other than the CPU is downloaded first. A single row or column is handed over without
+/// a copy; other shapes are transposed into column-major order.
+///
+/// # Errors
+///
+/// * `UnsupportedDevice` if the device of the tensor has no backend.
+/// * `DataLength` if the tensor no longer holds its data, e.g. after a backward pass released it.
+impl<T: Element> TryFrom<Tensor<T>> for DMatrix<T> {
+    type Error = NanogradError;
+
+    fn try_from(tensor: Tensor<T>) -> Result<Self, Self::Error> {
+        let dim = tensor.dim();
+        to_matrix(tensor.try_into_data()?, dim)
+    }
+}
+
+/// Copy a tensor into a `nalgebra` matrix.
+impl<T: Element> TryFrom<&Tensor<T>> for DMatrix<T> {
+    type Error = NanogradError;
+
+    fn try_from(tensor: &Tensor<T>) -> Result<Self, Self::Error> {
+        to_matrix(tensor.lazy_data.clone().into_data()?, tensor.dim())
+    }
+}
+
+fn to_matrix<T: Element>(data: DataArray<T>, dim: Dimensions) -> Result<DMatrix<T>, NanogradError> {
+    if data.len() != dim.0 * dim.1 {
+        return Err(NanogradError::DataLength { dim, len: data.len() });
+    }
+    if dim.0 == 1 || dim.1 == 1 {
+        Ok(DMatrix::from_vec(dim.0, dim.1, data.into_vec()))
+    } else {
+        Ok(DMatrix::from_row_slice(dim.0, dim.1, &data))
+    }
+}
@@ -0,0 +1,88 @@
+use alloc::vec::Vec;
+
+use ::ndarray::{ Array2, ArrayView2 };
+
+use crate::{ Tensor, Element, NanogradError };
+
+///
+/// Convert an `ndarray` matrix into a tensor on the CPU that does not require gradients.
+///
+/// A matrix in standard, row-major layout hands its buffer over without a copy. Any other layout,
+/// e.g. a column-major matrix or a transposed one, is copied into row-major order.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::{ array, Array2, ShapeBuilder };
+/// use nanograd::Tensor;
+///
+/// let rows = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+/// let tensor: Tensor<f64> = Tensor::from(rows.clone());
+/// assert_eq!(tensor.dim(), (2, 3));
+/// assert_eq!(tensor.data().as_ref(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+///
+/// // the same matrix stored column by column
+/// let columns = Array2::from_shape_vec((2, 3).f(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]).unwrap();
+/// assert_eq!(Tensor::from(columns).data(), tensor.data());
+/// assert_eq!(Tensor::from(rows.t()).dim(), (3, 2));
+///
+/// let back: Array2<f64> = Array2::try_from(tensor).unwrap();
+/// assert_eq!(back, rows);
+/// ```
+impl<T: Element> From<Array2<T>> for Tensor<T> {
+    fn from(array: Array2<T>) -> Self {
+        let (rows, columns) = array.dim();
+        let len = rows * columns;
+        let data: Vec<T> = if array.is_standard_layout() {
+            let (mut data, offset) = array.into_raw_vec_and_offset();
+            let offset = offset.unwrap_or(0);
+            // a sliced matrix keeps the buffer it was sliced from
+            if offset != 0 || data.len() != len {
+                data = data[offset..offset + len].to_vec();
+            }
+            data
+        } else {
+            array.iter().copied().collect()
+        };
+        Tensor::from_vec(data, (rows, columns), None, None)
+    }
+}
+
+/// Copy an `ndarray` view into a tensor on the CPU, in row-major order.
+impl<T: Element> From<ArrayView2<'_, T>> for Tensor<T> {
+    fn from(view: ArrayView2<'_, T>) -> Self {
+        let data: Vec<T> = view.iter().copied().collect();
+        Tensor::from_vec(data, view.dim(), None, None)
+    }
+}
+
+///
+/// Convert a tensor into an `ndarray` matrix without copying its data. The graph of the tensor
+/// is dropped, and data on a device other than the CPU is downloaded first.
+///
+/// # Errors
+///
+/// * `UnsupportedDevice` if the device of the tensor has no backend.
+/// * `DataLength` if the tensor no longer holds its data, e.g. after a backward pass released it.
+impl<T: Element> TryFrom<Tensor<T>> for Array2<T> {
+    type Error = NanogradError;
+
+    fn try_from(tensor: Tensor<T>) -> Result<Self, Self::Error> {
+        let dim = tensor.dim();
+        let data = tensor.try_into_data()?;
+        let len = data.len();
+        Array2::from_shape_vec(dim, data.into_vec()).map_err(|_| NanogradError::DataLength { dim, len })
+    }
+}
+
+/// Copy a tensor into an `ndarray` matrix.
+impl<T: Element> TryFrom<&Tensor<T>> for Array2<T> {
+    type Error = NanogradError;
+
+    fn try_from(tensor: &Tensor<T>) -> Result<Self, Self::Error> {
+        let dim = tensor.dim();
+        let data = tensor.lazy_data.clone().into_data()?;
+        let len = data.len();
+        Array2::from_shape_vec(dim, data.into_vec()).map_err(|_| NanogradError::DataLength { dim, len })
+    }
+}
@@ -0,0 +1,4 @@
+// reading and writing tensors in file formats shared with other frameworks, each behind a feature of the same name
+
+#[cfg(feature = "safetensors")]
+pub mod safetensors;
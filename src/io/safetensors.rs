@@ -0,0 +1,498 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{ self, BufWriter, ErrorKind, Write };
+use std::mem::size_of;
+use std::path::Path;
+
+use bytemuck::Pod;
+use memmap2::Mmap;
+use serde::{ Serialize, Serializer, Deserialize, ser::SerializeMap };
+
+use crate::{ Tensor, Element, Dimensions, NanogradError, f16, bf16 };
+use crate::error::Result;
+use crate::types::dtype::DType;
+
+/// Headers larger than this are rejected before parsing, as the reference implementation does.
+const MAX_HEADER_SIZE: usize = 100_000_000;
+
+/// The header key of the free-form string metadata.
+const METADATA_KEY: &str = "__metadata__";
+
+/// The supported dtypes and their names in the header, by increasing alignment in the order of
+/// the reference implementation. Tensors are written from the last of these to the first.
+const DTYPES: [(DType, &str); 5] = [
+    (DType::F16, "F16"),
+    (DType::BF16, "BF16"),
+    (DType::F32, "F32"),
+    (DType::F64, "F64"),
+    (DType::I64, "I64"),
+];
+
+///
+/// An element type a safetensors file can hold: `f32`, `f64`, `f16`, `bf16` or `i64`.
+pub trait SafeElement: Element + Pod {
+    const DTYPE: DType;
+
+    /// Wrap a tensor of this type for `save`.
+    fn into_any(tensor: Tensor<Self>) -> AnyTensor;
+}
+
+impl SafeElement for f32 {
+    const DTYPE: DType = DType::F32;
+
+    fn into_any(tensor: Tensor<Self>) -> AnyTensor {
+        AnyTensor::F32(tensor)
+    }
+}
+
+impl SafeElement for f64 {
+    const DTYPE: DType = DType::F64;
+
+    fn into_any(tensor: Tensor<Self>) -> AnyTensor {
+        AnyTensor::F64(tensor)
+    }
+}
+
+impl SafeElement for f16 {
+    const DTYPE: DType = DType::F16;
+
+    fn into_any(tensor: Tensor<Self>) -> AnyTensor {
+        AnyTensor::F16(tensor)
+    }
+}
+
+impl SafeElement for bf16 {
+    const DTYPE: DType = DType::BF16;
+
+    fn into_any(tensor: Tensor<Self>) -> AnyTensor {
+        AnyTensor::BF16(tensor)
+    }
+}
+
+impl SafeElement for i64 {
+    const DTYPE: DType = DType::I64;
+
+    fn into_any(tensor: Tensor<Self>) -> AnyTensor {
+        AnyTensor::I64(tensor)
+    }
+}
+
+///
+/// A tensor of any element type a safetensors file can hold, so that one checkpoint can mix them.
+/// Convert a typed tensor with `From`, and match on the variant to get it back.
+pub enum AnyTensor {
+    F32(Tensor<f32>),
+    F64(Tensor<f64>),
+    F16(Tensor<f16>),
+    BF16(Tensor<bf16>),
+    I64(Tensor<i64>),
+}
+
+impl AnyTensor {
+    pub fn dtype(&self) -> DType {
+        match self {
+            AnyTensor::F32(_) => DType::F32,
+            AnyTensor::F64(_) => DType::F64,
+            AnyTensor::F16(_) => DType::F16,
+            AnyTensor::BF16(_) => DType::BF16,
+            AnyTensor::I64(_) => DType::I64,
+        }
+    }
+
+    pub fn dim(&self) -> Dimensions {
+        match self {
+            AnyTensor::F32(tensor) => tensor.dim(),
+            AnyTensor::F64(tensor) => tensor.dim(),
+            AnyTensor::F16(tensor) => tensor.dim(),
+            AnyTensor::BF16(tensor) => tensor.dim(),
+            AnyTensor::I64(tensor) => tensor.dim(),
+        }
+    }
+
+    fn write_data<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            AnyTensor::F32(tensor) => write_le(writer, &tensor.lazy_data.host_data()?),
+            AnyTensor::F64(tensor) => write_le(writer, &tensor.lazy_data.host_data()?),
+            AnyTensor::F16(tensor) => write_le(writer, &tensor.lazy_data.host_data()?),
+            AnyTensor::BF16(tensor) => write_le(writer, &tensor.lazy_data.host_data()?),
+            AnyTensor::I64(tensor) => write_le(writer, &tensor.lazy_data.host_data()?),
+        }
+    }
+}
+
+impl<T: SafeElement> From<Tensor<T>> for AnyTensor {
+    fn from(tensor: Tensor<T>) -> Self {
+        T::into_any(tensor)
+    }
+}
+
+/// Where a tensor is in the file, as written in the header.
+#[derive(Serialize, Deserialize)]
+struct TensorInfo {
+    dtype: String,
+    shape: Vec<usize>,
+    data_offsets: (usize, usize),
+}
+
+/// The header as it is written: the metadata first, if any, then the tensors in file order.
+struct Header<'a> {
+    metadata: &'a BTreeMap<String, String>,
+    tensors: Vec<(&'a str, TensorInfo)>,
+}
+
+impl Serialize for Header<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        let has_metadata = !self.metadata.is_empty();
+        let mut map = serializer.serialize_map(Some(self.tensors.len() + has_metadata as usize))?;
+        if has_metadata {
+            map.serialize_entry(METADATA_KEY, self.metadata)?;
+        }
+        for (name, info) in &self.tensors {
+            map.serialize_entry(name, info)?;
+        }
+        map.end()
+    }
+}
+
+/// The header as it is read, in any order.
+#[derive(Deserialize)]
+struct HeaderParts {
+    #[serde(rename = "__metadata__", default)]
+    metadata: BTreeMap<String, String>,
+    #[serde(flatten)]
+    tensors: BTreeMap<String, TensorInfo>,
+}
+
+///
+/// Save named tensors and string metadata to a safetensors file, the checkpoint format shared with
+/// PyTorch and other frameworks. Tensors on a device other than the CPU are downloaded first.
+///
+/// Files are reproducible: tensors are laid out like the reference implementation does, by
+/// decreasing alignment and then by name, and the metadata is sorted by key.
+///
+/// # Arguments
+///
+/// * `tensors` - The tensors by name.
+/// * `metadata` - Free-form strings stored in the header, e.g. `format: pt`. May be empty.
+/// * `path` - Where to write the file.
+///
+/// # Errors
+///
+/// * `Io` if the file cannot be written, or a tensor is named `__metadata__`.
+/// * `UnsupportedDevice` if the device of a tensor has no backend.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use nanograd::Tensor;
+/// use nanograd::io::safetensors::{ save, load, AnyTensor };
+///
+/// let weight: Tensor<f32> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+/// let step: Tensor<i64> = Tensor::from_vec(vec![1000], (1, 1), None, None);
+///
+/// let mut tensors: BTreeMap<String, AnyTensor> = BTreeMap::new();
+/// tensors.insert("weight".to_string(), weight.into());
+/// tensors.insert("step".to_string(), step.into());
+/// let metadata = BTreeMap::from([("format".to_string(), "pt".to_string())]);
+///
+/// let path = std::env::temp_dir().join("nanograd_save_example.safetensors");
+/// save(&tensors, &metadata, &path).unwrap();
+///
+/// let file = load(&path).unwrap();
+/// assert_eq!(file.metadata()["format"], "pt");
+/// assert_eq!(file.tensor::<f32>("weight").unwrap().dim(), (2, 2));
+/// assert_eq!(file.view::<i64>("step").unwrap().as_ref(), &[1000]);
+/// ```
+pub fn save<P: AsRef<Path>>(tensors: &BTreeMap<String, AnyTensor>, metadata: &BTreeMap<String, String>, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(tensors, metadata, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write named tensors and metadata in the safetensors format, e.g. to a buffer. See `save`.
+pub fn write<W: Write>(tensors: &BTreeMap<String, AnyTensor>, metadata: &BTreeMap<String, String>, writer: &mut W) -> Result<()> {
+    if tensors.contains_key(METADATA_KEY) {
+        return Err(io_error(ErrorKind::InvalidInput, format!("A tensor cannot be named `{}`", METADATA_KEY)));
+    }
+    let mut order: Vec<(&String, &AnyTensor)> = tensors.iter().collect();
+    order.sort_by(|(left_name, left), (right_name, right)| {
+        rank(right.dtype()).cmp(&rank(left.dtype())).then(left_name.cmp(right_name))
+    });
+
+    let mut offset = 0;
+    let mut infos: Vec<(&str, TensorInfo)> = Vec::with_capacity(order.len());
+    for (name, tensor) in &order {
+        let (rows, columns) = tensor.dim();
+        let len = rows * columns * tensor.dtype().size();
+        let info = TensorInfo {
+            dtype: code(tensor.dtype()).to_string(),
+            shape: vec![rows, columns],
+            data_offsets: (offset, offset + len),
+        };
+        infos.push((name.as_str(), info));
+        offset += len;
+    }
+
+    let header = Header { metadata, tensors: infos };
+    let mut header = serde_json::to_vec(&header).map_err(|error| io_error(ErrorKind::InvalidData, error.to_string()))?;
+    // pad with spaces so the data starts 8-byte aligned
+    header.resize(header.len().next_multiple_of(8), b' ');
+
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(&header)?;
+    for (_, tensor) in order {
+        tensor.write_data(writer)?;
+    }
+    Ok(())
+}
+
+///
+/// Open a safetensors file by memory-mapping it. Only the header is read up front; the data of a
+/// tensor is read from the mapping when it is accessed. Reading it without a copy is only possible
+/// through `SafeTensors::view`, since tensors own their data.
+///
+/// # Errors
+///
+/// * `Io` if the file cannot be opened or mapped, or is not a valid safetensors file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<SafeTensors> {
+    let file = File::open(path)?;
+    // SAFETY: the mapping is read-only. Like every memory-mapped reader, it assumes no other
+    // process truncates or rewrites the file while it is open.
+    let mmap = unsafe { Mmap::map(&file)? };
+    SafeTensors::parse(mmap)
+}
+
+///
+/// A memory-mapped safetensors file. `view` is the zero-copy path: it borrows the data of a tensor
+/// read-only, straight from the mapping. `tensor` and `tensors` copy the data into new tensors on
+/// the default device, because a `Tensor` owns its buffer and cannot borrow the file.
+///
+/// Tensors with one dimension load as a single row and scalars as `1 x 1`. Tensors with more
+/// dimensions, and dtypes other than those of `SafeElement`, can be listed but not read.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use nanograd::{ Tensor, bf16, NanogradError };
+/// use nanograd::io::safetensors::{ save, load, AnyTensor };
+/// use nanograd::types::dtype::DType;
+///
+/// let embedding: Tensor<bf16> = Tensor::from_vec(vec![bf16::from_f32(0.5); 6], (3, 2), None, None);
+/// let tensors = BTreeMap::from([("embedding".to_string(), AnyTensor::from(embedding))]);
+/// let path = std::env::temp_dir().join("nanograd_load_example.safetensors");
+/// save(&tensors, &BTreeMap::new(), &path).unwrap();
+///
+/// let file = load(&path).unwrap();
+/// assert_eq!(file.names().collect::<Vec<_>>(), vec!["embedding"]);
+/// assert_eq!(file.dtype("embedding").unwrap(), DType::BF16);
+/// assert_eq!(file.shape("embedding").unwrap(), &[3, 2]);
+/// assert!(file.metadata().is_empty());
+///
+/// // the dtype has to match
+/// assert!(matches!(file.tensor::<f32>("embedding"), Err(NanogradError::DTypeConversion { .. })));
+/// assert!(matches!(file.tensors().unwrap()["embedding"], AnyTensor::BF16(_)));
+/// ```
+pub struct SafeTensors {
+    mmap: Mmap,
+    data_start: usize,
+    metadata: BTreeMap<String, String>,
+    tensors: BTreeMap<String, TensorInfo>,
+}
+
+impl SafeTensors {
+    /// Read and check the header: the tensors must fill the data without gaps or overlaps.
+    fn parse(mmap: Mmap) -> Result<Self> {
+        let size: [u8; 8] = mmap.get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("The file is too short for a header".to_string()))?;
+        let header_len = usize::try_from(u64::from_le_bytes(size))
+            .ok()
+            .filter(|len| *len <= MAX_HEADER_SIZE)
+            .ok_or_else(|| invalid("The header is too large".to_string()))?;
+        let data_start = 8 + header_len;
+        let header = mmap.get(8..data_start)
+            .ok_or_else(|| invalid(format!("The file is too short for a header of {} bytes", header_len)))?;
+        let HeaderParts { metadata, tensors } = serde_json::from_slice(header)
+            .map_err(|error| invalid(format!("Invalid header: {}", error)))?;
+
+        let mut order: Vec<(&String, &TensorInfo)> = tensors.iter().collect();
+        order.sort_by_key(|(_, info)| info.data_offsets);
+        let mut offset = 0;
+        for (name, info) in order {
+            let (begin, end) = info.data_offsets;
+            if begin != offset || end < begin {
+                return Err(invalid(format!("The data of `{}` overlaps another tensor or leaves a gap", name)));
+            }
+            if let Some(dtype) = parse_code(&info.dtype) {
+                let len = info.shape.iter().try_fold(dtype.size(), |len, axis| len.checked_mul(*axis));
+                if len != Some(end - begin) {
+                    return Err(invalid(format!("The data of `{}` does not match its shape {:?}", name, info.shape)));
+                }
+            }
+            offset = end;
+        }
+        // offsets of unsupported dtypes are not checked against a shape, so they can be anything
+        if data_start.checked_add(offset) != Some(mmap.len()) {
+            return Err(invalid(format!("The header describes {} bytes of data, the file has {}", offset, mmap.len() - data_start)));
+        }
+        Ok(SafeTensors { mmap, data_start, metadata, tensors })
+    }
+
+    /// The metadata strings of the header, empty if it has none.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// The names of the tensors, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tensors.keys().map(String::as_str)
+    }
+
+    ///
+    /// The dtype of a tensor.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` if there is no tensor named `name`, or its dtype is not supported.
+    pub fn dtype(&self, name: &str) -> Result<DType> {
+        let info = self.info(name)?;
+        parse_code(&info.dtype).ok_or_else(|| {
+            invalid(format!("The dtype {} of `{}` is not supported", info.dtype, name))
+        })
+    }
+
+    ///
+    /// The shape of a tensor as stored, with any number of dimensions.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` if there is no tensor named `name`.
+    pub fn shape(&self, name: &str) -> Result<&[usize]> {
+        Ok(&self.info(name)?.shape)
+    }
+
+    ///
+    /// Borrow the elements of a tensor from the mapped file, in row-major order.
+    ///
+    /// The elements are borrowed without a copy. They are only copied on big-endian targets, or
+    /// if the data of the tensor is not aligned for `T`, which files written by `save` and by the
+    /// reference implementation always are.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` if there is no tensor named `name`, or its dtype is not supported.
+    /// * `DTypeConversion` if the tensor is not of type `T`.
+    pub fn view<T: SafeElement>(&self, name: &str) -> Result<Cow<'_, [T]>> {
+        let dtype = self.dtype(name)?;
+        if dtype != T::DTYPE {
+            return Err(NanogradError::DTypeConversion {
+                from: code(dtype),
+                to: code(T::DTYPE),
+                value: name.to_string(),
+            });
+        }
+        let (begin, end) = self.info(name)?.data_offsets;
+        let bytes = &self.mmap[self.data_start + begin..self.data_start + end];
+        if cfg!(target_endian = "little") {
+            if let Ok(data) = bytemuck::try_cast_slice(bytes) {
+                return Ok(Cow::Borrowed(data));
+            }
+        }
+        Ok(Cow::Owned(bytes.chunks_exact(size_of::<T>()).map(read_le).collect()))
+    }
+
+    ///
+    /// Copy a tensor out of the file into a tensor that does not require gradients. Use `view` to
+    /// read the data without copying it.
+    ///
+    /// # Errors
+    ///
+    /// * The errors of `view`.
+    /// * `UnsupportedOp` if the tensor has more than two dimensions.
+    pub fn tensor<T: SafeElement>(&self, name: &str) -> Result<Tensor<T>> {
+        let dim: Dimensions = match self.shape(name)? {
+            [] => (1, 1),
+            [columns] => (1, *columns),
+            [rows, columns] => (*rows, *columns),
+            shape => {
+                let reason = format!("tensors have at most 2 dimensions, `{}` has {}", name, shape.len());
+                return Err(NanogradError::unsupported("safetensors", &reason));
+            }
+        };
+        Tensor::try_from_vec(self.view::<T>(name)?.into_owned(), dim, None, None)
+    }
+
+    ///
+    /// Copy every tensor out of the file, e.g. to `save` them again.
+    ///
+    /// # Errors
+    ///
+    /// * The errors of `tensor`, for any tensor in the file.
+    pub fn tensors(&self) -> Result<BTreeMap<String, AnyTensor>> {
+        self.names()
+            .map(|name| {
+                let tensor = match self.dtype(name)? {
+                    DType::F32 => AnyTensor::F32(self.tensor(name)?),
+                    DType::F64 => AnyTensor::F64(self.tensor(name)?),
+                    DType::F16 => AnyTensor::F16(self.tensor(name)?),
+                    DType::BF16 => AnyTensor::BF16(self.tensor(name)?),
+                    // the only other dtype `dtype` returns
+                    _ => AnyTensor::I64(self.tensor(name)?),
+                };
+                Ok((name.to_string(), tensor))
+            })
+            .collect()
+    }
+
+    fn info(&self, name: &str) -> Result<&TensorInfo> {
+        self.tensors.get(name).ok_or_else(|| io_error(ErrorKind::NotFound, format!("No tensor named `{}`", name)))
+    }
+}
+
+/// The position of a dtype in the file order of the reference implementation.
+fn rank(dtype: DType) -> usize {
+    DTYPES.iter().position(|(supported, _)| *supported == dtype).unwrap_or(0)
+}
+
+fn code(dtype: DType) -> &'static str {
+    DTYPES.iter()
+        .find(|(supported, _)| *supported == dtype)
+        .map_or("unsupported", |(_, code)| code)
+}
+
+fn parse_code(code: &str) -> Option<DType> {
+    DTYPES.iter().find(|(_, supported)| *supported == code).map(|(dtype, _)| *dtype)
+}
+
+fn write_le<T: Pod, W: Write>(writer: &mut W, data: &[T]) -> Result<()> {
+    if cfg!(target_endian = "little") {
+        writer.write_all(bytemuck::cast_slice(data))?;
+    } else {
+        for value in data {
+            let mut value = *value;
+            bytemuck::bytes_of_mut(&mut value).reverse();
+            writer.write_all(bytemuck::bytes_of(&value))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_le<T: Pod>(bytes: &[u8]) -> T {
+    let mut value: T = bytemuck::pod_read_unaligned(bytes);
+    if cfg!(target_endian = "big") {
+        bytemuck::bytes_of_mut(&mut value).reverse();
+    }
+    value
+}
+
+fn io_error(kind: ErrorKind, message: String) -> NanogradError {
+    NanogradError::Io(io::Error::new(kind, message))
+}
+
+fn invalid(message: String) -> NanogradError {
+    io_error(ErrorKind::InvalidData, message)
+}
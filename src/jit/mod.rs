@@ -0,0 +1,521 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+use std::rc::Rc;
+
+use crate::{ Tensor, TensorTrait, Ops, DataArray, Dimensions, Device };
+use crate::autograd::function::{ Context, CustomOp };
+use crate::backend::{ pool::recycle, Storage };
+use crate::backward::functional::vjp_data;
+use crate::graph::ir::{ Graph, NodeKind };
+use crate::graph::memory::{ BufferKind, MemoryPlan };
+use crate::graph::passes::{ optimize, Passes, PassReport };
+use crate::types::ops::LoadOps;
+use crate::types::shape::{ Bindings, Shape };
+
+/// The context of every custom op during a call.
+type Contexts<T> = Vec<Option<Rc<Context<T>>>>;
+
+///
+/// Why a compiled function refused its inputs.
+///
+/// * `InputCount` - A different number of inputs was passed than were traced.
+/// * `InputShape` - An input does not fit the shape it was traced with, or binds a variable to a
+///   different size than an earlier input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceError {
+    InputCount {
+        expected: usize,
+        found: usize,
+    },
+    InputShape {
+        input: usize,
+        expected: Shape,
+        found: Dimensions,
+    },
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::InputCount { expected, found } => {
+                write!(f, "traced function takes {} inputs but {} were given", expected, found)
+            }
+            TraceError::InputShape { input, expected, found } => {
+                write!(
+                    f,
+                    "input {} was traced as {} but is {}x{}; trace again for the new shape",
+                    input,
+                    expected,
+                    found.0,
+                    found.1
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+///
+/// A traced function: an optimized graph that reruns on new input data without recording it again.
+///
+/// Leaves passed as inputs to `trace` take new data on every call. Other leaves that require
+/// gradients are parameters; their data is kept in the compiled function and can be replaced with
+/// `set_parameter` between training steps. The input shapes are fixed by the trace, except for
+/// the variables of a symbolic trace, which are bound to the sizes of the inputs on every call.
+///
+/// Every call runs in one arena laid out by the `MemoryPlan` of the bound graph: each op writes
+/// its value, and the backward pass each gradient, at its planned offset. The arena is kept and
+/// reused by the next call, so buffers whose lifetimes do not overlap share memory.
+pub struct Compiled<T: TensorTrait<T>> {
+    graph: Graph<T>,
+    parameters: Vec<usize>,
+    /// Whether each node is on a path from a parameter, and so gets a gradient.
+    needs_grad: Vec<bool>,
+    report: PassReport,
+    /// The graph with its variables bound, for every binding seen so far.
+    bound: RefCell<HashMap<Bindings, Rc<Graph<T>>>>,
+    /// The memory plan of a training step, for every binding seen so far.
+    plans: RefCell<HashMap<Bindings, Rc<MemoryPlan>>>,
+    /// The arena of the last call.
+    arena: RefCell<Vec<T>>,
+}
+
+///
+/// Record the graph `f` builds once, optimize it and plan the memory of its forward and backward pass.
+/// The passes run are the ones `Passes::from_env` enables.
+///
+/// # Arguments
+///
+/// * `f` - The function to trace. It must not branch on the data of its inputs, since only the
+///   path taken for `example_inputs` is recorded.
+/// * `example_inputs` - Inputs with the shapes every later call will use.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, jit, autograd::grad, nn::{ activation::relu, transformation::sum } };
+///
+/// let w: Tensor<f64> = Tensor::from_vec(vec![0.5, -1.0, 2.0, 0.25, -0.5, 1.0], (3, 2), None, Some(true));
+/// let x: Tensor<f64> = Tensor::ones((4, 3), None, None);
+///
+/// let model = |inputs: Vec<Tensor<f64>>| sum(relu(inputs[0].clone() * w.clone()));
+/// let step = jit::trace(model, &[x]);
+///
+/// // rerun on a new batch without recording the graph again
+/// let batch: Tensor<f64> = Tensor::from_vec((0..12).map(|i| i as f64).collect(), (4, 3), None, None);
+/// let (loss, grads) = step.run_with_grad(&[batch.clone()]).unwrap();
+///
+/// let eager = grad(|w| sum(relu(batch.clone() * w[0].clone())), &[w.clone()]);
+/// assert_eq!(grads[0].data(), eager[0].data());
+/// assert_eq!(loss.data(), step.run(&[batch]).unwrap().data());
+///
+/// // a batch of a different size needs a new trace
+/// let wrong: Tensor<f64> = Tensor::ones((8, 3), None, None);
+/// assert!(step.run(&[wrong]).is_err());
+/// ```
+pub fn trace<T, F>(f: F, example_inputs: &[Tensor<T>]) -> Compiled<T>
+    where T: TensorTrait<T>, F: FnOnce(Vec<Tensor<T>>) -> Tensor<T>
+{
+    let shapes: Vec<Shape> = example_inputs
+        .iter()
+        .map(|input| Shape::from(input.dim()))
+        .collect();
+    trace_symbolic(f, example_inputs, &shapes)
+}
+
+///
+/// Like `trace`, but the inputs have shapes with variables, such as a `batch` number of rows.
+/// Shapes are inferred through the graph, and every call binds the variables to the sizes of its
+/// inputs, so one trace serves batches of any size.
+///
+/// # Arguments
+///
+/// * `f` - The function to trace.
+/// * `example_inputs` - Inputs that fit `shapes`.
+/// * `shapes` - The shape of each input.
+///
+/// # Panics
+///
+/// * If an example input does not fit its shape.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, jit, nn::{ activation::relu, transformation::sum } };
+/// use nanograd::types::shape::{ Bindings, Dim, Shape };
+///
+/// let w: Tensor<f64> = Tensor::ones((3, 2), None, Some(true));
+/// let x: Tensor<f64> = Tensor::ones((4, 3), None, None);
+///
+/// let model = |inputs: Vec<Tensor<f64>>| sum(relu(inputs[0].clone() * w.clone() + 1.0));
+/// let step = jit::trace_symbolic(model, &[x], &[Shape(Dim::var("batch"), Dim::from(3))]);
+///
+/// // the last batch of an epoch is smaller
+/// for rows in [4, 4, 1] {
+///     let batch: Tensor<f64> = Tensor::ones((rows, 3), None, None);
+///     let (loss, grads) = step.run_with_grad(&[batch]).unwrap();
+///     assert_eq!(loss.data()[0], 8.0 * rows as f64);
+///     assert_eq!(grads[0].data()[0], rows as f64);
+/// }
+///
+/// // the number of columns is still fixed
+/// assert!(step.run(&[Tensor::ones((4, 5), None, None)]).is_err());
+///
+/// let mut bindings = Bindings::new();
+/// bindings.insert("batch".to_string(), 1);
+/// let small = step.memory_plan(&bindings);
+/// bindings.insert("batch".to_string(), 256);
+/// assert!(small.peak_bytes() < step.memory_plan(&bindings).peak_bytes());
+/// ```
+///
+/// Constants built from the traced batch size, like the column of ones a `Linear` layer spreads
+/// its bias with, follow the batch as well:
+///
+/// ```
+/// use nanograd::{ Tensor, jit, nn::linear::Linear };
+/// use nanograd::types::shape::{ Dim, Shape };
+///
+/// let w: Tensor<f64> = Tensor::from_vec(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0], (3, 2), None, None);
+/// let x: Tensor<f64> = Tensor::ones((4, 3), None, None);
+///
+/// for trainable in [false, true] {
+///     let b: Tensor<f64> = Tensor::from_vec(vec![0.5, -0.5], (1, 2), None, Some(trainable));
+///     let layer = Linear::from_weights(w.clone(), Some(b));
+///     let step = jit::trace_symbolic(|inputs| layer.forward(inputs[0].clone()), &[x.clone()], &[Shape(Dim::var("batch"), Dim::from(3))]);
+///
+///     let batch: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 0.0, 0.0, 0.0], (2, 3), None, None);
+///     let y = step.run(&[batch]).unwrap();
+///     assert_eq!(y.dim(), (2, 2));
+///     assert_eq!(y.data().as_ref(), &[4.5, 4.5, 0.5, -0.5]);
+/// }
+/// ```
+pub fn trace_symbolic<T, F>(f: F, example_inputs: &[Tensor<T>], shapes: &[Shape]) -> Compiled<T>
+    where T: TensorTrait<T>, F: FnOnce(Vec<Tensor<T>>) -> Tensor<T>
+{
+    let output = f(example_inputs.to_vec());
+    let inputs: Vec<&Tensor<T>> = example_inputs.iter().collect();
+    let mut graph = Graph::from_tensors(&[&output], &inputs);
+    let report = optimize(&mut graph, &Passes::from_env());
+    graph.set_input_shapes(shapes);
+    Compiled::new(graph, report)
+}
+
+impl<T: TensorTrait<T>> Compiled<T> {
+    /// Compile an already recorded graph with a single output.
+    pub fn new(graph: Graph<T>, report: PassReport) -> Self {
+        if graph.outputs().len() != 1 {
+            panic!("A compiled graph must have exactly one output");
+        }
+        let parameters: Vec<usize> = graph
+            .nodes()
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.kind == NodeKind::Parameter)
+            .map(|(index, _)| index)
+            .collect();
+        let mut needs_grad = vec![false; graph.len()];
+        for (index, node) in graph.nodes().iter().enumerate() {
+            needs_grad[index] =
+                node.kind == NodeKind::Parameter ||
+                node.inputs.iter().any(|input| needs_grad[*input]);
+        }
+        Compiled {
+            graph,
+            parameters,
+            needs_grad,
+            report,
+            bound: RefCell::new(HashMap::new()),
+            plans: RefCell::new(HashMap::new()),
+            arena: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The optimized graph that is run.
+    pub fn graph(&self) -> &Graph<T> {
+        &self.graph
+    }
+
+    /// What the passes changed when the function was traced.
+    pub fn report(&self) -> PassReport {
+        self.report
+    }
+
+    /// The shape each input must have, in order.
+    pub fn input_shapes(&self) -> Vec<Shape> {
+        self.graph.input_shapes()
+    }
+
+    ///
+    /// The memory plan calls run with when the variables of the input shapes are bound to `bindings`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, jit, autograd::grad, nn::{ activation::relu, transformation::sum } };
+    /// use nanograd::types::shape::Bindings;
+    ///
+    /// let w1: Tensor<f64> = Tensor::from_vec((0..12).map(|i| i as f64 / 6.0 - 1.0).collect(), (3, 4), None, Some(true));
+    /// let w2: Tensor<f64> = Tensor::from_vec((0..16).map(|i| 1.0 - i as f64 / 8.0).collect(), (4, 4), None, Some(true));
+    /// let w3: Tensor<f64> = Tensor::from_vec(vec![1.0, -2.0, 0.5, 3.0], (4, 1), None, Some(true));
+    /// let x: Tensor<f64> = Tensor::from_vec((0..15).map(|i| i as f64 - 7.0).collect(), (5, 3), None, None);
+    ///
+    /// let model = |w: &[Tensor<f64>], x: Tensor<f64>| sum(relu(relu(x * w[0].clone()) * w[1].clone()) * w[2].clone());
+    /// let weights = [w1.clone(), w2.clone(), w3.clone()];
+    /// let step = jit::trace(|inputs| model(&weights, inputs[0].clone()), &[x.clone()]);
+    ///
+    /// // buffers that are dead by the time another is written share its memory
+    /// let plan = step.memory_plan(&Bindings::new());
+    /// assert!(plan.peak_bytes() < plan.naive_bytes());
+    ///
+    /// let eager = grad(|w| model(&w, x.clone()), &[w1, w2, w3]);
+    /// for _ in 0..2 {
+    ///     let (loss, grads) = step.run_with_grad(&[x.clone()]).unwrap();
+    ///     assert_eq!(loss.data(), step.run(&[x.clone()]).unwrap().data());
+    ///     for (compiled, eager) in grads.iter().zip(eager.iter()) {
+    ///         assert_eq!(compiled.data(), eager.data());
+    ///     }
+    /// }
+    /// ```
+    pub fn memory_plan(&self, bindings: &Bindings) -> MemoryPlan {
+        MemoryPlan::from_graph(&self.graph, bindings)
+    }
+
+    /// The current value of every parameter, in the order they appear in the graph.
+    pub fn parameters(&self) -> Vec<Tensor<T>> {
+        self.parameters
+            .iter()
+            .map(|index| {
+                let node = self.graph.node(*index);
+                Tensor::new(node.data.clone().unwrap(), node.dim, None, Some(true))
+            })
+            .collect()
+    }
+
+    ///
+    /// Replace the data of a parameter, e.g. after an optimizer step.
+    ///
+    /// # Panics
+    ///
+    /// * If there is no such parameter or the data has the wrong length.
+    pub fn set_parameter(&mut self, index: usize, data: DataArray<T>) {
+        let node = match self.parameters.get(index) {
+            Some(node) => *node,
+            None => panic!("Compiled function has {} parameters, not {}", self.parameters.len(), index + 1),
+        };
+        let node = self.graph.node_mut(node);
+        if data.len() != node.dim.0 * node.dim.1 {
+            panic!("Parameter {} is {}x{} but {} values were given", index, node.dim.0, node.dim.1, data.len());
+        }
+        node.data = Some(data);
+    }
+
+    ///
+    /// Compute the output for new inputs.
+    ///
+    /// # Errors
+    ///
+    /// * If the number or the shapes of the inputs differ from the trace.
+    pub fn run(&self, inputs: &[Tensor<T>]) -> Result<Tensor<T>, TraceError> {
+        self.with_bound(inputs, |graph, plan| {
+            let mut arena = self.take_arena(plan);
+            self.forward(graph, plan, &mut arena, inputs);
+            let output = self.output(graph, plan, &arena, inputs);
+            self.arena.replace(arena);
+            output
+        })
+    }
+
+    ///
+    /// Compute the output for new inputs and the gradients of the output with respect to every
+    /// parameter, seeding the backward pass with ones.
+    ///
+    /// # Returns
+    ///
+    /// The output and one gradient per parameter, in the order of `parameters`.
+    ///
+    /// # Errors
+    ///
+    /// * If the number or the shapes of the inputs differ from the trace.
+    pub fn run_with_grad(&self, inputs: &[Tensor<T>]) -> Result<(Tensor<T>, Vec<Tensor<T>>), TraceError> {
+        self.with_bound(inputs, |graph, plan| {
+            let mut arena = self.take_arena(plan);
+            let result = self.forward_backward(graph, plan, &mut arena, inputs);
+            self.arena.replace(arena);
+            result
+        })
+    }
+
+    fn forward_backward(
+        &self,
+        graph: &Graph<T>,
+        plan: &MemoryPlan,
+        arena: &mut [T],
+        inputs: &[Tensor<T>]
+    ) -> (Tensor<T>, Vec<Tensor<T>>) {
+        let contexts = self.forward(graph, plan, arena, inputs);
+        let n = graph.len();
+        let root = graph.outputs()[0];
+        // the arena holds stale data until a gradient is first written
+        let mut has_grad = vec![false; n];
+        for index in (0..n).rev() {
+            if index == root && self.needs_grad[root] {
+                plan.slice_mut(arena, root, BufferKind::Gradient).fill(T::one());
+                has_grad[root] = true;
+            }
+            let node = graph.node(index);
+            if node.kind != NodeKind::Op || !self.needs_grad[index] || !has_grad[index] {
+                continue;
+            }
+            let input_grads = {
+                let node_inputs: Vec<(&[T], Dimensions)> = node.inputs
+                    .iter()
+                    .map(|input| (self.value(graph, plan, arena, inputs, *input), graph.node(*input).dim))
+                    .collect();
+                let custom = node.custom.as_ref().map(|custom| CustomOp {
+                    function: custom.function.clone(),
+                    ctx: contexts[index].clone().unwrap(),
+                });
+                let output = (plan.slice(arena, index, BufferKind::Value), node.dim);
+                let grad_output = plan.slice(arena, index, BufferKind::Gradient);
+                vjp_data(node.op, custom.as_ref(), &node_inputs, output, grad_output)
+            };
+            for (input, grad) in node.inputs.iter().zip(input_grads) {
+                if self.needs_grad[*input] {
+                    let slot = plan.slice_mut(arena, *input, BufferKind::Gradient);
+                    if has_grad[*input] {
+                        for (existing, value) in slot.iter_mut().zip(grad.iter()) {
+                            *existing = *existing + *value;
+                        }
+                    } else {
+                        slot.copy_from_slice(&grad);
+                        has_grad[*input] = true;
+                    }
+                }
+                recycle(&Device::CPU, Storage::Host(grad));
+            }
+        }
+        let parameter_grads = self.parameters
+            .iter()
+            .map(|index| {
+                let dim = self.graph.node(*index).dim;
+                let grad = if has_grad[*index] {
+                    plan.slice(arena, *index, BufferKind::Gradient).into()
+                } else {
+                    vec![T::zero(); dim.0 * dim.1].into_boxed_slice()
+                };
+                Tensor::new(grad, dim, None, None)
+            })
+            .collect();
+        (self.output(graph, plan, arena, inputs), parameter_grads)
+    }
+
+    ///
+    /// Check the inputs against the traced shapes and run `f` on the graph with the variables bound
+    /// to the sizes of the inputs, and on its memory plan. Both are kept for the next call with the
+    /// same sizes.
+    fn with_bound<R>(&self, inputs: &[Tensor<T>], f: impl FnOnce(&Graph<T>, &MemoryPlan) -> R) -> Result<R, TraceError> {
+        let expected = self.input_shapes();
+        if inputs.len() != expected.len() {
+            return Err(TraceError::InputCount { expected: expected.len(), found: inputs.len() });
+        }
+        let mut bindings = Bindings::new();
+        for (input, (tensor, shape)) in inputs.iter().zip(expected).enumerate() {
+            if !shape.unify(tensor.dim(), &mut bindings) {
+                return Err(TraceError::InputShape { input, expected: shape, found: tensor.dim() });
+            }
+        }
+        let plan = self.plans
+            .borrow_mut()
+            .entry(bindings.clone())
+            .or_insert_with_key(|bindings| Rc::new(MemoryPlan::from_graph(&self.graph, bindings)))
+            .clone();
+        if !self.graph.is_symbolic() {
+            return Ok(f(&self.graph, &plan));
+        }
+        let graph = self.bound
+            .borrow_mut()
+            .entry(bindings)
+            .or_insert_with_key(|bindings| Rc::new(self.graph.bind(bindings)))
+            .clone();
+        Ok(f(&graph, &plan))
+    }
+
+    ///
+    /// Take the arena of the last call, grown to fit `plan`. A call made from inside a custom op
+    /// finds the arena taken and allocates its own.
+    fn take_arena(&self, plan: &MemoryPlan) -> Vec<T> {
+        let arena = self.arena.take();
+        if arena.len() * size_of::<T>() < plan.peak_bytes() {
+            plan.arena()
+        } else {
+            arena
+        }
+    }
+
+    ///
+    /// Run the forward pass of a bound graph, writing the value of every op at its place in the
+    /// arena. Keeps the context of custom ops.
+    fn forward(&self, graph: &Graph<T>, plan: &MemoryPlan, arena: &mut [T], inputs: &[Tensor<T>]) -> Contexts<T> {
+        let mut contexts: Contexts<T> = vec![None; graph.len()];
+        for (index, node) in graph.nodes().iter().enumerate() {
+            if node.kind != NodeKind::Op {
+                continue;
+            }
+            let value = {
+                let node_inputs: Vec<&[T]> = node.inputs
+                    .iter()
+                    .map(|input| self.value(graph, plan, arena, inputs, *input))
+                    .collect();
+                match node.op {
+                    Ops::LoadOps(LoadOps::CUSTOM) => {
+                        let custom = node.custom.as_ref().unwrap();
+                        let tensors: Vec<Tensor<T>> = node_inputs
+                            .iter()
+                            .zip(node.inputs.iter())
+                            .map(|(data, input)| Tensor::new((*data).into(), graph.node(*input).dim, None, None))
+                            .collect();
+                        let mut ctx = Context::new();
+                        let output = custom.function.forward(&mut ctx, &tensors);
+                        contexts[index] = Some(Rc::new(ctx));
+                        output.data().clone()
+                    }
+                    _ => graph.evaluate_node(node, &node_inputs),
+                }
+            };
+            plan.slice_mut(arena, index, BufferKind::Value).copy_from_slice(&value);
+            recycle(&Device::CPU, Storage::Host(value));
+        }
+        contexts
+    }
+
+    ///
+    /// The value of a node during a call. Ops are read from the arena, inputs from the call and
+    /// parameters from the compiled function, so bound graphs never go stale.
+    fn value<'a>(
+        &'a self,
+        graph: &'a Graph<T>,
+        plan: &MemoryPlan,
+        arena: &'a [T],
+        inputs: &'a [Tensor<T>],
+        index: usize
+    ) -> &'a [T] {
+        let node = graph.node(index);
+        match node.kind {
+            NodeKind::Op => plan.slice(arena, index, BufferKind::Value),
+            NodeKind::Input => {
+                let position = graph.inputs().iter().position(|input| *input == index).unwrap();
+                inputs[position].data()
+            }
+            NodeKind::Parameter => self.graph.node(index).data.as_ref().unwrap(),
+            NodeKind::Constant => node.data.as_ref().unwrap(),
+        }
+    }
+
+    fn output(&self, graph: &Graph<T>, plan: &MemoryPlan, arena: &[T], inputs: &[Tensor<T>]) -> Tensor<T> {
+        let root = graph.outputs()[0];
+        Tensor::new(self.value(graph, plan, arena, inputs, root).into(), graph.node(root).dim, None, None)
+    }
+}
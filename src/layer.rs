@@ -2,6 +2,7 @@ use crate::{ neuron::Neuron, value::Value };
 
 // struct adopted from https://github.com/danielway/micrograd-rs
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Layer {
     neurons: Vec<Neuron>,
 }
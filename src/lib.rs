@@ -1,13 +1,25 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 mod value;
+#[cfg(feature = "std")]
 pub use crate::value::Value;
 
+#[cfg(feature = "std")]
 mod neuron;
+#[cfg(feature = "std")]
 pub use crate::neuron::Neuron;
 
+#[cfg(feature = "std")]
 mod layer;
+#[cfg(feature = "std")]
 pub use crate::layer::Layer;
 
+#[cfg(feature = "std")]
 mod mlp;
+#[cfg(feature = "std")]
 pub use crate::mlp::MLP;
 
 mod tensor;
@@ -21,10 +33,14 @@ pub use crate::types::lazy::Dimensions;
 pub use crate::types::lazy::LazyBuffer;
 pub use crate::types::lazy::DataArray;
 pub use crate::types::data::FeaturesAndLabels;
+pub use crate::types::dual::Dual;
+pub use crate::types::half::{ f16, bf16 };
+pub use crate::types::fixed::Fixed;
 
 mod traits;
-pub use crate::traits::TensorTrait;
+pub use crate::traits::{ TensorTrait, Element, Numeric, FloatElement };
 
+#[cfg(feature = "std")]
 pub mod random;
 
 pub mod helpers;
@@ -34,3 +50,27 @@ pub mod backward;
 pub mod forward;
 
 pub mod nn;
+
+pub mod autograd;
+
+#[cfg(feature = "std")]
+pub mod graph;
+
+pub mod backend;
+
+#[cfg(any(feature = "ndarray", feature = "nalgebra"))]
+pub mod interop;
+
+#[cfg(feature = "safetensors")]
+pub mod io;
+
+pub mod print;
+pub use crate::print::{ PrintOptions, set_print_options };
+
+pub mod error;
+pub use crate::error::NanogradError;
+
+#[cfg(feature = "std")]
+pub mod jit;
+
+pub mod complex;
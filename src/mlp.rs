@@ -4,7 +4,26 @@ use crate::{ layer::Layer, value::Value };
 // Refer to this page for more information: https://en.wikipedia.org/wiki/Multilayer_perceptron
 
 // struct adopted from https://github.com/danielway/micrograd-rs
+///
+/// With the `serde` feature a network round-trips through any serde format, keeping the data of
+/// every weight and bias.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use nanograd::{ MLP, Value };
+///
+/// let mlp = MLP::new(2, vec![3, 1]);
+/// let json = serde_json::to_string(&mlp).unwrap();
+/// let restored: MLP = serde_json::from_str(&json).unwrap();
+///
+/// let xs = vec![Value::from(1.0), Value::from(-2.0)];
+/// assert_eq!(restored.forward(xs.clone())[0].data(), mlp.forward(xs)[0].data());
+/// # }
+/// ```
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MLP {
     layers: Vec<Layer>,
 }
@@ -3,6 +3,7 @@ use getrandom::getrandom;
 
 // struct adopted from https://github.com/danielway/micrograd-rs
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Neuron {
     weights: Vec<Value>,
     bias: Value,
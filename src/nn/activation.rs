@@ -1,10 +1,12 @@
 use core::panic;
-use std::f32::consts::E;
+use core::f32::consts::E;
+use alloc::vec::Vec;
 
-use crate::{ TensorTrait, Tensor, Dimensions, DataArray, types::ops::UnaryOps, Ops };
+use crate::{ TensorTrait, Tensor, Dimensions, DataArray, types::ops::UnaryOps };
 
-use crate::nn::transformation::max;
-use crate::nn::transformation::log;
+use crate::error::Result;
+use crate::nn::transformation::{ apply_unary, max, try_apply_unary, try_max };
+use crate::nn::transformation::try_log;
 /// Sigmoid function.
 ///
 /// # Arguments
@@ -15,20 +17,12 @@ use crate::nn::transformation::log;
 ///
 /// A tensor with the sigmoid function applied to it element-wise.
 pub fn sigmoid<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
-    let dim: Dimensions = val.dim();
-    let data: &DataArray<T> = val.data();
-    let new_data = sigmoid_op(data, dim);
-    let mut new_tensor = Tensor::_build_raw(
-        new_data,
-        dim,
-        None,
-        Some(true),
-        Some(Ops::UnaryOps(UnaryOps::Sigmoid)),
-        Some(val),
-        None
-    );
-    new_tensor.set_gradient(Tensor::zeros(dim, None, None));
-    new_tensor
+    apply_unary(val, UnaryOps::Sigmoid, None)
+}
+
+/// Sigmoid function, failing instead of panicking if the device has no backend.
+pub fn try_sigmoid<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
+    try_apply_unary(val, UnaryOps::Sigmoid, None)
 }
 
 // relu
@@ -36,6 +30,11 @@ pub fn relu<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
     max(val, T::zero())
 }
 
+/// Rectified linear unit, failing instead of panicking if the device has no backend.
+pub fn try_relu<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
+    try_max(val, T::zero())
+}
+
 /// Hyperbolic tangent function.
 ///
 /// # Arguments
@@ -47,9 +46,14 @@ pub fn relu<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
 /// A tensor with the hyperbolic tangent function applied to it element-wise.
 ///
 pub fn tanh<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
+    try_tanh(val).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Hyperbolic tangent function, failing instead of panicking if the device has no backend.
+pub fn try_tanh<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
     let one: T = T::one();
     let two: T = one + one;
-    sigmoid(val * two) * two - one
+    try_sigmoid(val.try_mul_scalar(two)?)?.try_mul_scalar(two)?.try_sub_scalar(one)
 }
 
 ///
@@ -83,30 +87,26 @@ pub fn tanh<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
 ///
 
 pub fn softmax<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
-    let dim: Dimensions = val.dim();
-    let val_data = val.data();
-    let new_data = softmax_op(val_data, dim);
-    let mut new_tensor = Tensor::_build_raw(
-        new_data,
-        dim,
-        None,
-        Some(true),
-        Some(Ops::UnaryOps(UnaryOps::EXP2)),
-        Some(val),
-        None
-    );
-    new_tensor.set_gradient(Tensor::zeros(dim, None, None));
-    new_tensor
+    apply_unary(val, UnaryOps::Softmax, None)
+}
+
+/// Row-wise softmax, failing instead of panicking if the device has no backend.
+pub fn try_softmax<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
+    try_apply_unary(val, UnaryOps::Softmax, None)
 }
 
 // .... ops
 
 pub fn log_softmax<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
-    let x = softmax(val);
-    log(x)
+    try_log_softmax(val).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Row-wise log of the softmax, failing instead of panicking if the device has no backend.
+pub fn try_log_softmax<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
+    try_log(try_softmax(val)?)
 }
 
-fn sigmoid_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataArray<T> {
+pub fn sigmoid_op<T: TensorTrait<T>>(data: &[T], dim: Dimensions) -> DataArray<T> {
     let mut new_data = Vec::with_capacity(dim.0 * dim.1);
     let exp_typed = T::from_f32(E);
     let exp_typed: T = match exp_typed {
@@ -115,14 +115,14 @@ fn sigmoid_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataAr
     };
     let one = T::one();
     for i in 0..dim.0 * dim.1 {
-        new_data.push(exp_typed.pow(data[i]) / (one + exp_typed.pow(data[i])));
+        new_data.push(exp_typed.powf(data[i]) / (one + exp_typed.powf(data[i])));
     }
     // create Box<[T]> from Vec<T>
     let new_data: DataArray<T> = new_data.into_boxed_slice();
     new_data
 }
 
-fn softmax_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataArray<T> {
+pub fn softmax_op<T: TensorTrait<T>>(data: &[T], dim: Dimensions) -> DataArray<T> {
     let mut new_data = Vec::with_capacity(dim.0 * dim.1);
     let exp_typed = T::from_f32(E);
     let exp_typed: T = match exp_typed {
@@ -134,11 +134,11 @@ fn softmax_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataAr
         // get the sum of the row
         let mut sum: T = T::zero();
         for j in 0..dim.1 {
-            sum = sum + exp_typed.pow(data[i * dim.1 + j]);
+            sum = sum + exp_typed.powf(data[i * dim.1 + j]);
         }
         // iterate through each element in the row
         for j in 0..dim.1 {
-            new_data.push(exp_typed.pow(data[i * dim.1 + j]) / sum);
+            new_data.push(exp_typed.powf(data[i * dim.1 + j]) / sum);
         }
     }
     // create Box<[T]> from Vec<T>
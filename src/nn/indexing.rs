@@ -0,0 +1,169 @@
+use alloc::{ vec::Vec, vec, string::ToString, format };
+
+use crate::{ Tensor, Dimensions, Element, Numeric, NanogradError };
+use crate::error::Result;
+use crate::types::ops::CmpOps;
+
+///
+/// Compare two tensors element-wise. The mask is not part of any computation graph.
+///
+/// # Arguments
+///
+/// * `a` - The left operand.
+/// * `b` - The right operand, of the same shape.
+/// * `op` - The comparison.
+///
+/// # Returns
+///
+/// A `bool` tensor on the device of `a`.
+///
+/// # Panics
+///
+/// * If the shapes differ or the operands live on different devices.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::indexing::compare, types::ops::CmpOps };
+///
+/// let labels: Tensor<i64> = Tensor::from_vec(vec![0, 2, 1, 2], (1, 4), None, None);
+/// let predicted: Tensor<i64> = Tensor::from_vec(vec![0, 1, 1, 2], (1, 4), None, None);
+///
+/// let correct: Tensor<bool> = compare(&labels, &predicted, CmpOps::EQ);
+/// assert_eq!(correct.data().as_ref(), &[true, false, true, true]);
+///
+/// let accuracy = correct.data().iter().filter(|&&hit| hit).count() as f64 / 4.0;
+/// assert_eq!(accuracy, 0.75);
+/// ```
+pub fn compare<T: Numeric>(a: &Tensor<T>, b: &Tensor<T>, op: CmpOps) -> Tensor<bool> {
+    try_compare(a, b, op).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Compare two tensors element-wise, failing instead of panicking on mismatched operands.
+pub fn try_compare<T: Numeric>(a: &Tensor<T>, b: &Tensor<T>, op: CmpOps) -> Result<Tensor<bool>> {
+    if a.dim() != b.dim() {
+        return Err(NanogradError::shape_mismatch(&format!("{:?}", op), a.dim(), b.dim()));
+    }
+    if a.device() != b.device() {
+        return Err(NanogradError::DeviceMismatch { left: a.device().clone(), right: b.device().clone() });
+    }
+    let mask: Vec<bool> = a.data()
+        .iter()
+        .zip(b.data().iter())
+        .map(|(x, y)| {
+            match op {
+                CmpOps::EQ => x == y,
+                CmpOps::LT => x < y,
+                CmpOps::GT => x > y,
+            }
+        })
+        .collect();
+    Tensor::try_from_vec(mask, a.dim(), Some(a.device().clone()), None)
+}
+
+///
+/// Encode class labels as one-hot rows.
+///
+/// # Arguments
+///
+/// * `labels` - A row or column of class indices, of any numeric type.
+/// * `num_classes` - The number of classes, i.e. the width of the encoding.
+///
+/// # Returns
+///
+/// A `labels x num_classes` tensor of zeros with a one at each label.
+///
+/// # Panics
+///
+/// * If a label is negative, fractional or not below `num_classes`.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::indexing::one_hot };
+///
+/// let labels: Tensor<u8> = Tensor::from_vec(vec![2, 0], (2, 1), None, None);
+/// let targets: Tensor<f64> = one_hot(&labels, 3);
+///
+/// assert_eq!(targets.dim(), (2, 3));
+/// assert_eq!(targets.data().as_ref(), &[0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+/// ```
+pub fn one_hot<I: Numeric, U: Numeric>(labels: &Tensor<I>, num_classes: usize) -> Tensor<U> {
+    try_one_hot(labels, num_classes).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Encode class labels as one-hot rows, failing instead of panicking on an invalid label.
+pub fn try_one_hot<I: Numeric, U: Numeric>(labels: &Tensor<I>, num_classes: usize) -> Result<Tensor<U>> {
+    let indices: Vec<usize> = to_indices(labels, num_classes)?;
+    let mut data: Vec<U> = vec![U::zero(); indices.len() * num_classes];
+    for (row, index) in indices.iter().enumerate() {
+        data[row * num_classes + index] = U::one();
+    }
+    Tensor::try_from_vec(data, (indices.len(), num_classes), Some(labels.device().clone()), None)
+}
+
+///
+/// Look up rows of a table by index, e.g. token embeddings. The result is a copy and is not
+/// part of any computation graph.
+///
+/// # Arguments
+///
+/// * `table` - The rows to choose from.
+/// * `indices` - A row or column of row indices, of any numeric type.
+///
+/// # Returns
+///
+/// An `indices x table columns` tensor whose i-th row is the table row at the i-th index.
+///
+/// # Panics
+///
+/// * If an index is negative, fractional or not below the number of table rows.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::indexing::gather_rows };
+///
+/// let table: Tensor<f32> = Tensor::from_vec(vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.5], (3, 2), None, None);
+/// let tokens: Tensor<i64> = Tensor::from_vec(vec![2, 2, 0], (1, 3), None, None);
+///
+/// let embedded = gather_rows(&table, &tokens);
+/// assert_eq!(embedded.dim(), (3, 2));
+/// assert_eq!(embedded.data().as_ref(), &[2.0, 2.5, 2.0, 2.5, 0.0, 0.5]);
+///
+/// let flags: Tensor<bool> = Tensor::from_vec(vec![true, false, false, true], (2, 2), None, None);
+/// assert_eq!(gather_rows(&flags, &Tensor::<u8>::from_vec(vec![1], (1, 1), None, None)).data().as_ref(), &[false, true]);
+/// ```
+pub fn gather_rows<T: Element, I: Numeric>(table: &Tensor<T>, indices: &Tensor<I>) -> Tensor<T> {
+    try_gather_rows(table, indices).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Look up rows of a table by index, failing instead of panicking on an invalid index.
+pub fn try_gather_rows<T: Element, I: Numeric>(table: &Tensor<T>, indices: &Tensor<I>) -> Result<Tensor<T>> {
+    let (rows, columns): Dimensions = table.dim();
+    let indices: Vec<usize> = to_indices(indices, rows)?;
+    let source = table.data();
+    let mut data: Vec<T> = Vec::with_capacity(indices.len() * columns);
+    for index in indices.iter() {
+        data.extend_from_slice(&source[index * columns..(index + 1) * columns]);
+    }
+    Tensor::try_from_vec(data, (indices.len(), columns), Some(table.device().clone()), None)
+}
+
+/// Read an index tensor, checking every index against the length of the axis it indexes.
+fn to_indices<I: Numeric>(indices: &Tensor<I>, len: usize) -> Result<Vec<usize>> {
+    let (rows, columns): Dimensions = indices.dim();
+    if rows != 1 && columns != 1 {
+        return Err(NanogradError::unsupported("indexing", "indices must be a row or a column"));
+    }
+    indices
+        .data()
+        .iter()
+        .map(|index| {
+            match index.to_usize() {
+                Some(position) if position < len && I::from_usize(position) == Some(*index) => Ok(position),
+                _ => Err(NanogradError::IndexOutOfBounds { index: index.to_string(), len }),
+            }
+        })
+        .collect()
+}
@@ -1,33 +1,167 @@
-use crate::{ Tensor, TensorTrait };
+use crate::{ Tensor, TensorTrait, NanogradError };
+use crate::error::Result;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Deserialize };
 
+///
+/// A fully connected layer, `input * weight + bias`.
+///
+/// The weight is `in_features x out_features` and the bias a `1 x out_features` row added to every
+/// row of the output. The forward pass only needs the tensor core, so a layer built from trained
+/// weights with `from_weights` also runs without the `std` feature.
+///
+/// With the `serde` feature the weight and bias are serialized as tensors. Deserializing checks
+/// the shape of the bias like `try_from_weights`.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use nanograd::{ Tensor, nn::linear::Linear };
+///
+/// let layer: Linear<f64> = Linear::new(3, 2, Some(true));
+/// let json = serde_json::to_string(&layer).unwrap();
+/// let restored: Linear<f64> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(restored.weight().data(), layer.weight().data());
+/// assert_eq!(restored.bias().unwrap().dim(), (1, 2));
+///
+/// let wrong_bias = json.replace(r#""shape":[1,2]"#, r#""shape":[2,1]"#);
+/// assert!(serde_json::from_str::<Linear<f64>>(&wrong_bias).is_err());
+/// # }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "LinearParts<T>", bound(deserialize = "T: Deserialize<'de>"))
+)]
 pub struct Linear<T: TensorTrait<T>> {
     weight: Tensor<T>,
     bias: Option<Tensor<T>>,
 }
 
 impl<T: TensorTrait<T>> Linear<T> {
+    ///
+    /// Create a layer with weights drawn uniformly from `[0, 1)` and a zero bias.
+    ///
+    /// # Arguments
+    ///
+    /// * `in_features` - The number of columns of the input.
+    /// * `out_features` - The number of columns of the output.
+    /// * `bias` - Whether to add a bias. Defaults to no bias.
+    #[cfg(feature = "std")]
     pub fn new(in_features: usize, out_features: usize, bias: Option<bool>) -> Self {
-        let new_dim = (in_features, out_features);
-        let new_weight = Tensor::rand(new_dim, None, Some(true));
+        let new_weight = Tensor::rand((in_features, out_features), None, Some(true));
         let new_bias: Option<Tensor<T>> = match bias {
-            Some(b) => {
-                if b { Some(Tensor::zeros(new_dim, None, Some(true))) } else { None }
-            }
-            None => None,
+            Some(true) => Some(Tensor::zeros((1, out_features), None, Some(true))),
+            _ => None,
         };
         Linear {
             weight: new_weight,
             bias: new_bias,
         }
     }
-    pub fn forward(&mut self, input: Tensor<T>) -> Tensor<T> {
-        // transpose the weight matrix
-        // self.weight.transpose();
-        // let output = input * self.weight;
-        // match self.bias {
-        //     Some(b) => output + b,
-        //     None => output,
-        // }
-        return Tensor::zeros((1, 1), None, None);
+
+    ///
+    /// Create a layer from existing weights, e.g. trained ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `weight` - An `in_features x out_features` tensor.
+    /// * `bias` - An optional `1 x out_features` tensor.
+    ///
+    /// # Panics
+    ///
+    /// * If the bias is not a single row as wide as the weight.
+    pub fn from_weights(weight: Tensor<T>, bias: Option<Tensor<T>>) -> Self {
+        Self::try_from_weights(weight, bias).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a layer from existing weights, failing instead of panicking on a bias of the wrong shape.
+    pub fn try_from_weights(weight: Tensor<T>, bias: Option<Tensor<T>>) -> Result<Self> {
+        if let Some(bias) = &bias {
+            if bias.dim() != (1, weight.dim().1) {
+                return Err(NanogradError::shape_mismatch("Linear", weight.dim(), bias.dim()));
+            }
+        }
+        Ok(Linear { weight, bias })
+    }
+
+    pub fn weight(&self) -> &Tensor<T> {
+        &self.weight
+    }
+
+    pub fn bias(&self) -> Option<&Tensor<T>> {
+        self.bias.as_ref()
+    }
+
+    ///
+    /// Apply the layer to a batch of inputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A `batch x in_features` tensor.
+    ///
+    /// # Returns
+    ///
+    /// A `batch x out_features` tensor.
+    ///
+    /// # Panics
+    ///
+    /// * If the input does not have `in_features` columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, autograd::grad, nn::{ linear::Linear, transformation::sum } };
+    ///
+    /// let weight: Tensor<f64> = Tensor::from_vec(vec![1.0, 0.0, -1.0, 0.0, 1.0, 2.0], (2, 3), None, Some(true));
+    /// let bias: Tensor<f64> = Tensor::from_vec(vec![0.5, 0.5, 0.5], (1, 3), None, Some(true));
+    /// let input: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+    ///
+    /// let layer = Linear::from_weights(weight.clone(), Some(bias.clone()));
+    /// let output = layer.forward(input.clone());
+    /// assert_eq!(output.dim(), (2, 3));
+    /// assert_eq!(output.data().as_ref(), &[1.5, 2.5, 3.5, 3.5, 4.5, 5.5]);
+    ///
+    /// // the bias receives the gradient of every row of the batch
+    /// let loss = |v: Vec<Tensor<f64>>| sum(Linear::from_weights(v[0].clone(), Some(v[1].clone())).forward(input.clone()));
+    /// let grads = grad(loss, &[weight, bias]);
+    /// assert_eq!(grads[0].data().as_ref(), &[4.0, 4.0, 4.0, 6.0, 6.0, 6.0]);
+    /// assert_eq!(grads[1].data().as_ref(), &[2.0, 2.0, 2.0]);
+    /// ```
+    pub fn forward(&self, input: Tensor<T>) -> Tensor<T> {
+        self.try_forward(input).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Apply the layer, failing instead of panicking on an input of the wrong width.
+    pub fn try_forward(&self, input: Tensor<T>) -> Result<Tensor<T>> {
+        let batch = input.dim().0;
+        let device = input.device().clone();
+        let output = input.try_mul(self.weight.clone())?;
+        match &self.bias {
+            // broadcast the bias row to every row of the batch
+            Some(bias) => {
+                let rows = Tensor::<T>::ones((batch, 1), Some(device), None).try_mul(bias.clone())?;
+                output.try_add(rows)
+            }
+            None => Ok(output),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "Linear")]
+struct LinearParts<T: TensorTrait<T>> {
+    weight: Tensor<T>,
+    bias: Option<Tensor<T>>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: TensorTrait<T>> TryFrom<LinearParts<T>> for Linear<T> {
+    type Error = NanogradError;
+
+    fn try_from(parts: LinearParts<T>) -> Result<Self> {
+        Linear::try_from_weights(parts.weight, parts.bias)
     }
 }
@@ -1,4 +1,5 @@
-use crate::{ Tensor, TensorTrait, nn::activation::log_softmax };
+use crate::{ Tensor, TensorTrait, NanogradError, nn::activation::log_softmax };
+use crate::error::Result;
 
 /// Categorical cross entropy loss function
 ///
@@ -29,10 +30,24 @@ use crate::{ Tensor, TensorTrait, nn::activation::log_softmax };
 ///
 pub fn categorical_cross_entropy<T: TensorTrait<T>>(
     y_pred: Tensor<T>,
-    mut y_true: Tensor<T>
+    y_true: Tensor<T>
 ) -> Tensor<T> {
-    // assert that y_pred and y_true have the same shape
-    assert_eq!(y_pred.dim(), y_true.dim());
+    try_categorical_cross_entropy(y_pred, y_true).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Categorical cross entropy loss function
+///
+/// # Errors
+///
+/// * `ShapeMismatch` if the predicted and true tensors have different dimensions.
+pub fn try_categorical_cross_entropy<T: TensorTrait<T>>(
+    y_pred: Tensor<T>,
+    mut y_true: Tensor<T>
+) -> Result<Tensor<T>> {
+    // y_pred and y_true must have the same shape
+    if y_pred.dim() != y_true.dim() {
+        return Err(NanogradError::shape_mismatch("categorical_cross_entropy", y_pred.dim(), y_true.dim()));
+    }
     // get the data of the true tensor
     let mut transformed_pred = log_softmax(y_pred);
     // total number of categories
@@ -48,8 +63,8 @@ pub fn categorical_cross_entropy<T: TensorTrait<T>>(
     y_true.flatten();
     // transpose to fulfill the matrix multiplication dimension requirements
     y_true.transpose();
-    let output = transformed_pred * y_true;
+    let output = transformed_pred.try_mul(y_true)?;
     // output should be a 1x1 tensor
     assert!(output.dim() == (1, 1));
-    output * num_categories_inv
+    Ok(output * num_categories_inv)
 }
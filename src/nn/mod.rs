@@ -5,3 +5,5 @@ pub mod transformation;
 pub mod linear;
 
 pub mod loss;
+
+pub mod indexing;
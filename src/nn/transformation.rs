@@ -1,38 +1,21 @@
-use std::f64::consts::E;
+use core::f64::consts::E;
+use alloc::vec::Vec;
 
-use crate::{ Tensor, TensorTrait, Dimensions, DataArray, types::ops::{ UnaryOps, ReduceOps }, Ops };
+use crate::{ Tensor, TensorTrait, Dimensions, DataArray, Device, LazyBuffer, types::ops::{ UnaryOps, ReduceOps }, Ops };
+use crate::backend::try_get_backend;
+use crate::error::{ NanogradError, Result };
 
 /// Raise each value in tensor to power of val
 ///
 /// # Arguments
 ///
 /// * `val` - The value to raise each value in tensor to.
-fn exp<T: TensorTrait<T>>(base: T, power: Tensor<T>) -> Tensor<T> {
-    // throw not implemented for now, since backward op is not defined
+fn exp<T: TensorTrait<T>>(base: T, power: Tensor<T>) -> Result<Tensor<T>> {
+    // not implemented for now, since backward op is not defined
     if base != T::from_f32(2.0).unwrap() {
-        panic!("Not implemented for bases other than 2.0");
+        return Err(NanogradError::unsupported("exp", "bases other than 2.0 have no backward rule"));
     }
-    let dim: Dimensions = power.dim();
-    let mut i: usize = 0;
-    let mut new_data = Vec::with_capacity(dim.0 * dim.1);
-    let data: &DataArray<T> = power.data();
-    while i < dim.0 * dim.1 {
-        new_data.push(data[i].pow(base));
-        i += 1;
-    }
-    let new_data: DataArray<T> = new_data.into_boxed_slice();
-    // create and return a new tensor
-    let mut new_tensor = Tensor::_build_raw(
-        new_data,
-        dim,
-        None,
-        Some(true),
-        Some(Ops::UnaryOps(UnaryOps::EXP2)),
-        Some(power),
-        None
-    );
-    new_tensor.set_gradient(Tensor::zeros(dim, None, None));
-    new_tensor
+    try_apply_unary(power, UnaryOps::EXP2, None)
 }
 
 /// Compute 2 raised to the power of each value in tensor.
@@ -55,87 +38,125 @@ fn exp<T: TensorTrait<T>>(base: T, power: Tensor<T>) -> Tensor<T> {
 /// ```
 ///
 pub fn exp2<T: TensorTrait<T>>(power: Tensor<T>) -> Tensor<T> {
+    try_exp2(power).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Compute 2 raised to the power of each value in tensor, or fail if the device has no backend.
+pub fn try_exp2<T: TensorTrait<T>>(power: Tensor<T>) -> Result<Tensor<T>> {
     exp(T::from_f32(2.0).unwrap(), power)
 }
 
 pub fn max<T: TensorTrait<T>>(val: Tensor<T>, other: T) -> Tensor<T> {
-    let dim: Dimensions = val.dim();
-    let mut new_data = Vec::with_capacity(dim.0 * dim.1);
-    let data: &DataArray<T> = val.data();
-    for i in 0..dim.0 * dim.1 {
-        new_data.push(if data[i] > other { data[i] } else { other });
-    }
-    let new_data: DataArray<T> = new_data.into_boxed_slice();
-    let mut new_tensor = Tensor::_build_raw(
-        new_data,
-        dim,
-        None,
-        Some(true),
-        Some(Ops::UnaryOps(UnaryOps::MAX)),
-        Some(val),
-        None
-    );
-    new_tensor.set_gradient(Tensor::zeros(dim, None, None));
-    new_tensor
+    try_max(val, other).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Take the larger of each value and `other`, or fail if the device has no backend.
+pub fn try_max<T: TensorTrait<T>>(val: Tensor<T>, other: T) -> Result<Tensor<T>> {
+    try_apply_unary(val, UnaryOps::MAX, Some(other))
 }
 
 pub fn log2<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
-    let dim: Dimensions = val.dim();
-    let data: &DataArray<T> = val.data();
-    let new_data = log2_op(data, dim);
-    let mut new_tensor = Tensor::_build_raw(
-        new_data,
-        dim,
-        None,
-        Some(true),
-        Some(Ops::UnaryOps(UnaryOps::LOG2)),
-        Some(val),
-        None
-    );
-    new_tensor.set_gradient(Tensor::zeros(dim, None, None));
-    new_tensor
+    try_log2(val).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Take the base 2 logarithm of each value, or fail if the device has no backend.
+pub fn try_log2<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
+    try_apply_unary(val, UnaryOps::LOG2, None)
 }
 
 pub fn log<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
-    let new_val_base_two = log2(val);
+    try_log(val).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Take the natural logarithm of each value, or fail if the device has no backend.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::{ activation::{ try_log_softmax, try_tanh }, transformation::try_log } };
+///
+/// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 4.0, 8.0], (2, 2), None, None);
+/// let y = try_log(x.clone()).unwrap();
+/// assert!((y.data()[3] - 8.0_f64.ln()).abs() < 1e-12);
+///
+/// let rows = try_log_softmax(x.clone()).unwrap();
+/// assert!((rows.data()[0].exp() + rows.data()[1].exp() - 1.0).abs() < 1e-6);
+/// assert!((try_tanh(x).unwrap().data()[0] - 1.0_f64.tanh()).abs() < 1e-6);
+/// ```
+pub fn try_log<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
+    let new_val_base_two = try_log2(val)?;
     let e = T::from_f64(E).unwrap();
     let conversion_val = T::from_f64(2.0).unwrap().log(e);
-    new_val_base_two * conversion_val
+    new_val_base_two.try_mul_scalar(conversion_val)
 }
 
 pub fn sum<T: TensorTrait<T>>(val: Tensor<T>) -> Tensor<T> {
+    try_sum(val).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Sum all values into a 1x1 tensor, or fail if the device has no backend.
+pub fn try_sum<T: TensorTrait<T>>(val: Tensor<T>) -> Result<Tensor<T>> {
     let dim: Dimensions = val.dim();
-    // get data
-    let data: &DataArray<T> = val.data();
-    // get running sum
-    let new_data = sum_op(data, dim);
-    let mut new_tensor = Tensor::_build_raw(
-        new_data,
-        (1, 1),
-        None,
+    let device: Device = val.device().clone();
+    // get running sum on the device of the tensor
+    let new_storage = try_get_backend::<T>(&device)?.reduce(ReduceOps::SUM, val.lazy_data.storage(), dim);
+    let lazy_data: LazyBuffer<T> = LazyBuffer::from_storage(new_storage, (1, 1), device.clone());
+    let mut new_tensor = Tensor::_build_lazy(
+        lazy_data,
         Some(true),
         Some(Ops::ReduceOps(ReduceOps::SUM)),
         Some(val),
         None
     );
-    new_tensor.set_gradient(Tensor::zeros(dim, None, None));
-    new_tensor
+    new_tensor.set_gradient(Tensor::zeros(dim, Some(device), None));
+    Ok(new_tensor)
+}
+
+///
+/// Apply an element-wise op with the backend of the tensor's device and record it in the graph.
+///
+/// # Arguments
+///
+/// * `val` - The tensor to apply the op to.
+/// * `op` - The op to apply.
+/// * `arg` - The scalar argument of ops that take one, e.g. the threshold of `MAX`.
+pub fn apply_unary<T: TensorTrait<T>>(val: Tensor<T>, op: UnaryOps, arg: Option<T>) -> Tensor<T> {
+    try_apply_unary(val, op, arg).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Like `apply_unary`, but fails instead of panicking if the device has no backend.
+pub fn try_apply_unary<T: TensorTrait<T>>(val: Tensor<T>, op: UnaryOps, arg: Option<T>) -> Result<Tensor<T>> {
+    let dim: Dimensions = val.dim();
+    let device: Device = val.device().clone();
+    let new_storage = try_get_backend::<T>(&device)?.unary(op, val.lazy_data.storage(), dim, arg);
+    let lazy_data: LazyBuffer<T> = LazyBuffer::from_storage(new_storage, dim, device.clone());
+    let mut new_tensor = Tensor::_build_lazy(
+        lazy_data,
+        Some(true),
+        Some(Ops::UnaryOps(op)),
+        Some(val),
+        None
+    );
+    new_tensor.set_gradient(Tensor::zeros(dim, Some(device), None));
+    new_tensor.arg = arg;
+    Ok(new_tensor)
 }
 
 // ..... ops .....
 
-pub fn exp2_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataArray<T> {
+pub fn exp2_op<T: TensorTrait<T>>(data: &[T], dim: Dimensions) -> DataArray<T> {
     let mut new_data = Vec::with_capacity(dim.0 * dim.1);
     let two = T::from_f32(2.0).unwrap();
     for i in 0..dim.0 * dim.1 {
-        new_data.push(two.pow(data[i]));
+        new_data.push(two.powf(data[i]));
     }
     // create Box<[T]> from Vec<T>
     let new_data: DataArray<T> = new_data.into_boxed_slice();
     new_data
 }
 
-pub fn log2_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataArray<T> {
+pub fn log2_op<T: TensorTrait<T>>(data: &[T], dim: Dimensions) -> DataArray<T> {
     let mut new_data = Vec::with_capacity(dim.0 * dim.1);
     for i in 0..dim.0 * dim.1 {
         new_data.push(data[i].log2());
@@ -145,7 +166,7 @@ pub fn log2_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataA
     new_data
 }
 
-pub fn max_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions, other: T) -> DataArray<T> {
+pub fn max_op<T: TensorTrait<T>>(data: &[T], dim: Dimensions, other: T) -> DataArray<T> {
     let mut new_data = Vec::with_capacity(dim.0 * dim.1);
     for i in 0..dim.0 * dim.1 {
         new_data.push(data[i].max(other));
@@ -155,7 +176,7 @@ pub fn max_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions, other: T)
     new_data
 }
 
-pub fn sum_op<T: TensorTrait<T>>(data: &DataArray<T>, dim: Dimensions) -> DataArray<T> {
+pub fn sum_op<T: TensorTrait<T>>(data: &[T], dim: Dimensions) -> DataArray<T> {
     let mut new_data = Vec::with_capacity(1);
     let mut sum = T::zero();
     for i in 0..dim.0 * dim.1 {
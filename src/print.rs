@@ -0,0 +1,214 @@
+use core::any::type_name;
+use core::fmt::{ self, Write };
+use core::iter::once;
+use core::sync::atomic::{ AtomicUsize, Ordering };
+use alloc::format;
+use alloc::string::{ String, ToString };
+use alloc::vec::Vec;
+
+use crate::Element;
+use crate::types::dtype::DType;
+
+///
+/// How tensors are printed. Set them for the whole program with `set_print_options`.
+///
+/// * `precision` - Digits after the decimal point of float elements. Defaults to 4.
+/// * `threshold` - Tensors with more elements than this are summarized. Defaults to 1000.
+/// * `edge_items` - Elements shown at the start and end of each axis of a summarized tensor. Defaults to 3.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrintOptions {
+    pub precision: usize,
+    pub threshold: usize,
+    pub edge_items: usize,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions { precision: 4, threshold: 1000, edge_items: 3 }
+    }
+}
+
+static PRECISION: AtomicUsize = AtomicUsize::new(4);
+static THRESHOLD: AtomicUsize = AtomicUsize::new(1000);
+static EDGE_ITEMS: AtomicUsize = AtomicUsize::new(3);
+
+///
+/// Set how every tensor is printed from now on, on every thread.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, PrintOptions, set_print_options };
+///
+/// let tensor: Tensor<f64> = Tensor::from_vec((0..2000).map(|i| i as f64 / 8.0).collect(), (2, 1000), None, None);
+///
+/// set_print_options(PrintOptions { precision: 2, edge_items: 2, ..PrintOptions::default() });
+/// assert_eq!(
+///     tensor.to_string(),
+///     "tensor(shape=(2, 1000), dtype=f64, device=CPU, requires_grad=false)\n\
+///      [[  0.00,   0.12, ..., 124.75, 124.88],\n \
+///       [125.00, 125.12, ..., 249.75, 249.88]]"
+/// );
+/// set_print_options(PrintOptions::default());
+/// ```
+pub fn set_print_options(options: PrintOptions) {
+    PRECISION.store(options.precision, Ordering::Relaxed);
+    THRESHOLD.store(options.threshold, Ordering::Relaxed);
+    EDGE_ITEMS.store(options.edge_items, Ordering::Relaxed);
+}
+
+/// Get the options tensors are currently printed with.
+pub fn print_options() -> PrintOptions {
+    PrintOptions {
+        precision: PRECISION.load(Ordering::Relaxed),
+        threshold: THRESHOLD.load(Ordering::Relaxed),
+        edge_items: EDGE_ITEMS.load(Ordering::Relaxed),
+    }
+}
+
+///
+/// Write elements laid out in row-major order as nested lists, one level per axis, the way
+/// NumPy prints arrays. Columns are aligned, and floats that are all whole numbers drop their
+/// fractional digits, e.g. `1.`.
+///
+/// # Arguments
+///
+/// * `out` - Where to write.
+/// * `data` - The elements.
+/// * `shape` - The length of each axis. An empty shape is a single element.
+/// * `options` - The precision and summarization to use.
+///
+/// # Errors
+///
+/// * If `data` does not have as many elements as `shape` describes, or writing fails.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::print::{ write_array, PrintOptions };
+///
+/// let mut out = String::new();
+/// let data: Vec<i64> = (0..12).collect();
+/// write_array(&mut out, &data, &[2, 2, 3], &PrintOptions::default()).unwrap();
+/// assert_eq!(out, "[[[ 0,  1,  2],\n  [ 3,  4,  5]],\n\n [[ 6,  7,  8],\n  [ 9, 10, 11]]]");
+///
+/// let mut out = String::new();
+/// write_array(&mut out, &[1.0_f32, -2.0, 3.0], &[3], &PrintOptions::default()).unwrap();
+/// assert_eq!(out, "[ 1., -2.,  3.]");
+/// ```
+pub fn write_array<T: Element, W: Write>(out: &mut W, data: &[T], shape: &[usize], options: &PrintOptions) -> fmt::Result {
+    if data.len() != shape.iter().product::<usize>() {
+        return Err(fmt::Error);
+    }
+    let edge = if data.len() > options.threshold { Some(options.edge_items) } else { None };
+    let mut cells: Vec<String> = Vec::new();
+    format_cells(data, shape, edge, 0, options.precision, &mut cells);
+    if is_float::<T>() && cells.iter().all(|cell| is_whole(cell)) {
+        for cell in cells.iter_mut() {
+            if let Some(point) = cell.find('.') {
+                cell.truncate(point + 1);
+            }
+        }
+    }
+    let width = cells.iter().map(|cell| cell.chars().count()).max().unwrap_or(0);
+    write_nested(out, &mut cells.iter(), shape, edge, 0, width)
+}
+
+///
+/// The name of an element type for printing: its `DType`, or its type name without module
+/// paths, e.g. `Dual<f64>`.
+pub fn dtype_name<T: 'static>() -> String {
+    match DType::of::<T>() {
+        Some(dtype) => dtype.to_string(),
+        None => {
+            let mut short = String::new();
+            let mut segment = String::new();
+            for c in type_name::<T>().chars().chain(once(' ')) {
+                if c.is_alphanumeric() || c == '_' || c == ':' {
+                    segment.push(c);
+                } else {
+                    short.push_str(segment.rsplit("::").next().unwrap_or(""));
+                    segment.clear();
+                    short.push(c);
+                }
+            }
+            short.trim_end().to_string()
+        }
+    }
+}
+
+// integers and booleans ignore the precision; types without a dtype, e.g. `Dual`, are treated as floats
+fn is_float<T: 'static>() -> bool {
+    DType::of::<T>().is_none_or(DType::is_float)
+}
+
+// whether a formatted float is a whole number, e.g. `-3.0000`; `inf` and `NaN` do not count against it
+fn is_whole(cell: &str) -> bool {
+    match cell.split_once('.') {
+        Some((whole, fraction)) => {
+            whole.trim_start_matches('-').chars().all(|c| c.is_ascii_digit()) && fraction.chars().all(|c| c == '0')
+        }
+        None => true,
+    }
+}
+
+/// The positions shown along an axis, with `None` where a summarized axis skips its middle.
+fn axis_items(len: usize, edge: Option<usize>) -> Vec<Option<usize>> {
+    match edge {
+        Some(edge) if len > 2 * edge => {
+            (0..edge)
+                .map(Some)
+                .chain(once(None))
+                .chain((len - edge..len).map(Some))
+                .collect()
+        }
+        _ => (0..len).map(Some).collect(),
+    }
+}
+
+fn format_cells<T: Element>(data: &[T], shape: &[usize], edge: Option<usize>, offset: usize, precision: usize, cells: &mut Vec<String>) {
+    if shape.is_empty() {
+        let cell = if is_float::<T>() { format!("{:.*}", precision, data[offset]) } else { data[offset].to_string() };
+        cells.push(cell);
+        return;
+    }
+    let stride: usize = shape[1..].iter().product();
+    for item in axis_items(shape[0], edge).into_iter().flatten() {
+        format_cells(data, &shape[1..], edge, offset + item * stride, precision, cells);
+    }
+}
+
+fn write_nested<'a, W: Write>(
+    out: &mut W,
+    cells: &mut impl Iterator<Item = &'a String>,
+    shape: &[usize],
+    edge: Option<usize>,
+    depth: usize,
+    width: usize
+) -> fmt::Result {
+    if shape.is_empty() {
+        return write!(out, "{:>width$}", cells.next().ok_or(fmt::Error)?, width = width);
+    }
+    out.write_char('[')?;
+    for (position, item) in axis_items(shape[0], edge).into_iter().enumerate() {
+        if position > 0 {
+            if shape.len() == 1 {
+                out.write_str(", ")?;
+            } else {
+                // a blank line between blocks for every axis below the rows
+                out.write_char(',')?;
+                for _ in 1..shape.len() {
+                    out.write_char('\n')?;
+                }
+                for _ in 0..=depth {
+                    out.write_char(' ')?;
+                }
+            }
+        }
+        match item {
+            Some(_) => write_nested(out, cells, &shape[1..], edge, depth + 1, width)?,
+            None => out.write_str("...")?,
+        }
+    }
+    out.write_char(']')
+}
@@ -1,6 +1,7 @@
 use getrandom::getrandom;
 
-use crate::TensorTrait;
+use crate::{ TensorTrait, NanogradError };
+use crate::error::Result;
 
 /// Generate a random number between low and high.
 ///
@@ -18,11 +19,22 @@ use crate::TensorTrait;
 /// ```
 ///
 pub fn random_number<T: TensorTrait<T>>(low: T, high: T) -> T {
+    try_random_number(low, high).unwrap_or_else(|error| panic!("{}", error))
+}
+
+///
+/// Generate a random number between low and high, or fail instead of panicking.
+///
+/// # Errors
+///
+/// * `Io` if the system has no source of randomness.
+/// * `DTypeConversion` if the number cannot be represented as a `T`.
+pub fn try_random_number<T: TensorTrait<T>>(low: T, high: T) -> Result<T> {
     let mut buffer = [0u8; 4]; // A buffer to hold the random bytes
 
     // Generate random bytes using getrandom
     if let Err(err) = getrandom(&mut buffer) {
-        panic!("Error generating random bytes: {}", err);
+        return Err(NanogradError::Io(std::io::Error::other(format!("Error generating random bytes: {}", err))));
     }
 
     // Convert the bytes into a u32 random number
@@ -31,19 +43,10 @@ pub fn random_number<T: TensorTrait<T>>(low: T, high: T) -> T {
     // Convert the u32 random number to a floating-point number between 0 and 1
     let random_float = (random_unsigned as f32) / (u32::MAX as f32);
 
-    let result_num = T::try_from(random_float);
-    if result_num.is_err() {
-        panic!("Error converting random float to tensor type.");
-    }
-    let random_num: T;
-    match result_num {
-        Ok(res) => {
-            random_num = res;
-        }
-        #[allow(unused_variables)]
-        Err(err) => panic!("Error converting random float to tensor type"),
-    }
+    let random_num: T = match T::from_f32(random_float) {
+        Some(res) => res,
+        None => return Err(NanogradError::conversion::<f32, T>(random_float)),
+    };
     // Map the range [0, 1] to the range [low, high]
-    let constrained_random = low + random_num * (high - low);
-    return constrained_random;
+    Ok(low + random_num * (high - low))
 }
@@ -1,11 +1,16 @@
 use core::panic;
-use std::borrow::BorrowMut;
-use std::fmt;
-use std::ops::Add;
-use std::ops::Mul;
-use std::ops::Neg;
-use std::ops::Sub;
-use std::vec;
+use core::fmt;
+use core::ops::Add;
+use core::ops::Mul;
+use core::ops::Neg;
+use core::ops::Sub;
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{ AtomicI32, Ordering };
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{ String, ToString };
+use alloc::vec;
+use alloc::vec::Vec;
 
 use crate::DataArray;
 use crate::Device;
@@ -13,14 +18,31 @@ use crate::Dimensions;
 use crate::LazyBuffer;
 use crate::Ops;
 use crate::TensorTrait;
-use crate::backward::orchestrator::backward_by_operation;
+use crate::{ Element, Numeric };
+#[cfg(feature = "std")]
+use num::ToPrimitive;
+use crate::autograd::function::CustomOp;
+use crate::autograd::cast::{ Cast, CastOp };
+use crate::autograd::try_backward;
+#[cfg(feature = "std")]
+use crate::graph::dot::tensor_to_dot;
 use crate::helpers::is_valid_matrix_multiplication;
 use crate::helpers::new_dimensions_after_matrix_multiplication;
-use crate::random::random_number;
+#[cfg(feature = "std")]
+use crate::random::try_random_number;
+use crate::backend::try_get_backend;
+use crate::backend::pool::alloc_host;
+use crate::error::{ NanogradError, Result };
 use crate::types::ops::BinaryOps;
+use crate::types::ops::MovementOps;
+use crate::types::ops::LoadOps;
+use crate::types::dtype::{ cast_data, checked_cast_data };
+use crate::print::{ write_array, print_options, dtype_name };
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Serializer, Deserialize, Deserializer, ser::SerializeStruct, de::Error };
 
 #[derive(Clone, Eq, PartialEq)]
-pub struct Tensor<T: TensorTrait<T>> {
+pub struct Tensor<T: Element> {
     pub lazy_data: LazyBuffer<T>,
     requires_grad: bool,
     pub op: Ops,
@@ -29,11 +51,17 @@ pub struct Tensor<T: TensorTrait<T>> {
     pub gradient: Option<TensorRef<T>>,
     pub unique_id: i32,
     pub is_input: bool,
+    pub custom: Option<CustomOp<T>>,
+    /// The graph of another element type this tensor was computed from, e.g. by `cast`.
+    pub cast: Option<CastOp<T>>,
+    pub label: Option<String>,
+    /// The scalar argument of the op that produced this tensor, e.g. the threshold of `MAX`.
+    pub arg: Option<T>,
 }
 
 pub type TensorRef<T> = Box<Tensor<T>>;
 
-impl<T> Tensor<T> where T: TensorTrait<T> {
+impl<T> Tensor<T> where T: Element {
     /// Create a new tensor.
     ///
     /// # Arguments
@@ -53,33 +81,49 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         device: Option<Device>,
         requires_grad: Option<bool>
     ) -> Self {
+        Self::try_new(data, dimensions, device, requires_grad).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    ///
+    /// Create a new tensor, or fail instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// * `DataLength` if the data length does not match the dimensions.
+    /// * `UnsupportedDevice` if no backend is registered for the device.
+    /// * `DTypeConversion` if the unique id cannot be generated.
+    pub fn try_new(
+        data: DataArray<T>,
+        dimensions: Dimensions,
+        device: Option<Device>,
+        requires_grad: Option<bool>
+    ) -> Result<Self> {
         if data.len() != dimensions.0 * dimensions.1 {
-            panic!("Data length does not match dimensions");
+            return Err(NanogradError::DataLength { dim: dimensions, len: data.len() });
         }
         let requires_grad = match requires_grad {
             Some(requires_grad) => requires_grad,
             None => false,
         };
-        let lazy_data: LazyBuffer<T> = LazyBuffer::new(data, dimensions, device);
+        let lazy_data: LazyBuffer<T> = LazyBuffer::try_new(data, dimensions, device)?;
         let new_op = Ops::None;
-        // create unique id
-        let rand_id = random_number(T::zero(), T::one()).to_f32();
-        let rand_id: i32 = match rand_id {
-            Some(rand_id) => (rand_id * 10000000.0) as i32,
-            None => panic!("Error converting random number to i32"),
-        };
+        let rand_id = new_id()?;
 
         // create gradient placeholder array
-        Self {
+        Ok(Self {
             lazy_data,
             requires_grad,
             op: new_op,
             left: None,
             right: None,
-            gradient: requires_grad.then(|| Box::from(Tensor::zeros(dimensions, None, None))),
+            gradient: requires_grad.then(|| Box::from(Tensor::new(vec![T::default(); dimensions.0 * dimensions.1].into_boxed_slice(), dimensions, None, None))),
             unique_id: rand_id,
             is_input: false,
-        }
+            custom: None,
+            cast: None,
+            label: None,
+            arg: None,
+        })
     }
 
     pub fn from_vec(
@@ -91,6 +135,16 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         Self::new(data.into_boxed_slice(), dimensions, device, requires_grad)
     }
 
+    /// Create a new tensor from a vector, or fail like `try_new`.
+    pub fn try_from_vec(
+        data: Vec<T>,
+        dimensions: Dimensions,
+        device: Option<Device>,
+        requires_grad: Option<bool>
+    ) -> Result<Self> {
+        Self::try_new(data.into_boxed_slice(), dimensions, device, requires_grad)
+    }
+
     /// Create a new tensor with full control over all fields. This is typically only needed for internal use.
     ///
     /// # Arguments
@@ -125,29 +179,35 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         if data.len() != dimensions.0 * dimensions.1 {
             panic!("Data length does not match dimensions");
         }
-        let requires_grad = match requires_grad {
-            Some(requires_grad) => requires_grad,
-            None => false,
-        };
         let lazy_data: LazyBuffer<T> = LazyBuffer::new(data, dimensions, device);
-        let new_op = match op {
-            Some(op) => op,
-            None => Ops::None,
-        };
-        let rand_id = random_number(T::zero(), T::one()).to_f32();
-        // create unique id
-        let rand_id: i32 = match rand_id {
-            Some(rand_id) => (rand_id * 10000000.0) as i32,
-            None => panic!("Error converting random number to i32"),
-        };
-        let new_left = match left {
-            None => None,
-            Some(left) => { Some(Box::from(left)) }
-        };
-        let new_right = match right {
-            None => None,
-            Some(right) => { Some(Box::from(right)) }
-        };
+        Self::_build_lazy(lazy_data, requires_grad, op, left, right)
+    }
+
+    /// Create a new tensor from a buffer produced by a backend. This is typically only needed for internal use.
+    ///
+    /// # Arguments
+    ///
+    /// * `lazy_data` - The buffer holding the data of the tensor, on any device.
+    /// * `requires_grad` - Whether or not the tensor requires gradients.
+    /// * `op` - The operation that created this tensor.
+    /// * `left` - The first tensor used to create this tensor.
+    /// * `right` - The second tensor used to create this tensor.
+    pub fn _build_lazy(
+        lazy_data: LazyBuffer<T>,
+        requires_grad: Option<bool>,
+        op: Option<Ops>,
+        left: Option<Tensor<T>>,
+        right: Option<Tensor<T>>
+    ) -> Self {
+        let dimensions: Dimensions = lazy_data.dim();
+        if lazy_data.storage().len() != dimensions.0 * dimensions.1 {
+            panic!("Data length does not match dimensions");
+        }
+        let requires_grad = requires_grad.unwrap_or_default();
+        let new_op = op.unwrap_or(Ops::None);
+        let rand_id = new_id().unwrap_or_else(|error| panic!("{}", error));
+        let new_left = left.map(Box::from);
+        let new_right = right.map(Box::from);
         Self {
             lazy_data,
             requires_grad,
@@ -157,6 +217,10 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
             gradient: None,
             unique_id: rand_id,
             is_input: false,
+            custom: None,
+            cast: None,
+            label: None,
+            arg: None,
         }
     }
 
@@ -174,9 +238,16 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         self.op = op;
     }
 
+    /// Attach a label that is shown when the graph is rendered.
+    pub fn with_label(mut self, label: &str) -> Tensor<T> {
+        self.label = Some(label.to_string());
+        self
+    }
+
     pub fn set_dim(&mut self, new_dim: Dimensions) {
         self.lazy_data.set_dim(new_dim);
     }
+
     /// Get data of tensor
     ///
     /// # Returns
@@ -186,10 +257,27 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         self.lazy_data.data()
     }
 
+    ///
+    /// Take the data of the tensor without copying it, dropping the graph it belongs to.
+    /// Data on a device other than the CPU is downloaded first.
+    ///
+    /// # Panics
+    ///
+    /// * If no backend is registered for the device of the tensor.
+    pub fn into_data(self) -> DataArray<T> {
+        self.try_into_data().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Take the data of the tensor, or fail if the device it lives on has no backend.
+    pub fn try_into_data(self) -> Result<DataArray<T>> {
+        self.lazy_data.into_data()
+    }
+
     // get device
     pub fn device(&self) -> &Device {
         self.lazy_data.device()
     }
+
     // get requires_grad
     pub fn requires_grad(&self) -> &bool {
         &self.requires_grad
@@ -202,35 +290,97 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
     ///
     /// * `new_data` - The new data to set.
     ///
+    /// # Panics
+    ///
+    /// * If the data does not fill the dimensions of the tensor.
     pub fn set_data(&mut self, new_data: DataArray<T>) {
-        self.lazy_data.set_data(new_data);
+        self.try_set_data(new_data).unwrap_or_else(|error| panic!("{}", error))
     }
 
-    /// Exchange rows and columns of tensor
+    ///
+    /// Set the data, or fail instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// * `DataLength` if the data does not fill the dimensions of the tensor.
+    /// * `UnsupportedDevice` if the device of the tensor has no backend.
+    pub fn try_set_data(&mut self, new_data: DataArray<T>) -> Result<()> {
+        let dim: Dimensions = self.dim();
+        if new_data.len() != dim.0 * dim.1 {
+            return Err(NanogradError::DataLength { dim, len: new_data.len() });
+        }
+        self.lazy_data.try_set_data(new_data)
+    }
+
+    ///
+    /// Move the tensor to another device. The tensor keeps its place in the computation graph.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to move to.
     ///
     /// # Examples
     ///
     /// ```
-    /// use nanograd::Tensor;
+    /// use nanograd::{ Tensor, Device };
     ///
-    /// let data = vec![1.0, 2.0, 3.0, 4.0].into_boxed_slice();
-    /// let mut tensor = Tensor::new(data, (2, 2), None, None);
-    /// tensor.transpose();
+    /// let tensor: Tensor<f64> = Tensor::ones((2, 2), None, None);
+    /// let tensor = tensor.to(Device::CPU);
     ///
-    /// assert_eq!(tensor.data(), &vec![1.0, 3.0, 2.0, 4.0].into_boxed_slice());
+    /// assert_eq!(tensor.device(), &Device::CPU);
     /// ```
-    pub fn transpose(&mut self) {
+    pub fn to(self, device: Device) -> Tensor<T> {
+        self.try_to(device).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Move the tensor to another device, or fail if either device has no backend.
+    pub fn try_to(mut self, device: Device) -> Result<Tensor<T>> {
+        self.lazy_data = self.lazy_data.try_to(&device)?;
+        Ok(self)
+    }
+
+    pub fn set_as_input(&mut self) {
+        self.requires_grad = false;
+    }
+
+    pub fn flatten(&mut self) {
         let dim: Dimensions = self.dim();
-        let mut new_data = Vec::with_capacity(dim.0 * dim.1);
-        let data: &DataArray<T> = self.data();
-        for i in 0..dim.1 {
-            for j in 0..dim.0 {
-                let index = j * dim.1 + i;
-                new_data.push(data[index]);
-            }
-        }
-        let new_data: DataArray<T> = new_data.into_boxed_slice();
-        self.lazy_data = LazyBuffer::new(new_data, (dim.1, dim.0), None);
+        self.set_dim((1, dim.0 * dim.1));
+    }
+}
+
+impl<T> Tensor<T> where T: Numeric {
+    ///
+    /// Convert the tensor to another numeric type, checking every value. The result is a new leaf
+    /// that gradients do not flow through; use `cast` to convert between floats inside a graph.
+    ///
+    /// # Errors
+    ///
+    /// * `DTypeConversion` for NaN, and for values that overflow `U`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, NanogradError, f16 };
+    ///
+    /// let pixels: Tensor<f64> = Tensor::from_vec(vec![0.0, 127.9, 255.0, 300.0], (2, 2), None, None);
+    /// match pixels.checked_cast::<u8>() {
+    ///     Err(NanogradError::DTypeConversion { value, .. }) => assert_eq!(value, "300"),
+    ///     _ => panic!("expected an overflow"),
+    /// }
+    ///
+    /// let loss: Tensor<f32> = Tensor::from_vec(vec![f32::NAN], (1, 1), None, None);
+    /// assert!(loss.checked_cast::<i64>().is_err());
+    ///
+    /// let large: Tensor<f64> = Tensor::from_vec(vec![1e6], (1, 1), None, None);
+    /// assert!(large.checked_cast::<f16>().is_err());
+    ///
+    /// let labels: Tensor<i64> = Tensor::from_vec(vec![0.0, 2.0, 1.0, 2.0], (1, 4), None, None).checked_cast().unwrap();
+    /// assert_eq!(labels.data().as_ref(), &[0, 2, 1, 2]);
+    /// ```
+    pub fn checked_cast<U: Numeric>(&self) -> Result<Tensor<U>> {
+        let data: DataArray<U> = checked_cast_data(self.data())?;
+        Tensor::try_new(data, self.dim(), Some(self.device().clone()), None)
     }
 
     /// Compute sum of all elements in tensor
@@ -270,18 +420,52 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         device: Option<Device>,
         requires_grad: Option<bool>
     ) -> Self {
-        let data: DataArray<T> = vec![fill_value; dim.0 * dim.1].into_boxed_slice();
-        Self::new(data, dim, device, requires_grad)
+        Self::try_full(dim, fill_value, device, requires_grad).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    ///
+    /// Create a tensor filled with `fill_value`, or fail like `try_new`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, Device, NanogradError };
+    ///
+    /// let sevens: Tensor<f64> = Tensor::try_full((2, 2), 7.0, None, None).unwrap();
+    /// assert_eq!(sevens.data().as_ref(), &[7.0; 4]);
+    ///
+    /// let missing = Tensor::<f64>::try_zeros((2, 2), Some(Device::Custom("tpu".to_string())), None);
+    /// assert!(matches!(missing, Err(NanogradError::UnsupportedDevice(Device::Custom(_)))));
+    /// ```
+    pub fn try_full(
+        dim: Dimensions,
+        fill_value: T,
+        device: Option<Device>,
+        requires_grad: Option<bool>
+    ) -> Result<Self> {
+        let mut data: DataArray<T> = alloc_host(dim.0 * dim.1);
+        data.fill(fill_value);
+        Self::try_new(data, dim, device, requires_grad)
     }
 
     pub fn zeros(dim: Dimensions, device: Option<Device>, requires_grad: Option<bool>) -> Self {
         Self::full(dim, T::zero(), device, requires_grad)
     }
 
+    /// Create a tensor of zeros, or fail like `try_new`.
+    pub fn try_zeros(dim: Dimensions, device: Option<Device>, requires_grad: Option<bool>) -> Result<Self> {
+        Self::try_full(dim, T::zero(), device, requires_grad)
+    }
+
     pub fn ones(dim: Dimensions, device: Option<Device>, requires_grad: Option<bool>) -> Self {
         Self::full(dim, T::one(), device, requires_grad)
     }
 
+    /// Create a tensor of ones, or fail like `try_new`.
+    pub fn try_ones(dim: Dimensions, device: Option<Device>, requires_grad: Option<bool>) -> Result<Self> {
+        Self::try_full(dim, T::one(), device, requires_grad)
+    }
+
     pub fn full_like(other: Tensor<T>, fill_value: T) -> Self {
         let dim: Dimensions = other.dim();
         let device: Option<Device> = Some(other.device().clone());
@@ -297,12 +481,216 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         Self::full_like(other, T::one())
     }
 
-    pub fn set_as_input(&mut self) {
-        self.requires_grad = false;
+    ///
+    /// Fill diagonal of tensor with value
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to fill the diagonal with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Tensor;
+    ///
+    /// let mut tensor:Tensor<f64> = Tensor::ones((2, 2), None, None);
+    /// tensor.fill_diagonal(2.0);
+    /// ```
+    ///
+    pub fn fill_diagonal(&mut self, value: T) {
+        let dim: Dimensions = self.dim();
+        let mut i: usize = 0;
+        let mut j: usize = 0;
+        let mut new_data = vec![T::zero(); dim.0 * dim.1];
+        while i < dim.0 && j < dim.1 {
+            new_data[i * dim.1 + j] = value;
+            i += 1;
+            j += 1;
+        }
+        let new_data: DataArray<T> = new_data.into_boxed_slice();
+        let device: Device = self.device().clone();
+        self.lazy_data = LazyBuffer::new(new_data, dim, Some(device));
+    }
+}
+
+impl<T> Tensor<T> where T: TensorTrait<T> {
+    ///
+    /// Convert the tensor to another float type, e.g. to feed f64 data into an f32 model.
+    /// The cast is differentiable: the gradient of the result is converted back to `T` and flows
+    /// on into the graph of `self`.
+    ///
+    /// Values are rounded to the nearest value of `U` and become infinite if they are too large,
+    /// like `as`; use `checked_cast` to catch overflow instead. Graphs recorded for the JIT see the
+    /// result of a cast as a leaf, so trace functions of a single element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, autograd::grad, nn::transformation::sum };
+    ///
+    /// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+    /// let weight: Tensor<f32> = Tensor::from_vec(vec![0.5, 0.0, 0.0, 0.5], (2, 2), None, None);
+    ///
+    /// // an f32 layer inside an f64 function
+    /// let grads = grad(|x| sum((x[0].clone().cast::<f32>() * weight.clone()).cast::<f64>()), &[x.clone()]);
+    /// assert_eq!(grads[0].data(), &vec![0.5, 0.5, 0.5, 0.5].into_boxed_slice());
+    ///
+    /// // Tensor::backward stores the gradients on the source graph
+    /// let mut loss = sum(x.cast::<f32>());
+    /// loss.backward();
+    /// let source = loss.left.as_ref().unwrap().cast_source::<f64>().unwrap();
+    /// assert_eq!(source.get_gradient().unwrap().data(), &vec![1.0, 1.0, 1.0, 1.0].into_boxed_slice());
+    /// ```
+    pub fn cast<U: TensorTrait<U>>(self) -> Tensor<U> {
+        self.try_cast().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Convert the tensor to another float type like `cast`, or fail if its device has no backend.
+    pub fn try_cast<U: TensorTrait<U>>(self) -> Result<Tensor<U>> {
+        let dim: Dimensions = self.dim();
+        let device: Device = self.device().clone();
+        let data: DataArray<U> = cast_data(self.data());
+        let lazy_data: LazyBuffer<U> = LazyBuffer::try_new(data, dim, Some(device.clone()))?;
+        let mut new_tensor = Tensor::_build_lazy(
+            lazy_data,
+            Some(true),
+            Some(Ops::LoadOps(LoadOps::CAST)),
+            None,
+            None
+        );
+        new_tensor.set_gradient(Tensor::try_zeros(dim, Some(device), None)?);
+        new_tensor.cast = Some(CastOp { source: Box::new(Cast { source: self }) });
+        Ok(new_tensor)
+    }
+
+    /// Get the tensor this tensor was cast from, if it was cast from a `Tensor<S>`.
+    pub fn cast_source<S: TensorTrait<S>>(&self) -> Option<&Tensor<S>> {
+        self.cast.as_ref().and_then(|cast| cast.source::<S>())
+    }
+
+    /// Exchange rows and columns of tensor
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Tensor;
+    ///
+    /// let data = vec![1.0, 2.0, 3.0, 4.0].into_boxed_slice();
+    /// let mut tensor = Tensor::new(data, (2, 2), None, None);
+    /// tensor.transpose();
+    ///
+    /// assert_eq!(tensor.data(), &vec![1.0, 3.0, 2.0, 4.0].into_boxed_slice());
+    /// ```
+    pub fn transpose(&mut self) {
+        self.try_transpose().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Exchange rows and columns of tensor, or fail if its device has no backend.
+    pub fn try_transpose(&mut self) -> Result<()> {
+        let dim: Dimensions = self.dim();
+        let device: Device = self.device().clone();
+        let new_storage = try_get_backend::<T>(&device)?.movement(
+            MovementOps::PERMUTE,
+            self.lazy_data.storage(),
+            dim
+        );
+        self.lazy_data = LazyBuffer::from_storage(new_storage, (dim.1, dim.0), device);
+        Ok(())
+    }
+
+    ///
+    /// Add two tensors of the same dimensions.
+    ///
+    /// # Errors
+    ///
+    /// * `ShapeMismatch` if the dimensions differ.
+    /// * `DeviceMismatch` if the tensors live on different devices.
+    pub fn try_add(self, other: Tensor<T>) -> Result<Tensor<T>> {
+        elementwise(self, other, BinaryOps::ADD)
+    }
+
+    ///
+    /// Subtract a tensor of the same dimensions.
+    ///
+    /// # Errors
+    ///
+    /// * `ShapeMismatch` if the dimensions differ.
+    /// * `DeviceMismatch` if the tensors live on different devices.
+    pub fn try_sub(self, other: Tensor<T>) -> Result<Tensor<T>> {
+        elementwise(self, other, BinaryOps::SUB)
+    }
+
+    ///
+    /// Multiply two matrices.
+    ///
+    /// # Errors
+    ///
+    /// * `ShapeMismatch` if the columns of `self` do not match the rows of `other`.
+    /// * `DeviceMismatch` if the tensors live on different devices.
+    pub fn try_mul(self, other: Tensor<T>) -> Result<Tensor<T>> {
+        mul(self, other)
+    }
+
+    ///
+    /// Add a scalar to every element, like `tensor + scalar`.
+    ///
+    /// # Errors
+    ///
+    /// * `UnsupportedDevice` if the device of the tensor has no backend.
+    pub fn try_add_scalar(self, other: T) -> Result<Tensor<T>> {
+        let new_constant_tensor = Tensor::try_full(self.dim(), other, Some(self.device().clone()), None)?;
+        self.try_add(new_constant_tensor)
+    }
+
+    ///
+    /// Subtract a scalar from every element, like `tensor - scalar`.
+    ///
+    /// # Errors
+    ///
+    /// * `UnsupportedDevice` if the device of the tensor has no backend.
+    pub fn try_sub_scalar(self, other: T) -> Result<Tensor<T>> {
+        let new_constant_tensor = Tensor::try_full(self.dim(), other, Some(self.device().clone()), None)?;
+        self.try_sub(new_constant_tensor)
+    }
+
+    ///
+    /// Multiply every element by a scalar, like `tensor * scalar`.
+    ///
+    /// # Errors
+    ///
+    /// * `UnsupportedDevice` if the device of the tensor has no backend.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Tensor;
+    ///
+    /// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], (2, 3), None, None);
+    /// let y = x.clone().try_mul_scalar(2.0).unwrap().try_add_scalar(1.0).unwrap().try_neg().unwrap();
+    /// assert_eq!(y.data().as_ref(), &[-3.0, -5.0, -7.0, -9.0, -11.0, -13.0]);
+    /// assert_eq!((-(x * 2.0 + 1.0)).data(), y.data());
+    /// ```
+    pub fn try_mul_scalar(self, other: T) -> Result<Tensor<T>> {
+        // multiply by a square matrix with the scalar on its diagonal
+        let columns = self.dim().1;
+        let mut new_constant_tensor = Tensor::try_zeros((columns, columns), Some(self.device().clone()), None)?;
+        new_constant_tensor.fill_diagonal(other);
+        self.try_mul(new_constant_tensor)
+    }
+
+    ///
+    /// Negate every element, like `-tensor`.
+    ///
+    /// # Errors
+    ///
+    /// * `UnsupportedDevice` if the device of the tensor has no backend.
+    pub fn try_neg(self) -> Result<Tensor<T>> {
+        self.try_mul_scalar(T::zero() - T::one())
     }
 
     //
     // run a recursive call to print the tensor and all of its parents and their gradients/data
+    #[cfg(feature = "std")]
     pub fn print_path(&self, depth: usize) {
         let mut i = 0;
         while i < depth {
@@ -338,6 +726,39 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         }
     }
 
+    ///
+    /// Render this tensor and everything it was computed from as a Graphviz DOT graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Tensor;
+    ///
+    /// let a: Tensor<f64> = Tensor::ones((2, 2), None, Some(true)).with_label("a");
+    /// let b: Tensor<f64> = Tensor::ones((2, 2), None, Some(true)).with_label("b");
+    /// let c = a.clone() * b + a;
+    ///
+    /// let dot = c.to_dot();
+    /// assert!(dot.starts_with("digraph tensor_graph {"));
+    /// // a is used twice but drawn once
+    /// assert_eq!(dot.matches("| a |").count(), 1);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn to_dot(&self) -> String {
+        tensor_to_dot(self).render()
+    }
+
+    ///
+    /// Write the Graphviz DOT graph of this tensor to a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where to write the `.dot` file.
+    #[cfg(feature = "std")]
+    pub fn write_dot(&self, path: &str) -> std::io::Result<()> {
+        tensor_to_dot(self).write(path)
+    }
+
     //
     // Generate a tensor with random values drawn from a uniform distribution between 0 and 1.
     //
@@ -345,15 +766,23 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
     // * `dim` - The dimensions of the tensor.
     // * `device` - The device to store the tensor on.
     // * `requires_grad` - Whether or not the tensor requires gradients.
+    #[cfg(feature = "std")]
     pub fn rand(dim: Dimensions, device: Option<Device>, requires_grad: Option<bool>) -> Self {
-        let mut new_data = Vec::with_capacity(dim.0 * dim.1);
-        let mut i: usize = 0;
-        while i < dim.0 * dim.1 {
-            new_data.push(random_number(T::zero(), T::one()));
-            i += 1;
-        }
-        Self::new(new_data.into_boxed_slice(), dim, device, requires_grad)
+        Self::try_rand(dim, device, requires_grad).unwrap_or_else(|error| panic!("{}", error))
     }
+
+    ///
+    /// Generate a tensor of random values between 0 and 1, or fail instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` if the system has no source of randomness.
+    /// * `UnsupportedDevice` if no backend is registered for the device.
+    #[cfg(feature = "std")]
+    pub fn try_rand(dim: Dimensions, device: Option<Device>, requires_grad: Option<bool>) -> Result<Self> {
+        Self::try_uniform(dim, T::zero(), T::one(), device, requires_grad)
+    }
+
     ///
     /// Generate a tensor with random values from a uniform distribution.
     ///
@@ -364,6 +793,7 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
     /// * `high` - The upper bound of the uniform distribution.
     /// * `device` - The device to store the tensor on.
     /// * `requires_grad` - Whether or not the tensor requires gradients.
+    #[cfg(feature = "std")]
     pub fn uniform(
         dim: Dimensions,
         low: T,
@@ -371,13 +801,35 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         device: Option<Device>,
         requires_grad: Option<bool>
     ) -> Self {
+        Self::try_uniform(dim, low, high, device, requires_grad).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    ///
+    /// Generate a tensor of random values between `low` and `high`, or fail like `try_rand`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Tensor;
+    ///
+    /// let noise: Tensor<f64> = Tensor::try_uniform((3, 3), -1.0, 1.0, None, None).unwrap();
+    /// assert!(noise.data().iter().all(|x| (-1.0..=1.0).contains(x)));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn try_uniform(
+        dim: Dimensions,
+        low: T,
+        high: T,
+        device: Option<Device>,
+        requires_grad: Option<bool>
+    ) -> Result<Self> {
         let mut new_data = Vec::with_capacity(dim.0 * dim.1);
         let mut i: usize = 0;
         while i < dim.0 * dim.1 {
-            new_data.push(random_number(low, high));
+            new_data.push(try_random_number(low, high)?);
             i += 1;
         }
-        Self::new(new_data.into_boxed_slice(), dim, device, requires_grad)
+        Self::try_new(new_data.into_boxed_slice(), dim, device, requires_grad)
     }
 
     /// Set gradient of tensor
@@ -453,6 +905,7 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
         self.replace_input(new_data);
         self.forward_internal();
     }
+
     pub fn forward_internal(&mut self) {
         // run new data through computation graph
         if self.left.is_some() {
@@ -462,26 +915,130 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
             self.right.as_mut().unwrap().forward_internal();
         }
     }
+
     pub fn backward(&mut self) {
-        self.set_gradient(Tensor::ones(self.dim(), None, None));
+        self.backward_with_options(None, None);
+    }
 
-        self.backward_internal();
+    /// Compute backward pass like `backward`, or fail like `try_backward_with_options`.
+    pub fn try_backward(&mut self) -> Result<()> {
+        self.try_backward_with_options(None, None)
     }
 
-    fn backward_internal(&mut self) {
-        if self.has_parents() {
-            backward_by_operation(self);
-        }
-        if self.left.is_some() {
-            self.left.as_mut().unwrap().backward_internal();
-        }
-        if self.right.is_some() {
-            self.right.as_mut().unwrap().backward_internal();
-        }
+    ///
+    /// Compute backward pass starting from an explicit gradient of this tensor.
+    /// Needed when the tensor is not a scalar and should not be seeded with ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `grad_output` - The gradient of this tensor. Must have the same dimensions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Tensor;
+    ///
+    /// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, Some(true));
+    /// let b: Tensor<f64> = Tensor::from_vec(vec![1.0, 1.0, 1.0, 1.0], (2, 2), None, Some(true));
+    /// let mut c = a + b;
+    /// c.backward_with(Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None));
+    ///
+    /// let grad_a = c.left.as_ref().unwrap().get_gradient().unwrap();
+    /// assert_eq!(grad_a.data(), &vec![1.0, 2.0, 3.0, 4.0].into_boxed_slice());
+    /// ```
+    pub fn backward_with(&mut self, grad_output: Tensor<T>) {
+        self.backward_with_options(Some(grad_output), None);
+    }
+
+    /// Compute backward pass from an explicit gradient, or fail like `try_backward_with_options`.
+    pub fn try_backward_with(&mut self, grad_output: Tensor<T>) -> Result<()> {
+        self.try_backward_with_options(Some(grad_output), None)
     }
 
-    fn has_parents(&self) -> bool {
-        self.left.is_some() || self.right.is_some()
+    ///
+    /// Compute backward pass with full control over the seed gradient and graph lifetime.
+    ///
+    /// # Arguments
+    ///
+    /// * `grad_output` - The gradient of this tensor. Defaults to ones.
+    /// * `retain_graph` - Whether to keep intermediate buffers for another pass. Defaults to true.
+    ///   When false, the data of every intermediate tensor is freed once gradients are computed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Tensor;
+    ///
+    /// let a: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, Some(true));
+    /// let b: Tensor<f64> = Tensor::from_vec(vec![1.0, 1.0, 1.0, 1.0], (2, 2), None, Some(true));
+    /// let mut d = (a + b) - Tensor::ones((2, 2), None, None);
+    /// d.backward_with_options(None, Some(false));
+    ///
+    /// // the intermediate sum has been freed, the leaves keep their data
+    /// let sum = d.left.as_ref().unwrap();
+    /// assert!(!sum.lazy_data.is_realized());
+    /// assert_eq!(sum.left.as_ref().unwrap().data().len(), 4);
+    /// ```
+    pub fn backward_with_options(
+        &mut self,
+        grad_output: Option<Tensor<T>>,
+        retain_graph: Option<bool>
+    ) {
+        self.try_backward_with_options(grad_output, retain_graph).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    ///
+    /// Compute backward pass like `backward_with_options`, or fail instead of panicking. Gradients
+    /// stored before the failure stay on the graph.
+    ///
+    /// # Errors
+    ///
+    /// * `ShapeMismatch` if `grad_output` does not match the dimensions of this tensor.
+    /// * `GraphFreed` if the graph was freed by a pass with `retain_graph` set to false.
+    /// * `UnsupportedOp` if an op of the graph has no backward rule.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, NanogradError, nn::transformation::sum };
+    ///
+    /// let a: Tensor<f64> = Tensor::ones((2, 2), None, Some(true));
+    /// let mut b = sum(a.clone() + a);
+    /// assert!(matches!(b.try_backward_with(Tensor::ones((2, 2), None, None)), Err(NanogradError::ShapeMismatch { .. })));
+    ///
+    /// b.try_backward_with_options(None, Some(false)).unwrap();
+    /// assert!(matches!(b.try_backward(), Err(NanogradError::GraphFreed)));
+    /// ```
+    pub fn try_backward_with_options(
+        &mut self,
+        grad_output: Option<Tensor<T>>,
+        retain_graph: Option<bool>
+    ) -> Result<()> {
+        let grad_outputs: Vec<Tensor<T>> = grad_output.into_iter().collect();
+        try_backward(core::slice::from_mut(self), &grad_outputs, retain_graph)
+    }
+
+    ///
+    /// Cut this tensor out of the computation graph.
+    /// The data is moved, not copied, into a new leaf that gradients do not flow through.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::{ Tensor, autograd::grad, nn::transformation::sum };
+    ///
+    /// let x: Tensor<f64> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, None);
+    ///
+    /// // stop the gradient through the second term
+    /// let grads = grad(|x| sum(x[0].clone() + x[0].clone().detach()), &[x]);
+    ///
+    /// assert_eq!(grads[0].data(), &vec![1.0, 1.0, 1.0, 1.0].into_boxed_slice());
+    /// ```
+    pub fn detach(self) -> Tensor<T> {
+        let device: Device = self.device().clone();
+        let mut detached = Tensor::new(Vec::new().into_boxed_slice(), (0, 0), Some(device), None);
+        detached.lazy_data = self.lazy_data;
+        detached
     }
 
     ///
@@ -510,6 +1067,10 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
             self.right = new_input.right;
             self.gradient = new_input.gradient;
             self.unique_id = new_input.unique_id;
+            self.custom = new_input.custom;
+            self.cast = new_input.cast;
+            self.label = new_input.label;
+            self.arg = new_input.arg;
             self.is_input = true;
             true
         } else {
@@ -523,148 +1084,99 @@ impl<T> Tensor<T> where T: TensorTrait<T> {
             is_replaced
         }
     }
+}
 
-    ///
-    /// Fill diagonal of tensor with value
-    ///
-    /// # Arguments
-    ///
-    /// * `value` - The value to fill the diagonal with.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use nanograd::Tensor;
-    ///
-    /// let mut tensor:Tensor<f64> = Tensor::ones((2, 2), None, None);
-    /// tensor.fill_diagonal(2.0);
-    /// ```
-    ///
-    pub fn fill_diagonal(&mut self, value: T) {
-        let dim: Dimensions = self.dim();
-        let mut i: usize = 0;
-        let mut j: usize = 0;
-        let mut new_data = vec![T::zero(); dim.0 * dim.1];
-        let mut index: usize = 0;
-        while i < dim.0 && j < dim.1 {
-            index = i * dim.1 + j;
-            new_data[index] = value;
-            i += 1;
-            j += 1;
-        }
-        let new_data: DataArray<T> = new_data.into_boxed_slice();
-        self.lazy_data = LazyBuffer::new(new_data, dim, None);
+// TODO: ONLY ADD GRADIENT/PREV IF REQUIRES GRAD IS TRUE
+// math helpers
+/// Create a unique id for a new tensor.
+#[cfg(feature = "std")]
+fn new_id() -> Result<i32> {
+    let rand_id: f32 = try_random_number(0.0, 1.0)?;
+    match (rand_id * 10000000.0).to_i32() {
+        Some(rand_id) => Ok(rand_id),
+        None => Err(NanogradError::conversion::<f32, i32>(rand_id)),
     }
+}
 
-    pub fn flatten(&mut self) {
-        let dim: Dimensions = self.dim();
-        self.set_dim((1, dim.0 * dim.1));
+/// Create a unique id for a new tensor. Without an RNG, ids count up from zero.
+#[cfg(not(feature = "std"))]
+fn new_id() -> Result<i32> {
+    static NEXT_ID: AtomicI32 = AtomicI32::new(0);
+    Ok(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Get the device shared by both operands of a binary op.
+fn common_device<T: Element>(a: &Tensor<T>, b: &Tensor<T>) -> Result<Device> {
+    if a.device() != b.device() {
+        return Err(NanogradError::DeviceMismatch { left: a.device().clone(), right: b.device().clone() });
     }
+    Ok(a.device().clone())
 }
 
-// TODO: ONLY ADD GRADIENT/PREV IF REQUIRES GRAD IS TRUE
-// math helpers
-fn add<T: TensorTrait<T>>(a: Tensor<T>, b: Tensor<T>) -> Tensor<T> {
+fn elementwise<T: TensorTrait<T>>(a: Tensor<T>, b: Tensor<T>, op: BinaryOps) -> Result<Tensor<T>> {
     // make sure dimensions match
     let a_dim: Dimensions = a.dim();
     let b_dim: Dimensions = b.dim();
     // can only add tensors of same dimensions
-    assert_eq!(a_dim, b_dim);
-    let array_len = a_dim.0 * a_dim.1;
-    let mut new_data = Vec::with_capacity(a_dim.0 * a_dim.1);
-    let mut i: usize = 0;
-    let a_data = a.data();
-    let b_data = b.data();
-    while i < array_len {
-        new_data.push(a_data[i] + b_data[i]);
-        i += 1;
-    }
-    // create Box<[T]> from Vec<T>
-    let new_data: DataArray<T> = new_data.into_boxed_slice();
-    let mut new_tensor = Tensor::new_internal(
-        new_data,
-        a_dim,
-        None,
+    if a_dim != b_dim {
+        return Err(NanogradError::shape_mismatch(&format!("{:?}", op), a_dim, b_dim));
+    }
+    let device: Device = common_device(&a, &b)?;
+    let new_storage = try_get_backend::<T>(&device)?.binary(
+        op,
+        a.lazy_data.storage(),
+        b.lazy_data.storage(),
+        a_dim
+    );
+    let lazy_data: LazyBuffer<T> = LazyBuffer::from_storage(new_storage, a_dim, device.clone());
+    let mut new_tensor = Tensor::_build_lazy(
+        lazy_data,
         Some(true),
-        Some(Ops::BinaryOps(BinaryOps::ADD)),
+        Some(Ops::BinaryOps(op)),
         Some(a),
         Some(b)
     );
-    new_tensor.set_gradient(Tensor::zeros(a_dim, None, None));
-    new_tensor
+    new_tensor.set_gradient(Tensor::try_zeros(a_dim, Some(device), None)?);
+    Ok(new_tensor)
+}
+
+fn add<T: TensorTrait<T>>(a: Tensor<T>, b: Tensor<T>) -> Tensor<T> {
+    a.try_add(b).unwrap_or_else(|error| panic!("{}", error))
 }
 
-fn mul<T: TensorTrait<T>>(a: Tensor<T>, b: Tensor<T>, add_to_graph: bool) -> Tensor<T> {
+fn mul<T: TensorTrait<T>>(a: Tensor<T>, b: Tensor<T>) -> Result<Tensor<T>> {
     // make sure dimensions match
     let a_dim: Dimensions = a.dim();
     let b_dim: Dimensions = b.dim();
     if !is_valid_matrix_multiplication(a_dim, b_dim) {
-        panic!("Invalid matrix multiplication");
-    }
-    let mut new_data = Vec::with_capacity(a_dim.0 * b_dim.1);
-    // print new data len
-    let a_data = a.data();
-    let b_data = b.data();
-    // multiply vals
-    for i in 0..a_dim.0 {
-        for j in 0..b_dim.1 {
-            let mut sum = T::zero();
-            for k in 0..b_dim.0 {
-                let index_a = i * a_dim.0 + k;
-                let index_b = k * b_dim.1 + j;
-                sum = sum + a_data[index_a] * b_data[index_b];
-            }
-            // can assume indices will be sequential
-            // curr index: i * other_dim.1 + j
-            new_data.push(sum);
-        }
+        return Err(NanogradError::shape_mismatch("MUL", a_dim, b_dim));
     }
-    // create Box<[T]> from Vec<T>
-    let new_data: DataArray<T> = new_data.into_boxed_slice();
-
+    let device: Device = common_device(&a, &b)?;
+    let new_storage = try_get_backend::<T>(&device)?.matmul(
+        a.lazy_data.storage(),
+        a_dim,
+        b.lazy_data.storage(),
+        b_dim
+    );
     let new_dim: Dimensions = new_dimensions_after_matrix_multiplication(a_dim, b_dim);
-    let mut new_tensor = Tensor::new_internal(
-        new_data,
-        new_dim,
-        None,
+    let lazy_data: LazyBuffer<T> = LazyBuffer::from_storage(new_storage, new_dim, device.clone());
+    let mut new_tensor = Tensor::_build_lazy(
+        lazy_data,
         Some(true),
         Some(Ops::BinaryOps(BinaryOps::MUL)),
         Some(a),
         Some(b)
     );
-    new_tensor.set_gradient(Tensor::zeros(new_dim, None, None));
-    new_tensor
+    new_tensor.set_gradient(Tensor::try_zeros(new_dim, Some(device), None)?);
+    Ok(new_tensor)
+}
+
+fn matmul<T: TensorTrait<T>>(a: Tensor<T>, b: Tensor<T>) -> Tensor<T> {
+    mul(a, b).unwrap_or_else(|error| panic!("{}", error))
 }
 
 fn sub<T: TensorTrait<T>>(a: Tensor<T>, b: Tensor<T>) -> Tensor<T> {
-    // make sure dimensions match
-    let a_dim: Dimensions = a.dim();
-    let b_dim: Dimensions = b.dim();
-    // can only add tensors of same dimensions
-    assert_eq!(a_dim, b_dim);
-    let array_len = a_dim.0 * a_dim.1;
-    let mut new_data = Vec::with_capacity(a_dim.0 * a_dim.1);
-    let mut i: usize = 0;
-    let a_data = a.data();
-    let b_data = b.data();
-    let neg_one = T::zero() - T::one();
-    while i < array_len {
-        new_data.push(a_data[i] + neg_one * b_data[i]);
-        i += 1;
-    }
-    // create Box<[T]> from Vec<T>
-    let new_data: DataArray<T> = new_data.into_boxed_slice();
-    let mut new_tensor = Tensor::new_internal(
-        new_data,
-        a_dim,
-        None,
-        Some(true),
-        Some(Ops::BinaryOps(BinaryOps::SUB)),
-        Some(a),
-        Some(b)
-    );
-    new_tensor.set_gradient(Tensor::zeros(a_dim, None, None));
-    new_tensor
+    a.try_sub(b).unwrap_or_else(|error| panic!("{}", error))
 }
 
 // addition
@@ -687,7 +1199,7 @@ impl<T> Sub<Tensor<T>> for Tensor<T> where T: TensorTrait<T> {
 impl<T> Mul<Tensor<T>> for Tensor<T> where T: TensorTrait<T> {
     type Output = Tensor<T>;
     fn mul(self, other: Tensor<T>) -> Tensor<T> {
-        mul(self, other, true)
+        matmul(self, other)
     }
 }
 
@@ -695,11 +1207,7 @@ impl<T> Mul<Tensor<T>> for Tensor<T> where T: TensorTrait<T> {
 impl<T> Mul<T> for Tensor<T> where T: TensorTrait<T> {
     type Output = Tensor<T>;
     fn mul(self, other: T) -> Tensor<T> {
-        // create new diagonal matrix with values of other
-        let dim: Dimensions = self.dim();
-        let mut new_constant_tensor = Tensor::zeros(dim, None, Some(true));
-        new_constant_tensor.fill_diagonal(other);
-        mul(self, new_constant_tensor, true)
+        self.try_mul_scalar(other).unwrap_or_else(|error| panic!("{}", error))
     }
 }
 
@@ -707,10 +1215,7 @@ impl<T> Mul<T> for Tensor<T> where T: TensorTrait<T> {
 impl<T> Add<T> for Tensor<T> where T: TensorTrait<T> {
     type Output = Tensor<T>;
     fn add(self, other: T) -> Tensor<T> {
-        // create new diagonal matrix with values of other
-        let dim: Dimensions = self.dim();
-        let new_constant_tensor = Tensor::full(dim, other, None, Some(true));
-        add(self, new_constant_tensor)
+        self.try_add_scalar(other).unwrap_or_else(|error| panic!("{}", error))
     }
 }
 
@@ -718,37 +1223,147 @@ impl<T> Add<T> for Tensor<T> where T: TensorTrait<T> {
 impl<T> Sub<T> for Tensor<T> where T: TensorTrait<T> {
     type Output = Tensor<T>;
     fn sub(self, other: T) -> Tensor<T> {
-        // create new diagonal matrix with values of other
-        let dim: Dimensions = self.dim();
-        let new_constant_tensor = Tensor::full(dim, other, None, Some(true));
-        sub(self, new_constant_tensor)
+        self.try_sub_scalar(other).unwrap_or_else(|error| panic!("{}", error))
     }
 }
 
-// implement display trait for lazy buffer
-impl<T> fmt::Display for Tensor<T> where T: TensorTrait<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // diplay array as matrix
-        let dim: Dimensions = self.dim();
-        let mut i: usize = 0;
-        println!("\n");
-        write!(f, "tensor( ")?;
-        while i < dim.0 {
-            if i != 0 {
-                write!(f, "\t")?;
-            }
-            let mut j: usize = 0;
-            while j < dim.1 {
-                let index = i * dim.0 + j;
-                write!(f, "{:} ", self.data()[index])?;
-                j += 1;
-            }
-            i += 1;
-            if i < dim.0 {
-                write!(f, "\n")?;
+impl<T> Tensor<T> where T: Element {
+    /// Write the header and the elements of the tensor, with `details` between the header's parentheses.
+    fn write_pretty(&self, f: &mut fmt::Formatter<'_>, details: &str) -> fmt::Result {
+        let (rows, columns): Dimensions = self.dim();
+        writeln!(
+            f,
+            "tensor({}shape=({}, {}), dtype={}, device={:?}, requires_grad={})",
+            details,
+            rows,
+            columns,
+            dtype_name::<T>(),
+            self.device(),
+            self.requires_grad
+        )?;
+        // read device buffers through the host
+        let host: LazyBuffer<T>;
+        let data = match self.device() {
+            Device::CPU => self.data(),
+            _ => {
+                host = self.lazy_data.try_to(&Device::CPU).map_err(|_| fmt::Error)?;
+                host.data()
             }
+        };
+        if data.len() != rows * columns {
+            return write!(f, "[released]");
+        }
+        write_array(f, data, &[rows, columns], &print_options())
+    }
+}
+
+///
+/// Print the tensor like NumPy: a header with the shape, element type, device and whether it
+/// requires gradients, then the elements with aligned columns. Floats are printed with the
+/// precision set by `set_print_options`, and large tensors are summarized with `...`.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::Tensor;
+///
+/// let a: Tensor<f32> = Tensor::from_vec(vec![1.5, -2.0, 3.25, 10.0, 0.0, 6.0], (2, 3), None, Some(true));
+/// assert_eq!(
+///     a.to_string(),
+///     "tensor(shape=(2, 3), dtype=f32, device=CPU, requires_grad=true)\n\
+///      [[ 1.5000, -2.0000,  3.2500],\n \
+///       [10.0000,  0.0000,  6.0000]]"
+/// );
+///
+/// let labels: Tensor<i64> = Tensor::from_vec(vec![3, 1, 4], (3, 1), None, None);
+/// assert_eq!(labels.to_string(), "tensor(shape=(3, 1), dtype=i64, device=CPU, requires_grad=false)\n[[3],\n [1],\n [4]]");
+/// ```
+impl<T> fmt::Display for Tensor<T> where T: Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_pretty(f, "")
+    }
+}
+
+///
+/// Print the tensor as `Display` does, with the id, op and label of its node in the header.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::Tensor;
+///
+/// let a: Tensor<f64> = Tensor::ones((2, 2), None, Some(true)).with_label("a");
+/// let b = a.clone() + a;
+///
+/// let debug = format!("{:?}", b);
+/// assert!(debug.contains("op=BinaryOps(ADD), label=None, shape=(2, 2), dtype=f64"));
+/// assert!(debug.ends_with("[[2., 2.],\n [2., 2.]]"));
+/// ```
+impl<T> fmt::Debug for Tensor<T> where T: Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let details = format!("id={}, op={:?}, label={:?}, ", self.unique_id, self.op, self.label);
+        self.write_pretty(f, &details)
+    }
+}
+
+///
+/// Serializes the data, shape, dtype and `requires_grad` of the tensor, not the graph it belongs
+/// to: a deserialized tensor is a leaf on the default device. Data on a device other than the CPU
+/// is downloaded first.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::Tensor;
+///
+/// let tensor: Tensor<f32> = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], (2, 2), None, Some(true));
+/// let json = serde_json::to_string(&tensor).unwrap();
+/// assert_eq!(json, r#"{"data":[1.0,2.0,3.0,4.0],"shape":[2,2],"dtype":"f32","requires_grad":true}"#);
+///
+/// let back: Tensor<f32> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(back.data(), tensor.data());
+/// assert!(*back.requires_grad());
+///
+/// let bytes = bincode::serialize(&tensor).unwrap();
+/// let back: Tensor<f32> = bincode::deserialize(&bytes).unwrap();
+/// assert_eq!(back.dim(), (2, 2));
+///
+/// // the dtype has to match
+/// assert!(serde_json::from_str::<Tensor<f64>>(&json).is_err());
+/// ```
+#[cfg(feature = "serde")]
+impl<T> Serialize for Tensor<T> where T: Element + Serialize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        let data = self.lazy_data.host_data().map_err(serde::ser::Error::custom)?;
+        let mut state = serializer.serialize_struct("Tensor", 4)?;
+        state.serialize_field("data", data.as_ref())?;
+        state.serialize_field("shape", &self.dim())?;
+        state.serialize_field("dtype", &dtype_name::<T>())?;
+        state.serialize_field("requires_grad", &self.requires_grad)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "Tensor")]
+struct TensorParts<T> {
+    data: Vec<T>,
+    shape: Dimensions,
+    dtype: String,
+    #[serde(default)]
+    requires_grad: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for Tensor<T> where T: Element + Deserialize<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        let TensorParts { data, shape, dtype, requires_grad } = TensorParts::<T>::deserialize(deserializer)?;
+        let expected = dtype_name::<T>();
+        if dtype != expected {
+            return Err(D::Error::custom(format!("Expected a tensor of {}, found {}", expected, dtype)));
         }
-        write!(f, ")\n")
+        Tensor::try_from_vec(data, shape, None, Some(requires_grad)).map_err(D::Error::custom)
     }
 }
 
@@ -757,10 +1372,6 @@ impl<T> fmt::Display for Tensor<T> where T: TensorTrait<T> {
 impl<T> Neg for Tensor<T> where T: TensorTrait<T> {
     type Output = Tensor<T>;
     fn neg(self) -> Tensor<T> {
-        // multiply by matrix with -1 on diagonal
-        let dim: Dimensions = self.dim();
-        let mut new_constant_tensor = Tensor::zeros(dim, None, Some(true));
-        new_constant_tensor.fill_diagonal(T::zero() - T::one());
-        mul(self, new_constant_tensor, true)
+        self.try_neg().unwrap_or_else(|error| panic!("{}", error))
     }
 }
@@ -1,47 +1,59 @@
-use std::ops::{ Add, Mul, Sub, Div };
-use std::fmt::Display;
-use std::fmt::Debug;
-use std::cmp::PartialOrd;
+use core::ops::{ Add, Mul, Sub, Div };
+use core::fmt::Display;
+use core::fmt::Debug;
+use core::cmp::PartialOrd;
 
 extern crate num;
-use num::{ ToPrimitive, FromPrimitive, Float };
-use self::num::pow::Pow;
+use num::{ ToPrimitive, FromPrimitive, NumCast, Float };
 use self::num::traits::Zero;
 use self::num::traits::One;
 
-pub trait TensorTrait<T>: Zero +
+///
+/// Anything a tensor can hold, e.g. `bool` masks.
+/// Tensors of any element can be created, moved between devices, indexed and printed.
+pub trait Element: 'static + Send + Sync + Clone + Copy + Default + Display + Debug + PartialEq {}
+
+impl<T> Element for T where T: 'static + Send + Sync + Clone + Copy + Default + Display + Debug + PartialEq {}
+
+///
+/// Elements with arithmetic and an order, e.g. `i64` labels and `u8` pixels.
+/// Numeric tensors can also be filled, summed and compared.
+pub trait Numeric: Element +
+    Zero +
     One +
-    Clone +
-    Copy +
-    TryFrom<f32> +
-    Display +
-    Debug +
+    PartialOrd +
     ToPrimitive +
     FromPrimitive +
-    PartialOrd +
-    Float +
-    Pow<T, Output = T> +
-    Div<T, Output = T> +
-    Add<T, Output = T> +
-    Mul<T, Output = T> +
-    Sub<T, Output = T> {}
-
-impl<T> TensorTrait<T>
+    NumCast +
+    Div<Self, Output = Self> +
+    Add<Self, Output = Self> +
+    Mul<Self, Output = Self> +
+    Sub<Self, Output = Self> {}
+
+impl<T> Numeric
     for T
     where
-        T: Zero +
+        T: Element +
+            Zero +
             One +
-            Clone +
-            Copy +
-            TryFrom<f32> +
-            Display +
-            Debug +
+            PartialOrd +
             ToPrimitive +
             FromPrimitive +
-            PartialOrd +
-            Float +
-            Pow<T, Output = T> +
+            NumCast +
             Div<T, Output = T> +
             Add<T, Output = T> +
             Mul<T, Output = T> +
             Sub<T, Output = T> {}
+
+///
+/// Floating point elements, including the half precision `f16` and `bf16`.
+/// Only float tensors can be differentiated.
+pub trait FloatElement: Numeric + Float {}
+
+impl<T> FloatElement for T where T: Numeric + Float {}
+
+///
+/// The element of a differentiable tensor. Every op that records a graph requires it.
+pub trait TensorTrait<T>: FloatElement {}
+
+impl<T> TensorTrait<T> for T where T: FloatElement {}
@@ -1,7 +1,12 @@
-#[derive(Clone, PartialEq, Eq)]
+use alloc::string::String;
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Device {
     CPU,
     OPENCL,
+    /// Kernels generated as C and compiled with the system compiler.
+    CLANG,
+    /// A device provided by a third-party backend, identified by name.
+    Custom(String),
 }
 
 // TODO: add a function to detct env and set device accordingly
@@ -0,0 +1,143 @@
+use core::any::TypeId;
+use core::fmt;
+
+use num::NumCast;
+
+use crate::{ DataArray, Numeric, FloatElement, NanogradError };
+use crate::error::Result;
+use crate::types::half::{ f16, bf16 };
+
+///
+/// The element type of a tensor as a value, for code that picks types at runtime, e.g. file formats
+/// and a future dynamically-typed tensor.
+///
+/// Combining two types follows these promotion rules, the same as PyTorch:
+///
+/// * A type combined with itself is unchanged.
+/// * `Bool` promotes to the other type.
+/// * Integers promote to the wider integer: `U8` with `I64` is `I64`.
+/// * An integer with a float is the float, whatever its width: `I64` with `F16` is `F16`.
+/// * Floats promote to the wider float: `F32` with `F64` is `F64`.
+/// * `F16` with `BF16` is `F32`, the smallest type that holds both.
+///
+/// Statically typed tensors never promote implicitly; call `Tensor::cast` to combine them.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ f16, bf16, types::dtype::DType };
+///
+/// assert_eq!(DType::of::<f32>(), Some(DType::F32));
+/// assert_eq!(DType::I64.promote(DType::F16), DType::F16);
+/// assert_eq!(DType::F16.promote(DType::BF16), DType::F32);
+/// assert_eq!(DType::Bool.promote(DType::U8), DType::U8);
+/// assert_eq!(DType::BF16.size(), 2);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DType {
+    Bool,
+    U8,
+    I64,
+    BF16,
+    F16,
+    F32,
+    F64,
+}
+
+impl DType {
+    /// The dtype of the element type `T`, or `None` if it has none, e.g. `Dual`.
+    pub fn of<T: 'static>() -> Option<DType> {
+        let id = TypeId::of::<T>();
+        [
+            (TypeId::of::<bool>(), DType::Bool),
+            (TypeId::of::<u8>(), DType::U8),
+            (TypeId::of::<i64>(), DType::I64),
+            (TypeId::of::<bf16>(), DType::BF16),
+            (TypeId::of::<f16>(), DType::F16),
+            (TypeId::of::<f32>(), DType::F32),
+            (TypeId::of::<f64>(), DType::F64),
+        ]
+            .iter()
+            .find(|(type_id, _)| *type_id == id)
+            .map(|(_, dtype)| *dtype)
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, DType::BF16 | DType::F16 | DType::F32 | DType::F64)
+    }
+
+    /// The size of one element in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            DType::Bool | DType::U8 => 1,
+            DType::BF16 | DType::F16 => 2,
+            DType::F32 => 4,
+            DType::I64 | DType::F64 => 8,
+        }
+    }
+
+    /// The type that two tensors of these types combine into. See the rules above.
+    pub fn promote(self, other: DType) -> DType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (DType::Bool, b) | (b, DType::Bool) => b,
+            (DType::F16, DType::BF16) | (DType::BF16, DType::F16) => DType::F32,
+            (a, b) if a.is_float() != b.is_float() => if a.is_float() { a } else { b },
+            (a, b) => a.max(b),
+        }
+    }
+}
+
+impl fmt::Display for DType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DType::Bool => "bool",
+            DType::U8 => "u8",
+            DType::I64 => "i64",
+            DType::BF16 => "bf16",
+            DType::F16 => "f16",
+            DType::F32 => "f32",
+            DType::F64 => "f64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+///
+/// Convert floats to another float type, rounding to the nearest value like `as`.
+/// Values too large for the target become infinite.
+pub fn cast_data<T: FloatElement, U: FloatElement>(data: &[T]) -> DataArray<U> {
+    data.iter()
+        .map(|value| value.to_f64().and_then(U::from_f64).unwrap_or_else(U::nan))
+        .collect()
+}
+
+///
+/// Convert elements to another numeric type, failing on values the target cannot represent.
+/// Fractions are truncated towards zero when converting to an integer.
+///
+/// # Errors
+///
+/// * `DTypeConversion` for NaN, and for values that overflow the target, e.g. 300 as `u8` or 1e6 as `f16`.
+pub fn checked_cast_data<T: Numeric, U: Numeric>(data: &[T]) -> Result<DataArray<U>> {
+    data.iter().map(|value| checked_cast_value(*value)).collect()
+}
+
+fn checked_cast_value<T: Numeric, U: Numeric>(value: T) -> Result<U> {
+    let source = value.to_f64();
+    if source.is_none_or(f64::is_nan) {
+        return Err(NanogradError::conversion::<T, U>(value));
+    }
+    match <U as NumCast>::from(value) {
+        Some(cast) => {
+            // floats saturate to infinity instead of failing, which is still an overflow
+            let overflowed = source.is_some_and(f64::is_finite) && !cast.to_f64().is_some_and(f64::is_finite);
+            if overflowed {
+                Err(NanogradError::conversion::<T, U>(value))
+            } else {
+                Ok(cast)
+            }
+        }
+        None => Err(NanogradError::conversion::<T, U>(value)),
+    }
+}
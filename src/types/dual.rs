@@ -0,0 +1,416 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::num::FpCategory;
+use core::ops::{ Add, Div, Mul, Neg, Rem, Sub };
+
+use num::{ Float, FromPrimitive, Num, NumCast, One, ToPrimitive, Zero };
+use num::pow::Pow;
+
+use crate::TensorTrait;
+
+///
+/// A dual number `real + dual * ε` with `ε² = 0`.
+///
+/// Evaluating a function on dual numbers carries the derivative along in the `dual` part,
+/// which makes `Dual<F>` a drop-in element type for forward-mode differentiation of any
+/// tensor function. Comparisons only look at the real part, so control flow such as `max`
+/// follows the primal values.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::Dual;
+/// use num::Float;
+///
+/// let x = Dual::variable(3.0_f64);
+/// let y = x * x + x.sin();
+///
+/// assert_eq!(y.real(), 9.0 + 3.0_f64.sin());
+/// assert_eq!(y.dual(), 6.0 + 3.0_f64.cos());
+///
+/// // x^0 is constant everywhere, including at 0
+/// let one = Dual::variable(0.0_f64).powi(0);
+/// assert_eq!((one.real(), one.dual()), (1.0, 0.0));
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Dual<F: TensorTrait<F>> {
+    real: F,
+    dual: F,
+}
+
+impl<F: TensorTrait<F>> Dual<F> {
+    /// Create a dual number from its real and tangent parts.
+    pub fn new(real: F, dual: F) -> Self {
+        Dual { real, dual }
+    }
+
+    /// Create a dual number with a zero tangent.
+    pub fn constant(real: F) -> Self {
+        Dual { real, dual: F::zero() }
+    }
+
+    /// Create a dual number with a unit tangent, i.e. the variable being differentiated.
+    pub fn variable(real: F) -> Self {
+        Dual { real, dual: F::one() }
+    }
+
+    /// Get the real (primal) part.
+    pub fn real(&self) -> F {
+        self.real
+    }
+
+    /// Get the tangent part.
+    pub fn dual(&self) -> F {
+        self.dual
+    }
+
+    /// Apply a scalar function with value `value` and derivative `derivative` at `self.real`.
+    fn chain(self, value: F, derivative: F) -> Self {
+        Dual { real: value, dual: derivative * self.dual }
+    }
+}
+
+impl<F: TensorTrait<F>> PartialEq for Dual<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.real == other.real
+    }
+}
+
+impl<F: TensorTrait<F>> PartialOrd for Dual<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.real.partial_cmp(&other.real)
+    }
+}
+
+impl<F: TensorTrait<F>> fmt::Display for Dual<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "{:.*}+{:.*}ε", precision, self.real, precision, self.dual),
+            None => write!(f, "{}+{}ε", self.real, self.dual),
+        }
+    }
+}
+
+// arithmetic
+
+impl<F: TensorTrait<F>> Add<Dual<F>> for Dual<F> {
+    type Output = Dual<F>;
+    fn add(self, other: Dual<F>) -> Dual<F> {
+        Dual::new(self.real + other.real, self.dual + other.dual)
+    }
+}
+
+impl<F: TensorTrait<F>> Sub<Dual<F>> for Dual<F> {
+    type Output = Dual<F>;
+    fn sub(self, other: Dual<F>) -> Dual<F> {
+        Dual::new(self.real - other.real, self.dual - other.dual)
+    }
+}
+
+impl<F: TensorTrait<F>> Mul<Dual<F>> for Dual<F> {
+    type Output = Dual<F>;
+    fn mul(self, other: Dual<F>) -> Dual<F> {
+        Dual::new(self.real * other.real, self.dual * other.real + self.real * other.dual)
+    }
+}
+
+impl<F: TensorTrait<F>> Div<Dual<F>> for Dual<F> {
+    type Output = Dual<F>;
+    fn div(self, other: Dual<F>) -> Dual<F> {
+        let real = self.real / other.real;
+        Dual::new(real, (self.dual - real * other.dual) / other.real)
+    }
+}
+
+impl<F: TensorTrait<F>> Rem<Dual<F>> for Dual<F> {
+    type Output = Dual<F>;
+    fn rem(self, other: Dual<F>) -> Dual<F> {
+        // a % b = a - b * trunc(a / b)
+        let quotient = (self.real / other.real).trunc();
+        Dual::new(self.real % other.real, self.dual - other.dual * quotient)
+    }
+}
+
+impl<F: TensorTrait<F>> Neg for Dual<F> {
+    type Output = Dual<F>;
+    fn neg(self) -> Dual<F> {
+        Dual::new(F::zero() - self.real, F::zero() - self.dual)
+    }
+}
+
+impl<F: TensorTrait<F>> Pow<Dual<F>> for Dual<F> {
+    type Output = Dual<F>;
+    fn pow(self, other: Dual<F>) -> Dual<F> {
+        self.powf(other)
+    }
+}
+
+// conversions
+
+impl<F: TensorTrait<F>> TryFrom<f32> for Dual<F> {
+    type Error = &'static str;
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        match F::from_f32(value) {
+            Some(real) => Ok(Dual::constant(real)),
+            None => Err("Error converting f32 to dual number"),
+        }
+    }
+}
+
+impl<F: TensorTrait<F>> Zero for Dual<F> {
+    fn zero() -> Self {
+        Dual::constant(F::zero())
+    }
+    fn is_zero(&self) -> bool {
+        self.real.is_zero() && self.dual.is_zero()
+    }
+}
+
+impl<F: TensorTrait<F>> One for Dual<F> {
+    fn one() -> Self {
+        Dual::constant(F::one())
+    }
+}
+
+impl<F: TensorTrait<F>> Num for Dual<F> {
+    type FromStrRadixErr = F::FromStrRadixErr;
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        F::from_str_radix(str, radix).map(Dual::constant)
+    }
+}
+
+impl<F: TensorTrait<F>> ToPrimitive for Dual<F> {
+    fn to_i64(&self) -> Option<i64> {
+        self.real.to_i64()
+    }
+    fn to_u64(&self) -> Option<u64> {
+        self.real.to_u64()
+    }
+    fn to_f32(&self) -> Option<f32> {
+        self.real.to_f32()
+    }
+    fn to_f64(&self) -> Option<f64> {
+        self.real.to_f64()
+    }
+}
+
+impl<F: TensorTrait<F>> FromPrimitive for Dual<F> {
+    fn from_i64(n: i64) -> Option<Self> {
+        F::from_i64(n).map(Dual::constant)
+    }
+    fn from_u64(n: u64) -> Option<Self> {
+        F::from_u64(n).map(Dual::constant)
+    }
+    fn from_f32(n: f32) -> Option<Self> {
+        F::from_f32(n).map(Dual::constant)
+    }
+    fn from_f64(n: f64) -> Option<Self> {
+        F::from_f64(n).map(Dual::constant)
+    }
+}
+
+impl<F: TensorTrait<F>> NumCast for Dual<F> {
+    fn from<N: ToPrimitive>(n: N) -> Option<Self> {
+        <F as NumCast>::from(n).map(Dual::constant)
+    }
+}
+
+// every float function carries its derivative along
+
+impl<F: TensorTrait<F>> Float for Dual<F> {
+    fn nan() -> Self {
+        Dual::constant(F::nan())
+    }
+    fn infinity() -> Self {
+        Dual::constant(F::infinity())
+    }
+    fn neg_infinity() -> Self {
+        Dual::constant(F::neg_infinity())
+    }
+    fn neg_zero() -> Self {
+        Dual::constant(F::neg_zero())
+    }
+    fn min_value() -> Self {
+        Dual::constant(F::min_value())
+    }
+    fn min_positive_value() -> Self {
+        Dual::constant(F::min_positive_value())
+    }
+    fn epsilon() -> Self {
+        Dual::constant(F::epsilon())
+    }
+    fn max_value() -> Self {
+        Dual::constant(F::max_value())
+    }
+    fn is_nan(self) -> bool {
+        self.real.is_nan()
+    }
+    fn is_infinite(self) -> bool {
+        self.real.is_infinite()
+    }
+    fn is_finite(self) -> bool {
+        self.real.is_finite()
+    }
+    fn is_normal(self) -> bool {
+        self.real.is_normal()
+    }
+    fn classify(self) -> FpCategory {
+        self.real.classify()
+    }
+    fn floor(self) -> Self {
+        Dual::constant(self.real.floor())
+    }
+    fn ceil(self) -> Self {
+        Dual::constant(self.real.ceil())
+    }
+    fn round(self) -> Self {
+        Dual::constant(self.real.round())
+    }
+    fn trunc(self) -> Self {
+        Dual::constant(self.real.trunc())
+    }
+    fn fract(self) -> Self {
+        Dual::new(self.real.fract(), self.dual)
+    }
+    fn abs(self) -> Self {
+        self.chain(self.real.abs(), self.real.signum())
+    }
+    fn signum(self) -> Self {
+        Dual::constant(self.real.signum())
+    }
+    fn is_sign_positive(self) -> bool {
+        self.real.is_sign_positive()
+    }
+    fn is_sign_negative(self) -> bool {
+        self.real.is_sign_negative()
+    }
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+    fn recip(self) -> Self {
+        let recip = self.real.recip();
+        self.chain(recip, F::zero() - recip * recip)
+    }
+    fn powi(self, n: i32) -> Self {
+        // x^0 is constant; the general rule would multiply 0 by 0^-1 = inf at x = 0
+        if n == 0 {
+            return Dual::constant(F::one());
+        }
+        let n_typed = F::from_i32(n).unwrap();
+        self.chain(self.real.powi(n), n_typed * self.real.powi(n - 1))
+    }
+    fn powf(self, n: Self) -> Self {
+        let value = self.real.powf(n.real);
+        let mut dual = F::zero();
+        if !self.dual.is_zero() {
+            dual = dual + n.real * self.real.powf(n.real - F::one()) * self.dual;
+        }
+        // only touch ln(base) when the exponent actually varies, so negative bases stay finite
+        if !n.dual.is_zero() {
+            dual = dual + value * self.real.ln() * n.dual;
+        }
+        Dual::new(value, dual)
+    }
+    fn sqrt(self) -> Self {
+        let sqrt = self.real.sqrt();
+        self.chain(sqrt, F::one() / (sqrt + sqrt))
+    }
+    fn exp(self) -> Self {
+        let exp = self.real.exp();
+        self.chain(exp, exp)
+    }
+    fn exp2(self) -> Self {
+        let exp2 = self.real.exp2();
+        self.chain(exp2, exp2 * F::from_f64(core::f64::consts::LN_2).unwrap())
+    }
+    fn ln(self) -> Self {
+        self.chain(self.real.ln(), self.real.recip())
+    }
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+    fn log2(self) -> Self {
+        let ln_two = F::from_f64(core::f64::consts::LN_2).unwrap();
+        self.chain(self.real.log2(), (self.real * ln_two).recip())
+    }
+    fn log10(self) -> Self {
+        let ln_ten = F::from_f64(core::f64::consts::LN_10).unwrap();
+        self.chain(self.real.log10(), (self.real * ln_ten).recip())
+    }
+    fn max(self, other: Self) -> Self {
+        if other.real > self.real { other } else { self }
+    }
+    fn min(self, other: Self) -> Self {
+        if other.real < self.real { other } else { self }
+    }
+    fn abs_sub(self, other: Self) -> Self {
+        (self - other).max(Dual::zero())
+    }
+    fn cbrt(self) -> Self {
+        let cbrt = self.real.cbrt();
+        let three = F::from_f32(3.0).unwrap();
+        self.chain(cbrt, (three * cbrt * cbrt).recip())
+    }
+    fn hypot(self, other: Self) -> Self {
+        (self * self + other * other).sqrt()
+    }
+    fn sin(self) -> Self {
+        self.chain(self.real.sin(), self.real.cos())
+    }
+    fn cos(self) -> Self {
+        self.chain(self.real.cos(), F::zero() - self.real.sin())
+    }
+    fn tan(self) -> Self {
+        let cos = self.real.cos();
+        self.chain(self.real.tan(), (cos * cos).recip())
+    }
+    fn asin(self) -> Self {
+        self.chain(self.real.asin(), (F::one() - self.real * self.real).sqrt().recip())
+    }
+    fn acos(self) -> Self {
+        let derivative = F::zero() - (F::one() - self.real * self.real).sqrt().recip();
+        self.chain(self.real.acos(), derivative)
+    }
+    fn atan(self) -> Self {
+        self.chain(self.real.atan(), (F::one() + self.real * self.real).recip())
+    }
+    fn atan2(self, other: Self) -> Self {
+        // d atan2(y, x) = (x dy - y dx) / (x^2 + y^2)
+        let denominator = self.real * self.real + other.real * other.real;
+        Dual::new(
+            self.real.atan2(other.real),
+            (other.real * self.dual - self.real * other.dual) / denominator
+        )
+    }
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+    fn exp_m1(self) -> Self {
+        self.chain(self.real.exp_m1(), self.real.exp())
+    }
+    fn ln_1p(self) -> Self {
+        self.chain(self.real.ln_1p(), (F::one() + self.real).recip())
+    }
+    fn sinh(self) -> Self {
+        self.chain(self.real.sinh(), self.real.cosh())
+    }
+    fn cosh(self) -> Self {
+        self.chain(self.real.cosh(), self.real.sinh())
+    }
+    fn tanh(self) -> Self {
+        let tanh = self.real.tanh();
+        self.chain(tanh, F::one() - tanh * tanh)
+    }
+    fn asinh(self) -> Self {
+        self.chain(self.real.asinh(), (self.real * self.real + F::one()).sqrt().recip())
+    }
+    fn acosh(self) -> Self {
+        self.chain(self.real.acosh(), (self.real * self.real - F::one()).sqrt().recip())
+    }
+    fn atanh(self) -> Self {
+        self.chain(self.real.atanh(), (F::one() - self.real * self.real).recip())
+    }
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.real.integer_decode()
+    }
+}
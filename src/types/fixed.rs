@@ -0,0 +1,432 @@
+use core::fmt;
+use core::num::FpCategory;
+use core::ops::{ Add, Div, Mul, Neg, Rem, Sub };
+
+use num::{ Float, FromPrimitive, Num, NumCast, One, ToPrimitive, Zero };
+use num::traits::ParseFloatError;
+
+///
+/// A Q-format fixed-point number: a 32-bit signed integer with `FRAC` fractional bits, so the
+/// value is `bits / 2^FRAC`. `Q16_16` covers ±32768 in steps of about 1.5e-5, `Q8_24` covers ±128
+/// in steps of about 6e-8.
+///
+/// Fixed-point tensors are meant for inference on targets without a floating point unit: load
+/// trained weights with `from_f32`, run the forward pass, and read the result back with `to_f32`.
+/// Arithmetic is integer arithmetic that rounds to the nearest step and saturates at the ends of
+/// the range instead of overflowing. There is no NaN or infinity: `nan()` is zero, infinities are
+/// the largest and smallest values, and dividing by zero saturates. Transcendental functions such
+/// as `exp` and `tanh` are evaluated in f64 and rounded back, so activations and `softmax` work,
+/// but gradients lose too much precision to train with.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, nn::{ linear::Linear, activation::relu } };
+/// use nanograd::types::fixed::Q16_16;
+///
+/// fn layer<T: nanograd::TensorTrait<T>>() -> (Linear<T>, Tensor<T>) {
+///     let tensor = |data: &[f32], dim| {
+///         Tensor::from_vec(data.iter().map(|value| T::from_f32(*value).unwrap()).collect(), dim, None, None)
+///     };
+///     let weight = tensor(&[0.5, -1.25, 0.75, 2.0, -0.5, 0.25], (3, 2));
+///     let bias = tensor(&[0.1, -0.2], (1, 2));
+///     let input = tensor(&[1.0, 2.0, -3.0, 0.5, 0.25, 4.0], (2, 3));
+///     (Linear::from_weights(weight, Some(bias)), input)
+/// }
+///
+/// let (fixed, input) = layer::<Q16_16>();
+/// let (float, expected) = layer::<f32>();
+/// let output = relu(fixed.forward(input));
+/// let expected = relu(float.forward(expected));
+///
+/// for (actual, expected) in output.data().iter().zip(expected.data().iter()) {
+///     assert!((actual.to_f32() - expected).abs() < 1e-4);
+/// }
+///
+/// // arithmetic saturates instead of overflowing
+/// assert_eq!(Q16_16::from_f32(30000.0) + Q16_16::from_f32(30000.0), Q16_16::MAX);
+/// assert_eq!(Q16_16::from_f32(1.5) * Q16_16::from_f32(-2.0), Q16_16::from_f32(-3.0));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct Fixed<const FRAC: u32>(i32);
+
+/// 16 integer and 16 fractional bits.
+pub type Q16_16 = Fixed<16>;
+
+/// 8 integer and 24 fractional bits, for values that stay small, e.g. normalized activations.
+pub type Q8_24 = Fixed<24>;
+
+impl<const FRAC: u32> Fixed<FRAC> {
+    /// The largest value, just below `2^(31 - FRAC)`.
+    pub const MAX: Self = Fixed(i32::MAX);
+
+    /// The smallest value, `-2^(31 - FRAC)`.
+    pub const MIN: Self = Fixed(i32::MIN);
+
+    // at least one bit has to be left for the sign, so `FRAC >= 31` fails to compile
+    const ONE: i32 = {
+        assert!(FRAC < 31, "Fixed needs FRAC < 31 to leave a bit for the sign");
+        1 << FRAC
+    };
+
+    ///
+    /// Create a number from its raw bits, i.e. the value times `2^FRAC`.
+    ///
+    /// `FRAC` has to be below 31, which is checked when the type is used:
+    ///
+    /// ```compile_fail
+    /// use nanograd::Fixed;
+    ///
+    /// let too_precise = Fixed::<31>::from_bits(1);
+    /// ```
+    pub const fn from_bits(bits: i32) -> Self {
+        let _ = Self::ONE;
+        Fixed(bits)
+    }
+
+    /// Get the raw bits, i.e. the value times `2^FRAC`.
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    /// Convert from f32, rounding to the nearest step and saturating outside the range. NaN is zero.
+    pub fn from_f32(value: f32) -> Self {
+        Self::saturating_from_f64(value as f64)
+    }
+
+    /// Convert to the nearest f32.
+    pub fn to_f32(self) -> f32 {
+        self.to_f64_value() as f32
+    }
+
+    fn to_f64_value(self) -> f64 {
+        (self.0 as f64) / (Self::ONE as f64)
+    }
+
+    fn saturating_from_f64(value: f64) -> Self {
+        if value.is_nan() {
+            return Fixed(0);
+        }
+        let scaled = Float::round(value * (Self::ONE as f64));
+        // `as` saturates at the ends of the range
+        Fixed(scaled as i32)
+    }
+
+    fn checked_from_f64(value: f64) -> Option<Self> {
+        let scaled = Float::round(value * (Self::ONE as f64));
+        if value.is_nan() || scaled < (i32::MIN as f64) || scaled > (i32::MAX as f64) {
+            return None;
+        }
+        Some(Fixed(scaled as i32))
+    }
+
+    fn saturate(value: i64) -> Self {
+        Fixed(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+
+    /// Apply a function in f64 and round the result back.
+    fn via_f64(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::saturating_from_f64(f(self.to_f64_value()))
+    }
+}
+
+impl<const FRAC: u32> fmt::Display for Fixed<FRAC> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // pass the formatter on so a precision applies
+        fmt::Display::fmt(&self.to_f64_value(), f)
+    }
+}
+
+// arithmetic
+
+impl<const FRAC: u32> Add<Fixed<FRAC>> for Fixed<FRAC> {
+    type Output = Fixed<FRAC>;
+    fn add(self, other: Fixed<FRAC>) -> Fixed<FRAC> {
+        Fixed(self.0.saturating_add(other.0))
+    }
+}
+
+impl<const FRAC: u32> Sub<Fixed<FRAC>> for Fixed<FRAC> {
+    type Output = Fixed<FRAC>;
+    fn sub(self, other: Fixed<FRAC>) -> Fixed<FRAC> {
+        Fixed(self.0.saturating_sub(other.0))
+    }
+}
+
+impl<const FRAC: u32> Mul<Fixed<FRAC>> for Fixed<FRAC> {
+    type Output = Fixed<FRAC>;
+    fn mul(self, other: Fixed<FRAC>) -> Fixed<FRAC> {
+        // round half up before dropping the extra fractional bits
+        let product = (self.0 as i64) * (other.0 as i64) + ((1_i64 << FRAC) >> 1);
+        Fixed::saturate(product >> FRAC)
+    }
+}
+
+impl<const FRAC: u32> Div<Fixed<FRAC>> for Fixed<FRAC> {
+    type Output = Fixed<FRAC>;
+    fn div(self, other: Fixed<FRAC>) -> Fixed<FRAC> {
+        if other.0 == 0 {
+            return match self.0 {
+                0 => Fixed(0),
+                n if n > 0 => Fixed::MAX,
+                _ => Fixed::MIN,
+            };
+        }
+        let numerator = (self.0 as i64) << FRAC;
+        let divisor = other.0 as i64;
+        // round to nearest, away from zero on ties
+        let half = divisor.abs() / 2;
+        let rounded = if (numerator < 0) == (divisor < 0) { numerator + half } else { numerator - half };
+        Fixed::saturate(rounded / divisor)
+    }
+}
+
+impl<const FRAC: u32> Rem<Fixed<FRAC>> for Fixed<FRAC> {
+    type Output = Fixed<FRAC>;
+    fn rem(self, other: Fixed<FRAC>) -> Fixed<FRAC> {
+        Fixed(self.0.checked_rem(other.0).unwrap_or(0))
+    }
+}
+
+impl<const FRAC: u32> Neg for Fixed<FRAC> {
+    type Output = Fixed<FRAC>;
+    fn neg(self) -> Fixed<FRAC> {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+// num traits
+
+impl<const FRAC: u32> Zero for Fixed<FRAC> {
+    fn zero() -> Self {
+        Fixed(0)
+    }
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl<const FRAC: u32> One for Fixed<FRAC> {
+    fn one() -> Self {
+        Fixed(Self::ONE)
+    }
+}
+
+impl<const FRAC: u32> Num for Fixed<FRAC> {
+    type FromStrRadixErr = ParseFloatError;
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        <f64 as Num>::from_str_radix(str, radix).map(Self::saturating_from_f64)
+    }
+}
+
+impl<const FRAC: u32> ToPrimitive for Fixed<FRAC> {
+    fn to_i64(&self) -> Option<i64> {
+        // integer division truncates towards zero, like `as`
+        Some((self.0 / Self::ONE) as i64)
+    }
+    fn to_u64(&self) -> Option<u64> {
+        if self.0 < 0 { None } else { Some((self.0 / Self::ONE) as u64) }
+    }
+    fn to_f32(&self) -> Option<f32> {
+        Some(Fixed::to_f32(*self))
+    }
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.to_f64_value())
+    }
+}
+
+impl<const FRAC: u32> FromPrimitive for Fixed<FRAC> {
+    fn from_i64(n: i64) -> Option<Self> {
+        Self::checked_from_f64(n as f64)
+    }
+    fn from_u64(n: u64) -> Option<Self> {
+        Self::checked_from_f64(n as f64)
+    }
+    fn from_f32(n: f32) -> Option<Self> {
+        Self::checked_from_f64(n as f64)
+    }
+    fn from_f64(n: f64) -> Option<Self> {
+        Self::checked_from_f64(n)
+    }
+}
+
+impl<const FRAC: u32> NumCast for Fixed<FRAC> {
+    fn from<N: ToPrimitive>(n: N) -> Option<Self> {
+        n.to_f64().and_then(Self::checked_from_f64)
+    }
+}
+
+impl<const FRAC: u32> Float for Fixed<FRAC> {
+    fn nan() -> Self {
+        Fixed(0)
+    }
+    fn infinity() -> Self {
+        Fixed::MAX
+    }
+    fn neg_infinity() -> Self {
+        Fixed::MIN
+    }
+    fn neg_zero() -> Self {
+        Fixed(0)
+    }
+    fn min_value() -> Self {
+        Fixed::MIN
+    }
+    fn min_positive_value() -> Self {
+        Fixed(1)
+    }
+    fn epsilon() -> Self {
+        Fixed(1)
+    }
+    fn max_value() -> Self {
+        Fixed::MAX
+    }
+    fn is_nan(self) -> bool {
+        false
+    }
+    fn is_infinite(self) -> bool {
+        false
+    }
+    fn is_finite(self) -> bool {
+        true
+    }
+    fn is_normal(self) -> bool {
+        self.0 != 0
+    }
+    fn classify(self) -> FpCategory {
+        if self.0 == 0 { FpCategory::Zero } else { FpCategory::Normal }
+    }
+    fn floor(self) -> Self {
+        // clearing the fractional bits of a two's complement number rounds towards -∞
+        Fixed(self.0 & !(Self::ONE - 1))
+    }
+    fn ceil(self) -> Self {
+        Fixed(self.0.saturating_add(Self::ONE - 1)).floor()
+    }
+    fn round(self) -> Self {
+        Fixed(self.0.saturating_add(Self::ONE >> 1)).floor()
+    }
+    fn trunc(self) -> Self {
+        if self.0 < 0 { self.ceil() } else { self.floor() }
+    }
+    fn fract(self) -> Self {
+        self - self.trunc()
+    }
+    fn abs(self) -> Self {
+        Fixed(self.0.saturating_abs())
+    }
+    fn signum(self) -> Self {
+        if self.0 < 0 { -Self::one() } else { Self::one() }
+    }
+    fn is_sign_positive(self) -> bool {
+        self.0 >= 0
+    }
+    fn is_sign_negative(self) -> bool {
+        self.0 < 0
+    }
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+    fn recip(self) -> Self {
+        Self::one() / self
+    }
+    fn powi(self, n: i32) -> Self {
+        self.via_f64(|x| Float::powi(x, n))
+    }
+    fn powf(self, n: Self) -> Self {
+        let n = n.to_f64_value();
+        self.via_f64(|x| Float::powf(x, n))
+    }
+    fn sqrt(self) -> Self {
+        self.via_f64(Float::sqrt)
+    }
+    fn exp(self) -> Self {
+        self.via_f64(Float::exp)
+    }
+    fn exp2(self) -> Self {
+        self.via_f64(Float::exp2)
+    }
+    fn ln(self) -> Self {
+        self.via_f64(Float::ln)
+    }
+    fn log(self, base: Self) -> Self {
+        let base = base.to_f64_value();
+        self.via_f64(|x| Float::log(x, base))
+    }
+    fn log2(self) -> Self {
+        self.via_f64(Float::log2)
+    }
+    fn log10(self) -> Self {
+        self.via_f64(Float::log10)
+    }
+    fn max(self, other: Self) -> Self {
+        Ord::max(self, other)
+    }
+    fn min(self, other: Self) -> Self {
+        Ord::min(self, other)
+    }
+    fn abs_sub(self, other: Self) -> Self {
+        Ord::max(self - other, Self::zero())
+    }
+    fn cbrt(self) -> Self {
+        self.via_f64(Float::cbrt)
+    }
+    fn hypot(self, other: Self) -> Self {
+        let other = other.to_f64_value();
+        self.via_f64(|x| Float::hypot(x, other))
+    }
+    fn sin(self) -> Self {
+        self.via_f64(Float::sin)
+    }
+    fn cos(self) -> Self {
+        self.via_f64(Float::cos)
+    }
+    fn tan(self) -> Self {
+        self.via_f64(Float::tan)
+    }
+    fn asin(self) -> Self {
+        self.via_f64(Float::asin)
+    }
+    fn acos(self) -> Self {
+        self.via_f64(Float::acos)
+    }
+    fn atan(self) -> Self {
+        self.via_f64(Float::atan)
+    }
+    fn atan2(self, other: Self) -> Self {
+        let other = other.to_f64_value();
+        self.via_f64(|x| Float::atan2(x, other))
+    }
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+    fn exp_m1(self) -> Self {
+        self.via_f64(Float::exp_m1)
+    }
+    fn ln_1p(self) -> Self {
+        self.via_f64(Float::ln_1p)
+    }
+    fn sinh(self) -> Self {
+        self.via_f64(Float::sinh)
+    }
+    fn cosh(self) -> Self {
+        self.via_f64(Float::cosh)
+    }
+    fn tanh(self) -> Self {
+        self.via_f64(Float::tanh)
+    }
+    fn asinh(self) -> Self {
+        self.via_f64(Float::asinh)
+    }
+    fn acosh(self) -> Self {
+        self.via_f64(Float::acosh)
+    }
+    fn atanh(self) -> Self {
+        self.via_f64(Float::atanh)
+    }
+    fn integer_decode(self) -> (u64, i16, i8) {
+        let sign = if self.0 < 0 { -1 } else { 1 };
+        (self.0.unsigned_abs() as u64, -(FRAC as i16), sign)
+    }
+}
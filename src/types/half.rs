@@ -0,0 +1,115 @@
+use core::any::TypeId;
+use alloc::vec::Vec;
+
+pub use ::half::{ f16, bf16 };
+
+use crate::{ DataArray, Numeric };
+
+///
+/// Whether `T` is `f16` or `bf16`. Half precision tensors are stored in half, but the CPU has no
+/// native half arithmetic, so their kernels widen the inputs to f32, compute in f32 and round the
+/// result once.
+///
+/// Every op in `nn` works on half precision tensors: the activations, transformations, `Linear`
+/// and the categorical cross entropy loss (the only loss `nn` has) and their gradients are checked
+/// against f32 below, and the `indexing` ops, which do no arithmetic, match f32 exactly. Compared
+/// with the same op in f32, results agree to within a relative error of `2e-3` for `f16` and
+/// `1.6e-2` for `bf16`: a few roundings to the 11 and 8 significant bits of the two formats.
+/// `f16` overflows above 65504; `bf16` has the range of f32.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Tensor, TensorTrait, f16, bf16, autograd::grad };
+/// use nanograd::nn::{ activation::{ sigmoid, relu, tanh, softmax, log_softmax }, loss::categorical_cross_entropy };
+/// use nanograd::nn::transformation::{ exp2, log2, log, max, sum };
+/// use nanograd::nn::{ linear::Linear, indexing::{ compare, one_hot, gather_rows } };
+/// use nanograd::types::{ half::{ widen, narrow }, ops::CmpOps };
+///
+/// fn check<T: TensorTrait<T>>(tolerance: f32) {
+///     let x: Vec<f32> = vec![0.5, -1.25, 2.0, 3.5];
+///     let labels: Vec<f32> = vec![0.0, 1.0, 1.0, 0.0];
+///     let w: Vec<f32> = vec![0.25, -0.5, 1.5, 0.75];
+///
+///     fn run<U: TensorTrait<U>>(x: &[f32], labels: &[f32], w: &[f32]) -> Vec<Vec<f32>> {
+///         let tensor = || Tensor::new(narrow::<U>(x), (2, 2), None, Some(true));
+///         let positive = || Tensor::new(narrow::<U>(&x.iter().map(|v| v.abs()).collect::<Vec<f32>>()), (2, 2), None, None);
+///         let weight = Tensor::new(narrow::<U>(w), (2, 2), None, Some(true));
+///         let targets = Tensor::new(narrow::<U>(labels), (2, 2), None, None);
+///
+///         let loss = |v: Vec<Tensor<U>>| categorical_cross_entropy(v[0].clone() * v[1].clone(), targets.clone());
+///         let grads = grad(loss, &[tensor(), weight.clone()]);
+///
+///         let bias = Tensor::new(narrow::<U>(&w[..2]), (1, 2), None, Some(true));
+///         let layer = |v: Vec<Tensor<U>>| sum(Linear::from_weights(v[0].clone(), Some(v[1].clone())).forward(tensor()));
+///         let layer_grads = grad(layer, &[weight.clone(), bias.clone()]);
+///
+///         let classes: Tensor<i64> = Tensor::from_vec(vec![1, 0, 1], (3, 1), None, None);
+///         let mask = compare(&tensor(), &positive(), CmpOps::LT);
+///
+///         let outputs = vec![
+///             sigmoid(tensor()), relu(tensor()), tanh(tensor()), softmax(tensor()), log_softmax(tensor()),
+///             exp2(tensor()), log2(positive()), log(positive()), max(tensor(), U::zero()), sum(tensor()),
+///             loss(vec![tensor(), weight.clone()]), grads[0].clone(), grads[1].clone(),
+///             Linear::from_weights(weight, Some(bias)).forward(tensor()), layer_grads[0].clone(), layer_grads[1].clone(),
+///             one_hot::<i64, U>(&classes, 2), gather_rows(&tensor(), &classes),
+///         ];
+///         let mut results: Vec<Vec<f32>> = outputs.iter().map(|output| widen(output.data())).collect();
+///         results.push(mask.data().iter().map(|&less| if less { 1.0 } else { 0.0 }).collect());
+///         results
+///     }
+///
+///     let expected = run::<f32>(&x, &labels, &w);
+///     let actual = run::<T>(&x, &labels, &w);
+///     for (expected, actual) in expected.iter().zip(actual.iter()) {
+///         for (e, a) in expected.iter().zip(actual.iter()) {
+///             assert!((e - a).abs() <= tolerance * e.abs().max(1.0), "{} vs {}", e, a);
+///         }
+///     }
+/// }
+///
+/// check::<f16>(2e-3);
+/// check::<bf16>(1.6e-2);
+///
+/// // half precision values convert losslessly to f32 and f64
+/// let value = f16::from_f32(0.1);
+/// assert_eq!(f16::from_f32(f32::from(value)), value);
+/// assert_eq!(f16::from_f64(f64::from(value)), value);
+/// ```
+pub fn is_half<T: 'static>() -> bool {
+    TypeId::of::<T>() == TypeId::of::<f16>() || TypeId::of::<T>() == TypeId::of::<bf16>()
+}
+
+///
+/// Convert elements to f32. Exact for `f16` and `bf16`, whose values are all representable in f32.
+///
+/// # Panics
+///
+/// * If an element has no f32 representation.
+pub fn widen<T: Numeric>(data: &[T]) -> Vec<f32> {
+    data.iter()
+        .map(|value| {
+            match value.to_f32() {
+                Some(value) => value,
+                None => panic!("Error converting {} to f32", value),
+            }
+        })
+        .collect()
+}
+
+///
+/// Convert f32 values to `T`, rounding to the nearest representable value.
+///
+/// # Panics
+///
+/// * If a value has no representation in `T`.
+pub fn narrow<T: Numeric>(data: &[f32]) -> DataArray<T> {
+    data.iter()
+        .map(|value| {
+            match T::from_f32(*value) {
+                Some(value) => value,
+                None => panic!("Error converting {} from f32", value),
+            }
+        })
+        .collect()
+}
@@ -1,31 +1,104 @@
-use crate::{ TensorTrait, Device, default_device };
+use crate::{ Element, Device, default_device };
+use crate::backend::{ find_backend, pool, Storage };
+use crate::error::Result;
+#[cfg(feature = "serde")]
+use crate::NanogradError;
 use core::panic;
-use std::hash::Hash;
+use core::hash::Hash;
+use alloc::{ vec::Vec, boxed::Box };
+#[cfg(any(feature = "serde", feature = "safetensors"))]
+use alloc::borrow::Cow;
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Serializer, Deserialize, Deserializer, ser::SerializeStruct, de::Error };
 
-#[derive(Clone, PartialEq, Eq)]
-pub struct LazyBuffer<T: TensorTrait<T>> {
-    data: DataArray<T>,
+/// Buffers are tracked by the pool of their device while alive and returned to it when dropped.
+#[derive(PartialEq, Eq)]
+pub struct LazyBuffer<T: Element> {
+    storage: Storage<T>,
     dimensions: Dimensions,
     device: Device,
     realized: bool,
     // prev
 }
 
-impl<T> LazyBuffer<T> where T: TensorTrait<T> {
+impl<T> LazyBuffer<T> where T: Element {
+    /// Create a buffer from host data, uploading it to `device` if it is not the CPU.
     pub fn new(data: DataArray<T>, dimensions: Dimensions, device: Option<Device>) -> Self {
+        Self::try_new(data, dimensions, device).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a buffer from host data, or fail if no backend is registered for `device`.
+    pub fn try_new(data: DataArray<T>, dimensions: Dimensions, device: Option<Device>) -> Result<Self> {
         let device = match device {
             Some(device) => device,
             None => default_device(),
         };
-        Self { data, dimensions, device, realized: true }
+        let storage = match device {
+            Device::CPU => Storage::Host(data),
+            _ => {
+                let storage = find_backend::<T>(&device)?.upload(&data);
+                pool::recycle(&Device::CPU, Storage::Host(data));
+                storage
+            }
+        };
+        Ok(Self::from_storage(storage, dimensions, device))
+    }
+
+    /// Create a buffer from storage that already lives on `device`.
+    pub fn from_storage(storage: Storage<T>, dimensions: Dimensions, device: Device) -> Self {
+        pool::track::<T>(&device, storage.len());
+        Self { storage, dimensions, device, realized: true }
     }
 
     pub fn dim(&self) -> Dimensions {
         self.dimensions
     }
+
+    ///
+    /// Get the data of the buffer.
+    ///
+    /// # Panics
+    ///
+    /// * If the buffer lives on a device other than the CPU. Move it with `Tensor::to` first.
     pub fn data(&self) -> &DataArray<T> {
-        &self.data
+        match &self.storage {
+            Storage::Host(data) => data,
+            Storage::Device(_) => {
+                panic!("Buffer lives on {:?}. Move it to Device::CPU to read its data.", self.device)
+            }
+        }
+    }
+
+    ///
+    /// Take the data out of the buffer without copying it, downloading it first if the buffer
+    /// lives on a device other than the CPU.
+    pub fn into_data(mut self) -> Result<DataArray<T>> {
+        let storage = core::mem::replace(&mut self.storage, Storage::Host(Vec::new().into_boxed_slice()));
+        pool::untrack::<T>(&self.device, storage.len());
+        match storage {
+            Storage::Host(data) => Ok(data),
+            storage => {
+                let data = find_backend::<T>(&self.device)?.download(&storage);
+                pool::recycle(&self.device, storage);
+                Ok(data)
+            }
+        }
+    }
+
+    /// Get the data of the buffer, borrowed on the CPU and downloaded from any other device.
+    #[cfg(any(feature = "serde", feature = "safetensors"))]
+    pub(crate) fn host_data(&self) -> Result<Cow<'_, [T]>> {
+        match &self.storage {
+            Storage::Host(data) => Ok(Cow::Borrowed(data)),
+            storage => Ok(Cow::Owned(find_backend::<T>(&self.device)?.download(storage).into_vec())),
+        }
     }
+
+    /// Get the backend-specific storage of the buffer.
+    pub fn storage(&self) -> &Storage<T> {
+        &self.storage
+    }
+
     pub fn device(&self) -> &Device {
         &self.device
     }
@@ -35,7 +108,44 @@ impl<T> LazyBuffer<T> where T: TensorTrait<T> {
     }
 
     pub fn set_data(&mut self, data: DataArray<T>) {
-        self.data = data;
+        self.try_set_data(data).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Replace the data of the buffer, or fail if no backend is registered for its device.
+    pub fn try_set_data(&mut self, data: DataArray<T>) -> Result<()> {
+        let storage = match self.device {
+            Device::CPU => Storage::Host(data),
+            _ => find_backend::<T>(&self.device)?.upload(&data),
+        };
+        pool::track::<T>(&self.device, storage.len());
+        self.release(storage);
+        Ok(())
+    }
+
+    /// Swap in new storage and return the old one to the pool.
+    fn release(&mut self, storage: Storage<T>) {
+        let old = core::mem::replace(&mut self.storage, storage);
+        pool::untrack::<T>(&self.device, old.len());
+        pool::recycle(&self.device, old);
+    }
+
+    ///
+    /// Copy the buffer to another device through the host.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to copy to.
+    pub fn to(&self, device: &Device) -> LazyBuffer<T> {
+        self.try_to(device).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Copy the buffer to another device, or fail if either device has no backend.
+    pub fn try_to(&self, device: &Device) -> Result<LazyBuffer<T>> {
+        if *device == self.device {
+            return Ok(self.clone());
+        }
+        let host: DataArray<T> = find_backend::<T>(&self.device)?.download(&self.storage);
+        LazyBuffer::try_new(host, self.dimensions, Some(device.clone()))
     }
 
     pub fn realize(&self) -> &LazyBuffer<T> {
@@ -47,17 +157,89 @@ impl<T> LazyBuffer<T> where T: TensorTrait<T> {
     pub fn set_dim(&mut self, dim: Dimensions) {
         self.dimensions = dim;
     }
+
+    /// Release the data while keeping the dimensions, e.g. once a backward pass no longer needs it.
+    pub fn free(&mut self) {
+        self.release(Storage::Host(Vec::new().into_boxed_slice()));
+        self.realized = false;
+    }
+}
+
+impl<T> Clone for LazyBuffer<T> where T: Element {
+    fn clone(&self) -> Self {
+        let storage = match &self.storage {
+            Storage::Host(data) => {
+                Storage::Host(pool::copy_host(data))
+            }
+            Storage::Device(_) => self.storage.clone(),
+        };
+        let mut buffer = Self::from_storage(storage, self.dimensions, self.device.clone());
+        buffer.realized = self.realized;
+        buffer
+    }
+}
+
+impl<T> Drop for LazyBuffer<T> where T: Element {
+    fn drop(&mut self) {
+        self.release(Storage::Host(Vec::new().into_boxed_slice()));
+    }
 }
 
 pub type DataArray<T> = Box<[T]>;
 
 pub type Dimensions = (usize, usize);
 
-impl<T> Hash for LazyBuffer<T> where T: TensorTrait<T> {
-    // TODO: IMPLEMENT AND REMOVE UNUSED VARIABLES WARNING SKIRT
-    #[allow(unused_variables)]
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        println!("TODO: implement hash for LazyBuffer<T> where T: TensorTrait<T>");
-        panic!("Not implemented");
+/// Hashes the structure of the buffer, its dimensions and device, not its data.
+/// Two buffers with the same hash can be used interchangeably by a compiled kernel.
+impl<T> Hash for LazyBuffer<T> where T: Element {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.dimensions.hash(state);
+        self.device.hash(state);
+    }
+}
+
+///
+/// Serializes the data and dimensions of the buffer. Data on a device other than the CPU is
+/// downloaded first, and buffers are always deserialized onto the default device.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::LazyBuffer;
+///
+/// let buffer: LazyBuffer<f32> = LazyBuffer::new(vec![1.0, 2.0].into_boxed_slice(), (1, 2), None);
+/// let json = serde_json::to_string(&buffer).unwrap();
+/// assert_eq!(json, r#"{"data":[1.0,2.0],"shape":[1,2]}"#);
+/// assert!(buffer == serde_json::from_str::<LazyBuffer<f32>>(&json).unwrap());
+///
+/// assert!(serde_json::from_str::<LazyBuffer<f32>>(r#"{"data":[1.0],"shape":[1,2]}"#).is_err());
+/// ```
+#[cfg(feature = "serde")]
+impl<T> Serialize for LazyBuffer<T> where T: Element + Serialize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        let data = self.host_data().map_err(serde::ser::Error::custom)?;
+        let mut state = serializer.serialize_struct("LazyBuffer", 2)?;
+        state.serialize_field("data", data.as_ref())?;
+        state.serialize_field("shape", &self.dimensions)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "LazyBuffer")]
+struct BufferParts<T> {
+    data: Vec<T>,
+    shape: Dimensions,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for LazyBuffer<T> where T: Element + Deserialize<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        let BufferParts { data, shape } = BufferParts::<T>::deserialize(deserializer)?;
+        if data.len() != shape.0 * shape.1 {
+            return Err(D::Error::custom(NanogradError::DataLength { dim: shape, len: data.len() }));
+        }
+        LazyBuffer::try_new(data.into_boxed_slice(), shape, None).map_err(D::Error::custom)
     }
 }
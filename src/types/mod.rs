@@ -7,3 +7,13 @@ pub mod ops;
 pub mod lazy;
 
 pub mod data;
+
+pub mod dual;
+
+pub mod shape;
+
+pub mod half;
+
+pub mod dtype;
+
+pub mod fixed;
@@ -1,7 +1,7 @@
 
 
 // TODO: REMOVE SIGMOID AND SOFTMAX OPS... THEY SHOULD BE COMPOSITIONS OF OTHER OPS
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum UnaryOps {
     EXP2,
     Sigmoid,
@@ -11,27 +11,41 @@ pub enum UnaryOps {
     SUM,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum BinaryOps {
     ADD,
     SUB,
     MUL,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// Element-wise comparisons. They produce `bool` masks and are never differentiated.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum CmpOps {
+    EQ,
+    LT,
+    GT,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum ReduceOps {
     SUM,
     MAX,
 }
 
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MovementOps {
+    RESHAPE,
+    PERMUTE,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum TernaryOps {
     MULACC,
     WHERE,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum LoadOps {
     EMPTY,
     RAND,
@@ -39,9 +53,10 @@ pub enum LoadOps {
     FROM,
     CONTIGUOUS,
     CUSTOM,
+    CAST,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Ops {
     UnaryOps(UnaryOps),
     BinaryOps(BinaryOps),
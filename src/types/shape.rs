@@ -0,0 +1,179 @@
+use alloc::collections::BTreeMap;
+use core::fmt;
+use alloc::format;
+use alloc::string::{ String, ToString };
+
+use crate::{ Ops, Dimensions, NanogradError };
+use crate::error::Result;
+use crate::types::ops::BinaryOps;
+
+/// Sizes given to the variables of symbolic shapes, by name.
+pub type Bindings = BTreeMap<String, usize>;
+
+///
+/// The size of one axis, known up front or named and bound when a graph is run.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::types::shape::{ Dim, Bindings };
+///
+/// let batch = Dim::var("batch");
+/// let mut bindings = Bindings::new();
+/// bindings.insert("batch".to_string(), 32);
+///
+/// assert_eq!(batch.bind(&bindings), Some(32));
+/// assert_eq!(Dim::from(784).bind(&bindings), Some(784));
+/// assert_eq!(Dim::var("time").bind(&bindings), None);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dim {
+    Fixed(usize),
+    Var(String),
+}
+
+impl Dim {
+    /// A dimension named `name`, e.g. `Dim::var("batch")`.
+    pub fn var(name: &str) -> Self {
+        Dim::Var(name.to_string())
+    }
+
+    pub fn is_symbolic(&self) -> bool {
+        matches!(self, Dim::Var(_))
+    }
+
+    /// The size of the dimension, or `None` if it is a variable without a binding.
+    pub fn bind(&self, bindings: &Bindings) -> Option<usize> {
+        match self {
+            Dim::Fixed(size) => Some(*size),
+            Dim::Var(name) => bindings.get(name).copied(),
+        }
+    }
+
+    /// Check a concrete size against the dimension, binding it if it is a new variable.
+    pub fn unify(&self, size: usize, bindings: &mut Bindings) -> bool {
+        match self {
+            Dim::Fixed(fixed) => *fixed == size,
+            Dim::Var(name) => *bindings.entry(name.clone()).or_insert(size) == size,
+        }
+    }
+}
+
+impl From<usize> for Dim {
+    fn from(size: usize) -> Self {
+        Dim::Fixed(size)
+    }
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dim::Fixed(size) => write!(f, "{}", size),
+            Dim::Var(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+///
+/// The rows and columns of a tensor, either of which may be symbolic.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::types::shape::{ Dim, Shape, Bindings };
+///
+/// let shape = Shape(Dim::var("batch"), Dim::from(784));
+/// let mut bindings = Bindings::new();
+///
+/// assert!(shape.unify((64, 784), &mut bindings));
+/// assert!(!shape.unify((32, 784), &mut bindings));
+/// assert_eq!(shape.bind(&bindings), Some((64, 784)));
+/// assert_eq!(shape.to_string(), "batchx784");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Shape(pub Dim, pub Dim);
+
+impl Shape {
+    pub fn is_symbolic(&self) -> bool {
+        self.0.is_symbolic() || self.1.is_symbolic()
+    }
+
+    /// The concrete dimensions, or `None` if a variable has no binding.
+    pub fn bind(&self, bindings: &Bindings) -> Option<Dimensions> {
+        Some((self.0.bind(bindings)?, self.1.bind(bindings)?))
+    }
+
+    /// Check concrete dimensions against the shape, binding any new variables.
+    pub fn unify(&self, dim: Dimensions, bindings: &mut Bindings) -> bool {
+        self.0.unify(dim.0, bindings) && self.1.unify(dim.1, bindings)
+    }
+}
+
+impl From<Dimensions> for Shape {
+    fn from(dim: Dimensions) -> Self {
+        Shape(Dim::Fixed(dim.0), Dim::Fixed(dim.1))
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.0, self.1)
+    }
+}
+
+///
+/// Infer the shape an op produces from the shapes of its inputs, carrying variables through.
+///
+/// # Returns
+///
+/// The output shape, or `None` for custom ops, whose shape only their function knows.
+///
+/// # Errors
+///
+/// * `SymbolicShapeMismatch` if the input shapes do not fit the op.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::{ Ops, NanogradError, types::{ ops::BinaryOps, shape::{ infer_shape, Dim, Shape } } };
+///
+/// let x = Shape(Dim::var("batch"), Dim::from(784));
+/// let w = Shape(Dim::from(784), Dim::from(128));
+///
+/// let y = infer_shape(Ops::BinaryOps(BinaryOps::MUL), &[&x, &w]).unwrap();
+/// assert_eq!(y, Some(Shape(Dim::var("batch"), Dim::from(128))));
+///
+/// // a fixed size never matches a variable, even one that happens to have the same size
+/// let b = Shape(Dim::from(32), Dim::from(128));
+/// match infer_shape(Ops::BinaryOps(BinaryOps::ADD), &[&y.clone().unwrap(), &b]) {
+///     Err(NanogradError::SymbolicShapeMismatch { op, left, right }) => {
+///         assert_eq!(op, "ADD");
+///         assert_eq!((left, right), (y.unwrap(), b));
+///     }
+///     _ => panic!("expected a shape mismatch"),
+/// }
+/// ```
+pub fn infer_shape(op: Ops, inputs: &[&Shape]) -> Result<Option<Shape>> {
+    match op {
+        Ops::UnaryOps(_) => Ok(Some(inputs[0].clone())),
+        Ops::BinaryOps(BinaryOps::MUL) => {
+            if inputs[0].1 != inputs[1].0 {
+                return Err(mismatch(BinaryOps::MUL, inputs));
+            }
+            Ok(Some(Shape(inputs[0].0.clone(), inputs[1].1.clone())))
+        }
+        Ops::BinaryOps(op) => {
+            if inputs[0] != inputs[1] {
+                return Err(mismatch(op, inputs));
+            }
+            Ok(Some(inputs[0].clone()))
+        }
+        Ops::ReduceOps(_) => Ok(Some(Shape::from((1, 1)))),
+        _ => Ok(None),
+    }
+}
+
+/// The error for two input shapes a binary op cannot combine.
+fn mismatch(op: BinaryOps, inputs: &[&Shape]) -> NanogradError {
+    NanogradError::SymbolicShapeMismatch { op: format!("{:?}", op), left: inputs[0].clone(), right: inputs[1].clone() }
+}
@@ -8,6 +8,10 @@ use std::{
     rc::Rc,
 };
 
+use crate::graph::dot::{ DotGraph, summarize };
+#[cfg(feature = "serde")]
+use serde::{ Serialize, Serializer, Deserialize, Deserializer, ser::SerializeStruct };
+
 #[derive(Copy, Clone)]
 pub enum Operation {
     Add,
@@ -130,6 +134,45 @@ impl Value {
         }
     }
 
+    ///
+    /// Render this value and everything it was computed from as a Graphviz DOT graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nanograd::Value;
+    ///
+    /// let a = Value::from(2.0).with_label("a");
+    /// let b = &a * &Value::from(3.0);
+    /// let c = &b + &a;
+    /// c.backward();
+    ///
+    /// let dot = c.to_dot();
+    /// assert!(dot.contains("grad: [4]"));
+    /// // a feeds into b and c but is drawn once
+    /// assert_eq!(dot.matches("| a |").count(), 1);
+    /// ```
+    pub fn to_dot(&self) -> String {
+        self.dot_graph().render()
+    }
+
+    ///
+    /// Write the Graphviz DOT graph of this value to a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where to write the `.dot` file.
+    pub fn write_dot(&self, path: &str) -> std::io::Result<()> {
+        self.dot_graph().write(path)
+    }
+
+    fn dot_graph(&self) -> DotGraph {
+        let mut graph = DotGraph::new("value_graph");
+        let mut visited: HashSet<usize> = HashSet::new();
+        dot_internal(&mut graph, &mut visited, self);
+        graph
+    }
+
     pub fn trace(&self) {
         let mut visited: HashSet<Value> = HashSet::new();
         println!("Tracing value...");
@@ -140,6 +183,54 @@ impl Value {
     }
 }
 
+///
+/// Serializes the data and label of the value as a leaf: the gradient and the operations it was
+/// computed from are dropped, so a deserialized value starts a new graph.
+///
+/// # Examples
+///
+/// ```
+/// use nanograd::Value;
+///
+/// let a = Value::from(2.0).with_label("a");
+/// let json = serde_json::to_string(&a).unwrap();
+/// assert_eq!(json, r#"{"data":2.0,"label":"a"}"#);
+///
+/// let b: Value = serde_json::from_str(&json).unwrap();
+/// assert_eq!(b.data(), 2.0);
+///
+/// // a computed value is stored as its result
+/// let c = &a * &Value::from(3.0);
+/// assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"data":6.0,"label":null}"#);
+/// ```
+#[cfg(feature = "serde")]
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = self.borrow();
+        let mut state = serializer.serialize_struct("Value", 2)?;
+        state.serialize_field("data", &value.data)?;
+        state.serialize_field("label", &value.label)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "Value")]
+struct Leaf {
+    data: f64,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Leaf { data, label } = Leaf::deserialize(deserializer)?;
+        Ok(Value::new(ValueInternal::new(data, label, Operation::None, Vec::new())))
+    }
+}
+
 impl Hash for Value {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.0.borrow().hash(state);
@@ -383,6 +474,35 @@ fn backward_by_operation(val: &Ref<ValueInternal>) {
     }
 }
 
+// values are identified by the address of their shared cell
+fn dot_id(value: &Value) -> String {
+    format!("v{}", Rc::as_ptr(&value.0) as usize)
+}
+
+fn dot_internal(graph: &mut DotGraph, visited: &mut HashSet<usize>, value: &Value) {
+    if !visited.insert(Rc::as_ptr(&value.0) as usize) {
+        return;
+    }
+    let id = dot_id(value);
+    let borrowed_value = value.borrow();
+    let op_name = match borrowed_value.operation {
+        Operation::None => "Leaf".to_string(),
+        operation => format!("{:?}", operation),
+    };
+    let mut fields = vec![op_name, "1x1".to_string()];
+    if let Some(label) = &borrowed_value.label {
+        fields.push(label.clone());
+    }
+    fields.push(format!("data: {}", summarize(&[borrowed_value.data])));
+    fields.push(format!("grad: {}", summarize(&[borrowed_value.gradient])));
+    fields.push("requires_grad: true".to_string());
+    graph.add_node(&id, &fields);
+    for parent in &borrowed_value.previous {
+        dot_internal(graph, visited, parent);
+        graph.add_edge(&dot_id(parent), &id);
+    }
+}
+
 fn trace_internal(visited: &mut HashSet<Value>, value: &Value) {
     if !visited.contains(&value) {
         visited.insert(value.clone());
@@ -0,0 +1,67 @@
+"""Write the safetensors fixtures byte by byte, following the format specification rather than nanograd.
+
+Run from this directory with `python3 generate.py`. Only the standard library is needed.
+
+The layout matches the reference implementation: an 8-byte little-endian header size, a compact
+JSON header with `__metadata__` first, padded with spaces to a multiple of 8 bytes, then the data
+of each tensor, ordered by decreasing alignment and then by name.
+"""
+
+import json
+import struct
+
+# dtypes by increasing alignment, as ordered by the reference implementation
+ORDER = ["BOOL", "U8", "I8", "I16", "U16", "F16", "BF16", "I32", "U32", "F32", "F64", "I64", "U64"]
+
+
+def bf16(value):
+    # the values used here are exact in bf16, so truncating the f32 bits is exact too
+    return struct.pack("<I", struct.unpack("<I", struct.pack("<f", value))[0])[2:]
+
+
+def encode(dtype, values):
+    if dtype == "BF16":
+        return b"".join(bf16(value) for value in values)
+    code = {"F16": "e", "F32": "f", "F64": "d", "I64": "q", "U8": "B"}[dtype]
+    return struct.pack("<%d%s" % (len(values), code), *values)
+
+
+def write(path, tensors, metadata=None):
+    ordered = sorted(tensors.items(), key=lambda item: (-ORDER.index(item[1][0]), item[0]))
+    header = {}
+    if metadata:
+        header["__metadata__"] = dict(sorted(metadata.items()))
+    data = b""
+    for name, (dtype, shape, values) in ordered:
+        encoded = encode(dtype, values)
+        header[name] = {"dtype": dtype, "shape": shape, "data_offsets": [len(data), len(data) + len(encoded)]}
+        data += encoded
+    header = json.dumps(header, separators=(",", ":"), ensure_ascii=False).encode("utf-8")
+    header += b" " * (-len(header) % 8)
+    with open(path, "wb") as file:
+        file.write(struct.pack("<Q", len(header)) + header + data)
+
+
+# one tensor of every supported dtype, as `save` writes them
+write(
+    "mixed.safetensors",
+    {
+        "weight": ("F32", [2, 3], [1.0, -2.5, 3.25, 0.0, 4.0, -0.125]),
+        "scale": ("F64", [1, 2], [0.1, -2.0]),
+        "bias": ("F16", [1, 3], [0.5, -1.0, 2.0]),
+        "embedding": ("BF16", [2, 2], [1.0, -2.0, 0.25, 3.0]),
+        "indices": ("I64", [1, 3], [0, -1, 9007199254740993]),
+    },
+    {"format": "pt", "producer": "nanograd", "note": "café \"v1\""},
+)
+
+# shapes and dtypes as PyTorch saves them: a vector, a scalar, a 3-D kernel and a byte mask
+write(
+    "pytorch.safetensors",
+    {
+        "bias": ("F32", [3], [0.5, -0.5, 1.5]),
+        "count": ("I64", [], [7]),
+        "kernel": ("F32", [2, 1, 2], [1.0, 2.0, 3.0, 4.0]),
+        "mask": ("U8", [4], [1, 0, 0, 1]),
+    },
+)
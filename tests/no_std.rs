@@ -0,0 +1,46 @@
+//! Build nanograd without `std`, through the fixed-point inference crate in `tests/no_std_inference`.
+
+use std::env;
+use std::path::{ Path, PathBuf };
+use std::process::Command;
+
+/// A Cortex-M4F microcontroller, with no operating system.
+const BARE_METAL_TARGET: &str = "thumbv7em-none-eabihf";
+
+fn build(target: Option<&str>) {
+    let target_dir: PathBuf = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std");
+    let mut command = Command::new(env!("CARGO"));
+    command
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["build", "--package", "nanograd-no-std-inference"])
+        .env("CARGO_TARGET_DIR", target_dir);
+    if let Some(target) = target {
+        command.args(["--target", target]);
+    }
+    let output = command.output().expect("Failed to run cargo");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+fn is_installed(target: &str) -> bool {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    match Command::new(rustc).args(["--print", "target-libdir", "--target", target]).output() {
+        Ok(output) if output.status.success() => {
+            Path::new(String::from_utf8_lossy(&output.stdout).trim()).exists()
+        }
+        _ => false,
+    }
+}
+
+#[test]
+fn builds_without_std() {
+    build(None);
+}
+
+#[test]
+fn builds_for_bare_metal() {
+    if !is_installed(BARE_METAL_TARGET) {
+        eprintln!("skipping: install the target with `rustup target add {}`", BARE_METAL_TARGET);
+        return;
+    }
+    build(Some(BARE_METAL_TARGET));
+}
@@ -0,0 +1,28 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use nanograd::{ Tensor, nn::{ linear::Linear, activation::relu } };
+use nanograd::types::fixed::Q16_16;
+
+fn tensor(data: &[f32], dim: (usize, usize)) -> Tensor<Q16_16> {
+    let data: Vec<Q16_16> = data.iter().map(|value| Q16_16::from_f32(*value)).collect();
+    Tensor::from_vec(data, dim, None, None)
+}
+
+///
+/// Score a row of four features with a trained 4 x 2 layer and return the index of the larger score.
+///
+/// # Arguments
+///
+/// * `features` - The input row.
+/// * `weight` - The layer weight, row by row.
+/// * `bias` - The layer bias.
+pub fn classify(features: &[f32; 4], weight: &[f32; 8], bias: &[f32; 2]) -> usize {
+    let layer = Linear::from_weights(tensor(weight, (4, 2)), Some(tensor(bias, (1, 2))));
+    let scores = relu(layer.forward(tensor(features, (1, 4))));
+    let scores = scores.data();
+    if scores[1] > scores[0] { 1 } else { 0 }
+}
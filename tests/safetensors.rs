@@ -0,0 +1,153 @@
+//! Read and write the byte-exact files in `tests/fixtures/safetensors`, made by `generate.py` from the format specification.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{ Path, PathBuf };
+
+use nanograd::{ Tensor, NanogradError, f16, bf16 };
+use nanograd::io::safetensors::{ load, save, write, AnyTensor };
+use nanograd::types::dtype::DType;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/safetensors").join(name)
+}
+
+fn temporary(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+/// The tensors and metadata of `mixed.safetensors`.
+fn mixed() -> (BTreeMap<String, AnyTensor>, BTreeMap<String, String>) {
+    let weight: Tensor<f32> = Tensor::from_vec(vec![1.0, -2.5, 3.25, 0.0, 4.0, -0.125], (2, 3), None, None);
+    let scale: Tensor<f64> = Tensor::from_vec(vec![0.1, -2.0], (1, 2), None, None);
+    let bias: Tensor<f16> = Tensor::from_vec([0.5, -1.0, 2.0].map(f16::from_f32).to_vec(), (1, 3), None, None);
+    let embedding: Tensor<bf16> = Tensor::from_vec([1.0, -2.0, 0.25, 3.0].map(bf16::from_f32).to_vec(), (2, 2), None, None);
+    let indices: Tensor<i64> = Tensor::from_vec(vec![0, -1, 9007199254740993], (1, 3), None, None);
+
+    let tensors = BTreeMap::from([
+        ("weight".to_string(), weight.into()),
+        ("scale".to_string(), scale.into()),
+        ("bias".to_string(), bias.into()),
+        ("embedding".to_string(), embedding.into()),
+        ("indices".to_string(), indices.into()),
+    ]);
+    let metadata = BTreeMap::from([
+        ("format".to_string(), "pt".to_string()),
+        ("producer".to_string(), "nanograd".to_string()),
+        ("note".to_string(), "café \"v1\"".to_string()),
+    ]);
+    (tensors, metadata)
+}
+
+#[test]
+fn writes_byte_exact_files() {
+    let (tensors, metadata) = mixed();
+    let expected = fs::read(fixture("mixed.safetensors")).unwrap();
+
+    let mut buffer = Vec::new();
+    write(&tensors, &metadata, &mut buffer).unwrap();
+    assert_eq!(buffer, expected);
+
+    let path = temporary("mixed.safetensors");
+    save(&tensors, &metadata, &path).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), expected);
+}
+
+#[test]
+fn reads_every_dtype() {
+    let file = load(fixture("mixed.safetensors")).unwrap();
+    assert_eq!(file.names().collect::<Vec<_>>(), vec!["bias", "embedding", "indices", "scale", "weight"]);
+    assert_eq!(file.metadata()["note"], "café \"v1\"");
+    assert_eq!(file.metadata().len(), 3);
+
+    assert_eq!(file.dtype("weight").unwrap(), DType::F32);
+    assert_eq!(file.tensor::<f32>("weight").unwrap().dim(), (2, 3));
+    assert_eq!(file.view::<f32>("weight").unwrap().as_ref(), &[1.0, -2.5, 3.25, 0.0, 4.0, -0.125]);
+    assert_eq!(file.view::<f64>("scale").unwrap().as_ref(), &[0.1, -2.0]);
+    assert_eq!(file.view::<f16>("bias").unwrap().as_ref(), &[0.5, -1.0, 2.0].map(f16::from_f32));
+    assert_eq!(file.view::<bf16>("embedding").unwrap().as_ref(), &[1.0, -2.0, 0.25, 3.0].map(bf16::from_f32));
+    assert_eq!(file.view::<i64>("indices").unwrap().as_ref(), &[0, -1, 9007199254740993]);
+
+    // the data is aligned, so it is read straight from the mapped file
+    if cfg!(target_endian = "little") {
+        assert!(matches!(file.view::<f64>("scale").unwrap(), Cow::Borrowed(_)));
+        assert!(matches!(file.view::<i64>("indices").unwrap(), Cow::Borrowed(_)));
+    }
+}
+
+#[test]
+fn round_trips_through_load() {
+    let file = load(fixture("mixed.safetensors")).unwrap();
+    let tensors = file.tensors().unwrap();
+    assert!(matches!(tensors["embedding"], AnyTensor::BF16(_)));
+
+    let mut buffer = Vec::new();
+    write(&tensors, file.metadata(), &mut buffer).unwrap();
+    assert_eq!(buffer, fs::read(fixture("mixed.safetensors")).unwrap());
+}
+
+#[test]
+fn reads_pytorch_shapes() {
+    let file = load(fixture("pytorch.safetensors")).unwrap();
+    assert!(file.metadata().is_empty());
+
+    // vectors become a row and scalars a 1 x 1 tensor
+    let bias = file.tensor::<f32>("bias").unwrap();
+    assert_eq!(bias.dim(), (1, 3));
+    assert_eq!(bias.data().as_ref(), &[0.5, -0.5, 1.5]);
+    assert_eq!(file.shape("count").unwrap(), &[] as &[usize]);
+    assert_eq!(file.tensor::<i64>("count").unwrap().dim(), (1, 1));
+
+    // more dimensions can be viewed but not loaded as a tensor
+    assert_eq!(file.shape("kernel").unwrap(), &[2, 1, 2]);
+    assert_eq!(file.view::<f32>("kernel").unwrap().as_ref(), &[1.0, 2.0, 3.0, 4.0]);
+    assert!(matches!(file.tensor::<f32>("kernel"), Err(NanogradError::UnsupportedOp { .. })));
+
+    // other dtypes are listed but cannot be read
+    assert_eq!(file.names().count(), 4);
+    assert!(matches!(file.dtype("mask"), Err(NanogradError::Io(error)) if error.kind() == ErrorKind::InvalidData));
+}
+
+#[test]
+fn rejects_wrong_requests() {
+    let file = load(fixture("mixed.safetensors")).unwrap();
+    assert!(matches!(file.view::<f32>("missing"), Err(NanogradError::Io(error)) if error.kind() == ErrorKind::NotFound));
+    match file.view::<f32>("scale") {
+        Err(NanogradError::DTypeConversion { from, to, value }) => assert_eq!((from, to, value.as_str()), ("F64", "F32", "scale")),
+        _ => panic!("expected a dtype mismatch"),
+    }
+
+    let (mut tensors, metadata) = mixed();
+    tensors.insert("__metadata__".to_string(), Tensor::<f32>::zeros((1, 1), None, None).into());
+    assert!(write(&tensors, &metadata, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn rejects_corrupt_files() {
+    let bytes = fs::read(fixture("mixed.safetensors")).unwrap();
+    let is_invalid = |name: &str, bytes: &[u8]| {
+        let path = temporary(name);
+        fs::write(&path, bytes).unwrap();
+        matches!(load(&path), Err(NanogradError::Io(error)) if error.kind() == ErrorKind::InvalidData)
+    };
+
+    assert!(is_invalid("truncated.safetensors", &bytes[..bytes.len() - 1]));
+    assert!(is_invalid("trailing.safetensors", &[bytes.as_slice(), &[0]].concat()));
+    assert!(is_invalid("no_header.safetensors", &bytes[..4]));
+
+    let mut huge_header = bytes.clone();
+    huge_header[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(is_invalid("huge_header.safetensors", &huge_header));
+
+    let mut not_json = bytes.clone();
+    not_json[8] = b'[';
+    assert!(is_invalid("not_json.safetensors", &not_json));
+
+    // a dtype without a known size, with offsets that would overflow the end of the data
+    let mut header = format!(r#"{{"mask":{{"dtype":"I32","shape":[1],"data_offsets":[0,{}]}}}}"#, usize::MAX).into_bytes();
+    header.resize(header.len().next_multiple_of(8), b' ');
+    let overflowing = [(header.len() as u64).to_le_bytes().as_slice(), &header].concat();
+    assert!(is_invalid("overflowing.safetensors", &overflowing));
+}